@@ -0,0 +1,50 @@
+//! `tick_one_t_cycle` x 70224 against one `tick_n_cycles(70224)`, one
+//! frame's worth of T-cycles. Run with `cargo bench --bench apu`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use gb_emulator::apu::Apu;
+use std::hint::black_box;
+
+const CYCLES_PER_FRAME: u32 = 70224;
+
+/// APU with all four channels triggered and panned to both sides.
+fn playing_apu() -> Apu {
+    let mut apu = Apu::default();
+    apu.set_sample_rate(48000);
+    for (address, value) in [
+        (0xFF26, 0x80), (0xFF24, 0x77), (0xFF25, 0xFF),
+        (0xFF12, 0xF0), (0xFF13, 0x00), (0xFF14, 0x87),
+        (0xFF17, 0xF0), (0xFF18, 0x80), (0xFF19, 0x86),
+        (0xFF1A, 0x80), (0xFF1C, 0x20), (0xFF1D, 0x00), (0xFF1E, 0x87),
+        (0xFF21, 0xF0), (0xFF22, 0x21), (0xFF23, 0x80),
+    ] {
+        apu.write_register(address, value);
+    }
+    apu
+}
+
+fn apu_frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apu_frame");
+    group.bench_function("tick_one_t_cycle", |b| {
+        let mut apu = playing_apu();
+        b.iter(|| {
+            for _ in 0..CYCLES_PER_FRAME {
+                apu.tick_one_t_cycle();
+            }
+            black_box(&apu.sample_buffer);
+            apu.sample_buffer.clear();
+        });
+    });
+    group.bench_function("tick_n_cycles", |b| {
+        let mut apu = playing_apu();
+        b.iter(|| {
+            apu.tick_n_cycles(black_box(CYCLES_PER_FRAME));
+            black_box(&apu.sample_buffer);
+            apu.sample_buffer.clear();
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, apu_frame);
+criterion_main!(benches);
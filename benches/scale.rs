@@ -0,0 +1,29 @@
+//! `upscale_nearest` against `upscale_bilinear` at each window scale.
+//! Run with `cargo bench --bench scale`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use gb_emulator::filters::{upscale_bilinear, upscale_nearest};
+use std::hint::black_box;
+
+fn scale(c: &mut Criterion) {
+    // A noisy frame so neither filter benefits from flat colour runs
+    let native: Vec<u32> = (0..160 * 144u32).map(|i| i.wrapping_mul(2654435761) & 0xFFFFFF).collect();
+    let mut doubled = vec![0u32; 320 * 288];
+
+    let mut group = c.benchmark_group("scale");
+    group.bench_function("nearest 2x", |b| {
+        b.iter(|| upscale_nearest(black_box(&native), &mut doubled, 160, 144));
+    });
+    // The window path: the 2x frame is resized to the window size
+    for factor in [2, 4, 8] {
+        let (w, h) = (160 * factor, 144 * factor);
+        let mut out = vec![0u32; w * h];
+        group.bench_with_input(BenchmarkId::new("bilinear", factor), &doubled, |b, doubled| {
+            b.iter(|| upscale_bilinear(black_box(doubled), &mut out, 320, 288, w, h));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, scale);
+criterion_main!(benches);
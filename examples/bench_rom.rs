@@ -0,0 +1,71 @@
+//! Writes the reference ROM that CI benchmarks with `--benchmark`.
+//! Run with `cargo run --example bench_rom -- bench.gb`.
+//!
+//! The ROM turns on the LCD (BG only) and square channel 1, then increments
+//! every byte of WRAM bank 0 in a loop forever, so the CPU, PPU, timer and
+//! APU all stay busy every frame.
+
+const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+const ENTRY: usize = 0x0150;
+
+/// (register, value) pairs written with `LDH (n),A` at start-up
+const INIT_REGISTERS: [(u8, u8); 6] = [
+    (0x40, 0x91), // LCDC: LCD and BG on
+    (0x26, 0x80), // NR52: sound on
+    (0x24, 0x77), // NR50: full volume
+    (0x25, 0xFF), // NR51: every channel on both sides
+    (0x12, 0xF0), // NR12: channel 1 envelope at max
+    (0x14, 0x87), // NR14: trigger channel 1
+];
+
+fn program() -> Vec<u8> {
+    let mut code = vec![0x31, 0xFE, 0xFF]; // LD SP,$FFFE
+    for (reg, value) in INIT_REGISTERS {
+        code.extend_from_slice(&[0x3E, value, 0xE0, reg]); // LD A,value; LDH (reg),A
+    }
+    let outer = code.len();
+    code.extend_from_slice(&[0x21, 0x00, 0xC0]); // LD HL,$C000
+    code.extend_from_slice(&[0x01, 0x00, 0x10]); // LD BC,$1000
+    let inner = code.len();
+    // LD A,(HL); INC A; LD (HL+),A; DEC BC; LD A,B; OR C
+    code.extend_from_slice(&[0x7E, 0x3C, 0x22, 0x0B, 0x78, 0xB1]);
+    code.extend_from_slice(&[0x20, jr_offset(code.len(), inner)]); // JR NZ,inner
+    code.extend_from_slice(&[0x18, jr_offset(code.len(), outer)]); // JR outer
+    code
+}
+
+/// Displacement for a two-byte JR at `from` jumping to `to`.
+fn jr_offset(from: usize, to: usize) -> u8 {
+    (to as isize - (from as isize + 2)) as i8 as u8
+}
+
+fn build_rom() -> Vec<u8> {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100..0x0104].copy_from_slice(&[0x00, 0xC3, ENTRY as u8, (ENTRY >> 8) as u8]); // NOP; JP entry
+    rom[0x0104..0x0134].copy_from_slice(&NINTENDO_LOGO);
+    rom[0x0134..0x0139].copy_from_slice(b"BENCH");
+    // $0147-$0149 stay 0: ROM only, 32 KiB, no RAM
+    let code = program();
+    rom[ENTRY..ENTRY + code.len()].copy_from_slice(&code);
+
+    rom[0x014D] = rom[0x0134..0x014D].iter().fold(0u8, |sum, &b| sum.wrapping_sub(b).wrapping_sub(1));
+    let global = rom.iter().fold(0u16, |sum, &b| sum.wrapping_add(b as u16));
+    rom[0x014E..0x0150].copy_from_slice(&global.to_be_bytes());
+    rom
+}
+
+fn main() {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("Usage: bench_rom <out.gb>");
+        std::process::exit(1);
+    };
+    if let Err(e) = std::fs::write(&path, build_rom()) {
+        eprintln!("Failed to write {}: {}", path, e);
+        std::process::exit(1);
+    }
+}
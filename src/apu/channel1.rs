@@ -1,3 +1,5 @@
+use super::Channel;
+
 const DUTY_TABLE: [[u8; 8]; 4] = [
     [0, 0, 0, 0, 0, 0, 0, 1], // 12.5%
     [1, 0, 0, 0, 0, 0, 0, 1], // 25%
@@ -145,14 +147,6 @@ impl Channel1 {
 
     // --- Clocking ---
 
-    pub fn tick(&mut self) {
-        self.frequency_timer -= 1;
-        if self.frequency_timer <= 0 {
-            self.frequency_timer = self.period();
-            self.duty_position = (self.duty_position + 1) & 7;
-        }
-    }
-
     pub fn clock_length(&mut self) {
         if self.length_enable() && self.length_counter > 0 {
             self.length_counter -= 1;
@@ -233,27 +227,6 @@ impl Channel1 {
         ((2048 - self.frequency() as i32) * 4).max(1)
     }
 
-    // --- Power off: zero all registers ---
-    pub fn power_off(&mut self) {
-        self.nr10 = 0;
-        self.nr11 = 0;
-        self.nr12 = 0;
-        self.nr13 = 0;
-        self.nr14 = 0;
-        self.enabled = false;
-        self.dac_enabled = false;
-        self.volume = 0;
-        self.envelope_timer = 0;
-        self.envelope_running = false;
-        self.frequency_timer = 0;
-        self.duty_position = 0;
-        self.sweep_timer = 0;
-        self.sweep_enabled = false;
-        self.sweep_shadow_frequency = 0;
-        self.sweep_negate_used = false;
-        // length_counter is preserved on DMG
-    }
-
     // --- Savestate ---
 
     pub fn save_state(&self, buf: &mut Vec<u8>) {
@@ -299,6 +272,46 @@ impl Channel1 {
     }
 }
 
+impl Channel for Channel1 {
+    const FULL_LENGTH: u16 = 64;
+
+    fn tick(&mut self) {
+        self.frequency_timer -= 1;
+        if self.frequency_timer <= 0 {
+            self.frequency_timer = self.period();
+            self.duty_position = (self.duty_position + 1) & 7;
+        }
+    }
+
+    fn power_off(&mut self) {
+        self.nr10 = 0;
+        self.nr11 = 0;
+        self.nr12 = 0;
+        self.nr13 = 0;
+        self.nr14 = 0;
+        self.enabled = false;
+        self.dac_enabled = false;
+        self.volume = 0;
+        self.envelope_timer = 0;
+        self.envelope_running = false;
+        self.frequency_timer = 0;
+        self.duty_position = 0;
+        self.sweep_timer = 0;
+        self.sweep_enabled = false;
+        self.sweep_shadow_frequency = 0;
+        self.sweep_negate_used = false;
+        // length_counter is preserved on DMG
+    }
+
+    fn frequency_timer_mut(&mut self) -> &mut i32 {
+        &mut self.frequency_timer
+    }
+
+    fn length_counter_mut(&mut self) -> &mut u16 {
+        &mut self.length_counter
+    }
+}
+
 impl Default for Channel1 {
     fn default() -> Self {
         Channel1 {
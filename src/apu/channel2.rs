@@ -1,3 +1,5 @@
+use super::Channel;
+
 const DUTY_TABLE: [[u8; 8]; 4] = [
     [0, 0, 0, 0, 0, 0, 0, 1], // 12.5%
     [1, 0, 0, 0, 0, 0, 0, 1], // 25%
@@ -105,14 +107,6 @@ impl Channel2 {
 
     // --- Clocking ---
 
-    pub fn tick(&mut self) {
-        self.frequency_timer -= 1;
-        if self.frequency_timer <= 0 {
-            self.frequency_timer = self.period();
-            self.duty_position = (self.duty_position + 1) & 7;
-        }
-    }
-
     pub fn clock_length(&mut self) {
         if self.length_enable() && self.length_counter > 0 {
             self.length_counter -= 1;
@@ -156,20 +150,6 @@ impl Channel2 {
         ((2048 - self.frequency() as i32) * 4).max(1)
     }
 
-    pub fn power_off(&mut self) {
-        self.nr21 = 0;
-        self.nr22 = 0;
-        self.nr23 = 0;
-        self.nr24 = 0;
-        self.enabled = false;
-        self.dac_enabled = false;
-        self.volume = 0;
-        self.envelope_timer = 0;
-        self.envelope_running = false;
-        self.frequency_timer = 0;
-        self.duty_position = 0;
-    }
-
     // --- Savestate ---
 
     pub fn save_state(&self, buf: &mut Vec<u8>) {
@@ -205,6 +185,40 @@ impl Channel2 {
     }
 }
 
+impl Channel for Channel2 {
+    const FULL_LENGTH: u16 = 64;
+
+    fn tick(&mut self) {
+        self.frequency_timer -= 1;
+        if self.frequency_timer <= 0 {
+            self.frequency_timer = self.period();
+            self.duty_position = (self.duty_position + 1) & 7;
+        }
+    }
+
+    fn power_off(&mut self) {
+        self.nr21 = 0;
+        self.nr22 = 0;
+        self.nr23 = 0;
+        self.nr24 = 0;
+        self.enabled = false;
+        self.dac_enabled = false;
+        self.volume = 0;
+        self.envelope_timer = 0;
+        self.envelope_running = false;
+        self.frequency_timer = 0;
+        self.duty_position = 0;
+    }
+
+    fn frequency_timer_mut(&mut self) -> &mut i32 {
+        &mut self.frequency_timer
+    }
+
+    fn length_counter_mut(&mut self) -> &mut u16 {
+        &mut self.length_counter
+    }
+}
+
 impl Default for Channel2 {
     fn default() -> Self {
         Channel2 {
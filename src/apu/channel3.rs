@@ -1,3 +1,5 @@
+use super::Channel;
+
 pub struct Channel3 {
     pub enabled: bool,
     pub dac_enabled: bool,
@@ -26,6 +28,8 @@ pub struct Channel3 {
     // the frequency timer expires and wave RAM is read internally
     wave_just_read: bool,
 
+    /// CGB hardware: no wave RAM corruption on retrigger
+    pub cgb_mode: bool,
 }
 
 impl Channel3 {
@@ -74,33 +78,35 @@ impl Channel3 {
         }
 
         if triggering {
-            // DMG wave RAM corruption: retriggering while channel is active
-            // when the internal frequency timer aligns with an APU cycle boundary
-            // where the sample countdown would be 0 (SameBoy equivalent).
-            // In our T-cycle model, timer == 2 maps to SameBoy's countdown == 0.
-            if self.enabled && self.frequency_timer == 2 {
-                // Use next position's byte (position hasn't advanced yet at timer==2)
-                let offset = (((self.position_counter as usize) + 1) >> 1) & 0xF;
-                if offset < 4 {
-                    self.wave_ram[0] = self.wave_ram[offset];
-                } else {
-                    let aligned = offset & !3;
-                    let src = [
-                        self.wave_ram[aligned],
-                        self.wave_ram[aligned + 1],
-                        self.wave_ram[aligned + 2],
-                        self.wave_ram[aligned + 3],
-                    ];
-                    self.wave_ram[0] = src[0];
-                    self.wave_ram[1] = src[1];
-                    self.wave_ram[2] = src[2];
-                    self.wave_ram[3] = src[3];
-                }
+            // Only the DMG corrupts wave RAM on a retrigger. There is
+            // deliberately no CGB corruption path: CGB hardware (and SameBoy)
+            // leaves wave RAM intact. No separate `was_playing` flag either,
+            // `enabled` already says whether the channel was playing.
+            if !self.cgb_mode {
+                self.corrupt_wave_ram_on_retrigger();
             }
             self.trigger(frame_step);
         }
     }
 
+    /// DMG wave RAM corruption: retriggering while channel is active
+    /// when the internal frequency timer aligns with an APU cycle boundary
+    /// where the sample countdown would be 0 (SameBoy equivalent).
+    /// In our T-cycle model, timer == 2 maps to SameBoy's countdown == 0.
+    fn corrupt_wave_ram_on_retrigger(&mut self) {
+        if !self.enabled || self.frequency_timer != 2 {
+            return;
+        }
+        // Use next position's byte (position hasn't advanced yet at timer==2)
+        let offset = (((self.position_counter as usize) + 1) >> 1) & 0xF;
+        if offset < 4 {
+            self.wave_ram[0] = self.wave_ram[offset];
+        } else {
+            let aligned = offset & !3;
+            self.wave_ram.copy_within(aligned..aligned + 4, 0);
+        }
+    }
+
     pub fn write_length(&mut self, val: u8) {
         self.length_counter = 256 - val as u16;
     }
@@ -155,21 +161,6 @@ impl Channel3 {
 
     // --- Clocking ---
 
-    pub fn tick(&mut self) {
-        // wave_just_read is per-T-cycle (for wave RAM read/write access window)
-        self.wave_just_read = false;
-
-        self.frequency_timer -= 1;
-        if self.frequency_timer <= 0 {
-            self.frequency_timer = self.period();
-            self.position_counter = (self.position_counter + 1) & 31;
-            // Read the sample byte at the new position
-            self.sample_buffer = self.wave_ram[(self.position_counter / 2) as usize];
-            // DMG: wave RAM is accessible during this T-cycle
-            self.wave_just_read = true;
-        }
-    }
-
     pub fn clock_length(&mut self) {
         if self.length_enable() && self.length_counter > 0 {
             self.length_counter -= 1;
@@ -207,20 +198,21 @@ impl Channel3 {
         ((2048 - self.frequency() as i32) * 2).max(1)
     }
 
-    pub fn power_off(&mut self) {
-        self.nr30 = 0;
-        self.nr31 = 0;
-        self.nr32 = 0;
-        self.nr33 = 0;
-        self.nr34 = 0;
-        self.enabled = false;
-        self.dac_enabled = false;
-        self.frequency_timer = 0;
-        self.position_counter = 0;
-        self.sample_buffer = 0;
-        self.wave_just_read = false;
-        // length_counter preserved on DMG
-        // wave_ram preserved on power off
+    /// Wave RAM contents at power-on (typical DMG pattern; real units vary).
+    pub fn default_wave_ram() -> [u8; 16] {
+        [
+            0x84, 0x40, 0x43, 0xAA, 0x2D, 0x78, 0x92, 0x3C,
+            0x60, 0x59, 0x59, 0xB0, 0x34, 0xB8, 0x2E, 0xDA,
+        ]
+    }
+
+    /// Raw wave RAM snapshot, bypassing the CPU access quirks of `read_wave_ram`.
+    pub fn serialize_wave_ram(&self) -> [u8; 16] {
+        self.wave_ram
+    }
+
+    pub fn deserialize_wave_ram(&mut self, data: &[u8; 16]) {
+        self.wave_ram = *data;
     }
 
     // --- Savestate ---
@@ -261,6 +253,49 @@ impl Channel3 {
     }
 }
 
+impl Channel for Channel3 {
+    const FULL_LENGTH: u16 = 256;
+
+    fn tick(&mut self) {
+        // wave_just_read is per-T-cycle (for wave RAM read/write access window)
+        self.wave_just_read = false;
+
+        self.frequency_timer -= 1;
+        if self.frequency_timer <= 0 {
+            self.frequency_timer = self.period();
+            self.position_counter = (self.position_counter + 1) & 31;
+            // Read the sample byte at the new position
+            self.sample_buffer = self.wave_ram[(self.position_counter / 2) as usize];
+            // DMG: wave RAM is accessible during this T-cycle
+            self.wave_just_read = true;
+        }
+    }
+
+    fn power_off(&mut self) {
+        self.nr30 = 0;
+        self.nr31 = 0;
+        self.nr32 = 0;
+        self.nr33 = 0;
+        self.nr34 = 0;
+        self.enabled = false;
+        self.dac_enabled = false;
+        self.frequency_timer = 0;
+        self.position_counter = 0;
+        self.sample_buffer = 0;
+        self.wave_just_read = false;
+        // length_counter preserved on DMG
+        // wave_ram preserved on power off
+    }
+
+    fn frequency_timer_mut(&mut self) -> &mut i32 {
+        &mut self.frequency_timer
+    }
+
+    fn length_counter_mut(&mut self) -> &mut u16 {
+        &mut self.length_counter
+    }
+}
+
 impl Default for Channel3 {
     fn default() -> Self {
         Channel3 {
@@ -277,6 +312,7 @@ impl Default for Channel3 {
             position_counter: 0,
             sample_buffer: 0,
             wave_just_read: false,
+            cgb_mode: false,
         }
     }
 }
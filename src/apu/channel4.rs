@@ -1,3 +1,5 @@
+use super::Channel;
+
 const DIVISOR_TABLE: [u32; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
 
 pub struct Channel4 {
@@ -102,24 +104,6 @@ impl Channel4 {
 
     // --- Clocking ---
 
-    pub fn tick(&mut self) {
-        self.frequency_timer -= 1;
-        if self.frequency_timer <= 0 {
-            self.frequency_timer = self.period();
-
-            // LFSR clock: XOR bits 0 and 1
-            let xor_result = (self.lfsr & 0x01) ^ ((self.lfsr >> 1) & 0x01);
-            self.lfsr >>= 1;
-            self.lfsr |= xor_result << 14; // Set bit 14
-
-            if self.width_mode() {
-                // 7-bit mode: also set bit 6
-                self.lfsr &= !(1 << 6);
-                self.lfsr |= xor_result << 6;
-            }
-        }
-    }
-
     pub fn clock_length(&mut self) {
         if self.length_enable() && self.length_counter > 0 {
             self.length_counter -= 1;
@@ -166,21 +150,6 @@ impl Channel4 {
         (divisor << self.clock_shift() as u32).max(1) as i32
     }
 
-    pub fn power_off(&mut self) {
-        self.nr41 = 0;
-        self.nr42 = 0;
-        self.nr43 = 0;
-        self.nr44 = 0;
-        self.enabled = false;
-        self.dac_enabled = false;
-        self.volume = 0;
-        self.envelope_timer = 0;
-        self.envelope_running = false;
-        self.lfsr = 0;
-        self.frequency_timer = 0;
-        // length_counter preserved on DMG
-    }
-
     // --- Savestate ---
 
     pub fn save_state(&self, buf: &mut Vec<u8>) {
@@ -216,6 +185,58 @@ impl Channel4 {
     }
 }
 
+impl Channel for Channel4 {
+    const FULL_LENGTH: u16 = 64;
+
+    fn tick(&mut self) {
+        self.frequency_timer -= 1;
+        if self.frequency_timer <= 0 {
+            self.frequency_timer = self.period();
+
+            // LFSR clock: XOR bits 0 and 1
+            let xor_result = (self.lfsr & 0x01) ^ ((self.lfsr >> 1) & 0x01);
+            self.lfsr >>= 1;
+            self.lfsr |= xor_result << 14; // Set bit 14
+
+            if self.width_mode() {
+                // 7-bit mode: also set bit 6
+                self.lfsr &= !(1 << 6);
+                self.lfsr |= xor_result << 6;
+            }
+        }
+    }
+
+    fn power_off(&mut self) {
+        self.nr41 = 0;
+        self.nr42 = 0;
+        self.nr43 = 0;
+        self.nr44 = 0;
+        self.enabled = false;
+        self.dac_enabled = false;
+        self.volume = 0;
+        self.envelope_timer = 0;
+        self.envelope_running = false;
+        self.lfsr = 0;
+        self.frequency_timer = 0;
+        // length_counter preserved on DMG
+    }
+
+    /// Power-on state: `power_off` plus full length counter and seeded LFSR.
+    fn reset(&mut self) {
+        self.power_off();
+        self.length_counter = Self::FULL_LENGTH;
+        self.lfsr = 0x7FFF;
+    }
+
+    fn frequency_timer_mut(&mut self) -> &mut i32 {
+        &mut self.frequency_timer
+    }
+
+    fn length_counter_mut(&mut self) -> &mut u16 {
+        &mut self.length_counter
+    }
+}
+
 impl Default for Channel4 {
     fn default() -> Self {
         Channel4 {
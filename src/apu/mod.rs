@@ -7,6 +7,36 @@ use channel1::Channel1;
 use channel2::Channel2;
 use channel3::Channel3;
 use channel4::Channel4;
+use crate::filters::Resampler;
+
+/// Clocking and power handling shared by the four sound channels.
+pub trait Channel {
+    /// Length counter loaded at power-on
+    const FULL_LENGTH: u16;
+
+    fn tick(&mut self);
+    fn power_off(&mut self);
+    fn frequency_timer_mut(&mut self) -> &mut i32;
+    fn length_counter_mut(&mut self) -> &mut u16;
+
+    /// Same as calling `tick` `n` times.
+    fn tick_n(&mut self, mut n: u32) {
+        while n > 0 {
+            // Skip straight to the cycle the frequency timer next expires on
+            let timer = self.frequency_timer_mut();
+            let step = ((*timer).max(1) as u32).min(n);
+            *timer -= step as i32 - 1;
+            self.tick();
+            n -= step;
+        }
+    }
+
+    /// Power-on state: `power_off` plus full length counter.
+    fn reset(&mut self) {
+        self.power_off();
+        *self.length_counter_mut() = Self::FULL_LENGTH;
+    }
+}
 
 // OR masks for APU registers: unused/write-only bits read as 1
 // Indexed by (address - 0xFF10)
@@ -36,6 +66,33 @@ const OR_MASKS: [u8; 23] = [
     0x70, // 0xFF26 NR52
 ];
 
+/// T-cycles between mixed samples fed to the resamplers (262144 Hz)
+const MIX_PERIOD: u32 = 16;
+const MIX_RATE: f64 = 4_194_304.0 / MIX_PERIOD as f64;
+
+/// DC-blocking capacitor on the audio output (Pan Docs "Obscure Behavior").
+#[derive(Default)]
+pub struct HighPassFilter {
+    capacitor: f32,
+}
+
+impl HighPassFilter {
+    /// Fraction of the capacitor charge kept per output sample. The hardware
+    /// keeps 0.999958 per T-cycle, compounded over a sample's worth of cycles.
+    pub fn charge_factor(sample_rate: u32) -> f32 {
+        if sample_rate == 0 {
+            return 1.0;
+        }
+        0.999958f64.powf(4_194_304.0 / sample_rate as f64) as f32
+    }
+
+    pub fn apply(&mut self, input: f32, charge_factor: f32) -> f32 {
+        let out = input - self.capacitor;
+        self.capacitor = input - out * charge_factor;
+        out
+    }
+}
+
 pub struct Apu {
     pub channel1: Channel1,
     pub channel2: Channel2,
@@ -53,7 +110,23 @@ pub struct Apu {
     // Sample generation
     pub sample_buffer: Vec<f32>,
     pub sample_rate: u32,
+    /// T-cycles since the last mixed sample was fed to the resamplers
     sample_timer: u32,
+    resampler_left: Resampler,
+    resampler_right: Resampler,
+
+    // Output high-pass filter, one per stereo side
+    pub high_pass_enabled: bool,
+    high_pass_left: HighPassFilter,
+    high_pass_right: HighPassFilter,
+    high_pass_charge: f32,
+
+    // Debug overrides (audio debug panel); not saved in states
+    pub channel_mute: [bool; 4],
+    pub bypass_hpf: bool,
+    /// Skip the resampler's low-pass and point-sample the mix instead
+    pub bypass_lpf: bool,
+    pub master_volume: f32,
 }
 
 impl Apu {
@@ -235,21 +308,69 @@ impl Apu {
         self.channel3.tick();
         self.channel4.tick();
 
-        // Sample generation: accumulate and produce sample when threshold reached
+        // Mix at a fixed rate; the resamplers decide when an output is due
         if self.sample_rate > 0 {
-            self.sample_timer += self.sample_rate;
-            if self.sample_timer >= 4_194_304 {
-                self.sample_timer -= 4_194_304;
+            self.sample_timer += 1;
+            if self.sample_timer >= MIX_PERIOD {
+                self.sample_timer = 0;
                 self.generate_sample();
             }
         }
     }
 
+    /// Advance `n` T-cycles at once, producing the same state and samples as
+    /// `n` calls to `tick_one_t_cycle`.
+    pub fn tick_n_cycles(&mut self, mut n: u32) {
+        while n > 0 {
+            let step = if self.sample_rate > 0 {
+                // Cycles until the next mix is due
+                (MIX_PERIOD - self.sample_timer).min(n)
+            } else {
+                n
+            };
+
+            self.channel1.tick_n(step);
+            self.channel2.tick_n(step);
+            self.channel3.tick_n(step);
+            self.channel4.tick_n(step);
+
+            if self.sample_rate > 0 {
+                self.sample_timer += step;
+                if self.sample_timer >= MIX_PERIOD {
+                    self.sample_timer = 0;
+                    self.generate_sample();
+                }
+            }
+            n -= step;
+        }
+    }
+
+    /// Mix the channels and feed the result through the resamplers, pushing
+    /// a stereo pair to `sample_buffer` when an output sample is due.
     fn generate_sample(&mut self) {
-        if !self.power {
-            self.sample_buffer.push(0.0);
-            self.sample_buffer.push(0.0);
+        let (raw_left, raw_right) = self.mix();
+        let ratio = self.sample_rate as f64 / MIX_RATE;
+        let left = self.resampler_left.process(raw_left, ratio);
+        let right = self.resampler_right.process(raw_right, ratio);
+        let (Some(mut left), Some(mut right)) = (left, right) else {
             return;
+        };
+        if self.bypass_lpf {
+            (left, right) = (raw_left, raw_right);
+        }
+
+        if self.high_pass_enabled && !self.bypass_hpf {
+            left = self.high_pass_left.apply(left, self.high_pass_charge);
+            right = self.high_pass_right.apply(right, self.high_pass_charge);
+        }
+
+        self.sample_buffer.push(left * self.master_volume);
+        self.sample_buffer.push(right * self.master_volume);
+    }
+
+    fn mix(&self) -> (f32, f32) {
+        if !self.power {
+            return (0.0, 0.0);
         }
 
         let ch_outputs: [f32; 4] = [
@@ -263,6 +384,7 @@ impl Apu {
         let mut right = 0.0f32;
 
         for i in 0..4 {
+            if self.channel_mute[i] { continue; }
             if self.nr51 & (1 << (i + 4)) != 0 { left += ch_outputs[i]; }
             if self.nr51 & (1 << i) != 0 { right += ch_outputs[i]; }
         }
@@ -273,9 +395,7 @@ impl Apu {
         // Normalize: 4 channels max, 8 volume levels
         left = left * left_vol / 32.0;
         right = right * right_vol / 32.0;
-
-        self.sample_buffer.push(left);
-        self.sample_buffer.push(right);
+        (left, right)
     }
 
     fn dac_output_ch1(&self) -> f32 {
@@ -312,8 +432,33 @@ impl Apu {
         // wave_ram is preserved (handled by channel3.power_off not touching it)
     }
 
+    /// Restore all APU and channel state to power-on defaults (APU powered off).
+    /// Wave RAM and the output sample rate are kept.
+    pub fn reset(&mut self) {
+        self.power_off();
+        self.channel1.reset();
+        self.channel2.reset();
+        self.channel3.reset();
+        self.channel4.reset();
+        self.power = false;
+        self.frame_step = 0;
+        self.sample_timer = 0;
+        self.resampler_left = Resampler::new();
+        self.resampler_right = Resampler::new();
+        self.sample_buffer.clear();
+    }
+
+    pub fn serialize_wave_ram(&self) -> [u8; 16] {
+        self.channel3.serialize_wave_ram()
+    }
+
+    pub fn deserialize_wave_ram(&mut self, data: &[u8; 16]) {
+        self.channel3.deserialize_wave_ram(data);
+    }
+
     pub fn set_sample_rate(&mut self, rate: u32) {
         self.sample_rate = rate;
+        self.high_pass_charge = HighPassFilter::charge_factor(rate);
     }
 
     // --- Savestate ---
@@ -339,7 +484,9 @@ impl Apu {
         self.power = read_bool(data, cursor);
         self.frame_step = read_u8(data, cursor);
         self.sample_rate = read_u32_le(data, cursor);
-        self.sample_timer = read_u32_le(data, cursor);
+        self.high_pass_charge = HighPassFilter::charge_factor(self.sample_rate);
+        // States from before the resampler stored a rate accumulator here
+        self.sample_timer = read_u32_le(data, cursor) % MIX_PERIOD;
         self.channel1.load_state(data, cursor);
         self.channel2.load_state(data, cursor);
         self.channel3.load_state(data, cursor);
@@ -363,6 +510,19 @@ impl Default for Apu {
             sample_buffer: Vec::new(),
             sample_rate: 44100,
             sample_timer: 0,
+            resampler_left: Resampler::new(),
+            resampler_right: Resampler::new(),
+            high_pass_enabled: true,
+            high_pass_left: HighPassFilter::default(),
+            high_pass_right: HighPassFilter::default(),
+            high_pass_charge: HighPassFilter::charge_factor(44100),
+            channel_mute: [false; 4],
+            bypass_hpf: false,
+            bypass_lpf: false,
+            master_volume: 1.0,
         }
     }
 }
+
+#[cfg(test)]
+mod tests;
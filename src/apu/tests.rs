@@ -0,0 +1,331 @@
+use super::*;
+
+fn powered_apu() -> Apu {
+    let mut apu = Apu::default();
+    apu.write_register(0xFF26, 0x80);
+    apu
+}
+
+// ===============================================
+// Tests for register read-back OR masks
+// ===============================================
+#[test]
+fn test_register_readback_zero_returns_or_mask() {
+    let mut apu = powered_apu();
+    for address in 0xFF10..=0xFF25u16 {
+        let index = (address - 0xFF10) as usize;
+        apu.write_register(address, 0x00);
+        assert_eq!(apu.read_register(address), OR_MASKS[index], "register ${:04X}", address);
+    }
+}
+
+#[test]
+fn test_register_readback_ff_returns_ff() {
+    let mut apu = powered_apu();
+    for address in 0xFF10..=0xFF25u16 {
+        apu.write_register(address, 0xFF);
+        assert_eq!(apu.read_register(address), 0xFF, "register ${:04X}", address);
+    }
+}
+
+#[test]
+fn test_nr52_readback() {
+    let mut apu = powered_apu();
+    apu.write_register(0xFF26, 0x00);
+    assert_eq!(apu.read_register(0xFF26), OR_MASKS[0x16]);
+
+    // Bits 0-3 are read-only channel status, not the written value
+    apu.write_register(0xFF26, 0xFF);
+    assert_eq!(apu.read_register(0xFF26), 0x80 | OR_MASKS[0x16]);
+}
+
+#[test]
+fn test_write_only_registers_read_as_mask() {
+    let mut apu = powered_apu();
+    for address in [0xFF13u16, 0xFF18, 0xFF1D, 0xFF20] {
+        let index = (address - 0xFF10) as usize;
+        for value in [0x00, 0x5A, 0xA5, 0xFF] {
+            apu.write_register(address, value);
+            assert_eq!(apu.read_register(address), OR_MASKS[index], "register ${:04X}", address);
+        }
+    }
+}
+
+// ===============================================
+// Tests for reset
+// ===============================================
+#[test]
+fn test_reset_leaves_power_off() {
+    let mut apu = Apu::default();
+    apu.reset();
+    assert_eq!(apu.read_register(0xFF26) & 0x80, 0);
+    assert_eq!(apu.channel1.length_counter, 64);
+    assert_eq!(apu.channel3.length_counter, 256);
+}
+
+#[test]
+fn test_reset_disables_triggered_channel() {
+    let mut apu = powered_apu();
+    apu.write_register(0xFF12, 0xF0); // DAC on, volume 15
+    apu.write_register(0xFF14, 0x80); // trigger
+    assert!(apu.channel1.enabled);
+
+    apu.reset();
+    assert!(!apu.channel1.enabled);
+    assert!(!apu.power);
+    assert_eq!(apu.read_register(0xFF26), 0x70);
+}
+
+// ===============================================
+// Tests for wave RAM snapshots
+// ===============================================
+#[test]
+fn test_wave_ram_snapshot_restore() {
+    let mut apu = Apu::default();
+    apu.deserialize_wave_ram(&Channel3::default_wave_ram());
+    let snapshot = apu.serialize_wave_ram();
+    assert_eq!(snapshot, Channel3::default_wave_ram());
+
+    apu.write_register(0xFF35, 0x00);
+    assert_eq!(apu.read_register(0xFF35), 0x00);
+
+    apu.deserialize_wave_ram(&snapshot);
+    assert_eq!(apu.read_register(0xFF35), snapshot[5]);
+    assert_eq!(apu.serialize_wave_ram(), snapshot);
+}
+
+// ===============================================
+// Tests for batched ticking
+// ===============================================
+fn playing_apu() -> Apu {
+    let mut apu = powered_apu();
+    apu.write_register(0xFF24, 0x77);
+    apu.write_register(0xFF25, 0xFF);
+    apu.write_register(0xFF11, 0x80); // ch1 50% duty
+    apu.write_register(0xFF12, 0xF0);
+    apu.write_register(0xFF13, 0x73);
+    apu.write_register(0xFF14, 0x86);
+    apu.write_register(0xFF1A, 0x80); // ch3 DAC on
+    apu.write_register(0xFF1C, 0x20);
+    apu.write_register(0xFF1D, 0x10);
+    apu.write_register(0xFF1E, 0x87);
+    apu.write_register(0xFF21, 0xF0); // ch4 noise
+    apu.write_register(0xFF22, 0x21);
+    apu.write_register(0xFF23, 0x80);
+    apu
+}
+
+#[test]
+fn test_tick_n_cycles_one_second_of_samples() {
+    let mut apu = powered_apu();
+    apu.tick_n_cycles(4_194_304);
+    assert_eq!(apu.sample_buffer.len(), apu.sample_rate as usize * 2);
+}
+
+#[test]
+fn test_tick_n_cycles_matches_single_ticks() {
+    let mut batched = playing_apu();
+    let mut single = playing_apu();
+
+    batched.tick_n_cycles(70224);
+    for _ in 0..70224 {
+        single.tick_one_t_cycle();
+    }
+
+    assert!(!single.sample_buffer.is_empty());
+    assert_eq!(batched.sample_buffer, single.sample_buffer);
+    assert_eq!(batched.serialize_wave_ram(), single.serialize_wave_ram());
+}
+
+// ===============================================
+// Tests for channel 1 sweep overflow
+// ===============================================
+fn trigger_ch1(apu: &mut Apu, nr10: u8, freq: u16) {
+    apu.write_register(0xFF10, nr10);
+    apu.write_register(0xFF11, 0x80);
+    apu.write_register(0xFF12, 0xF0); // DAC on
+    apu.write_register(0xFF13, (freq & 0xFF) as u8);
+    apu.write_register(0xFF14, 0x80 | (freq >> 8) as u8);
+}
+
+#[test]
+fn test_sweep_overflow_on_trigger_disables_channel() {
+    let mut apu = powered_apu();
+    trigger_ch1(&mut apu, 0x11, 0x7FF); // period 1, add, shift 1
+    assert_eq!(apu.read_register(0xFF26) & 0x01, 0);
+    assert!(apu.channel1.dac_enabled, "overflow leaves the DAC on");
+}
+
+#[test]
+fn test_sweep_overflow_on_second_check() {
+    let mut apu = powered_apu();
+    trigger_ch1(&mut apu, 0x11, 1280); // 1280 + 640 fits
+    assert_eq!(apu.read_register(0xFF26) & 0x01, 0x01);
+
+    // Writes 1920 back, then 1920 + 960 overflows
+    apu.channel1.clock_sweep();
+    assert_eq!(apu.read_register(0xFF26) & 0x01, 0);
+    assert_eq!(apu.channel1.nr13 as u16 | ((apu.channel1.nr14 as u16 & 0x07) << 8), 1920);
+}
+
+#[test]
+fn test_sweep_negate_then_add_disables_channel() {
+    let mut apu = powered_apu();
+    trigger_ch1(&mut apu, 0x19, 1024); // negate used in the trigger calculation
+    assert_eq!(apu.read_register(0xFF26) & 0x01, 0x01);
+    apu.write_register(0xFF10, 0x11);
+    assert_eq!(apu.read_register(0xFF26) & 0x01, 0);
+}
+
+// ===============================================
+// Tests for wave RAM reads during playback
+// ===============================================
+#[test]
+fn test_wave_ram_read_while_playing_returns_current_byte() {
+    let mut apu = powered_apu();
+    for i in 0..16u16 {
+        apu.write_register(0xFF30 + i, 0x10 + i as u8);
+    }
+    assert_eq!(apu.read_register(0xFF35), 0x15, "stopped channel reads stored bytes");
+
+    apu.write_register(0xFF1A, 0x80);
+    apu.write_register(0xFF1D, 0xFE);
+    apu.write_register(0xFF1E, 0x87); // trigger, frequency 0x7FE
+
+    let mut reads = Vec::new();
+    for _ in 0..64 {
+        apu.channel3.tick();
+        let byte = apu.read_register(0xFF3F);
+        if byte != 0xFF {
+            // Any offset reads the byte being played
+            assert_eq!(apu.read_register(0xFF30), byte);
+            reads.push(byte);
+        }
+    }
+    assert_eq!(reads[..5], [0x10, 0x11, 0x11, 0x12, 0x12]);
+}
+
+/// Retrigger channel 3 at position 9 just as it is about to read wave RAM.
+fn retrigger_mid_wave(cgb_mode: bool) -> [u8; 16] {
+    let mut apu = powered_apu();
+    apu.channel3.cgb_mode = cgb_mode;
+    for i in 0..16u16 {
+        apu.write_register(0xFF30 + i, 0x10 + i as u8);
+    }
+    apu.write_register(0xFF1A, 0x80);
+    apu.write_register(0xFF1D, 0xFE);
+    apu.write_register(0xFF1E, 0x87); // trigger, frequency 0x7FE (period 4)
+    for _ in 0..44 {
+        apu.channel3.tick();
+    }
+    apu.write_register(0xFF1E, 0x87);
+    apu.channel3.wave_ram
+}
+
+#[test]
+fn test_dmg_retrigger_corrupts_wave_ram() {
+    let wave = retrigger_mid_wave(false);
+    assert_eq!(wave[..4], [0x14, 0x15, 0x16, 0x17], "aligned block copied to the start");
+    assert_eq!(wave[4], 0x14);
+}
+
+#[test]
+fn test_cgb_retrigger_keeps_wave_ram() {
+    let wave = retrigger_mid_wave(true);
+    assert_eq!(wave[..4], [0x10, 0x11, 0x12, 0x13]);
+}
+
+// ===============================================
+// Tests for noise channel LFSR width
+// ===============================================
+/// Channel 4 output after each of `clocks` LFSR clocks (divisor code 0 = 8 T-cycles).
+fn noise_sequence(nr43: u8, clocks: usize) -> Vec<u8> {
+    let mut apu = powered_apu();
+    apu.write_register(0xFF21, 0xF0);
+    apu.write_register(0xFF22, nr43);
+    apu.write_register(0xFF23, 0x80);
+    (0..clocks)
+        .map(|_| {
+            for _ in 0..8 {
+                apu.channel4.tick();
+            }
+            apu.channel4.output()
+        })
+        .collect()
+}
+
+#[test]
+fn test_noise_7bit_lfsr_period_is_127() {
+    let seq = noise_sequence(0x08, 127 * 3);
+    assert!(seq.contains(&0) && seq.contains(&15));
+    // 127 is prime, so a repeat at 127 with a non-constant output is the full period
+    for i in 0..127 * 2 {
+        assert_eq!(seq[i], seq[i + 127], "clock {}", i);
+    }
+
+    let wide = noise_sequence(0x00, 127 * 2);
+    assert!((0..127).any(|i| wide[i] != wide[i + 127]), "15-bit mode should not repeat at 127");
+}
+
+// ===============================================
+// Tests for the output high-pass filter
+// ===============================================
+#[test]
+fn test_high_pass_filter_decays_dc() {
+    let charge = HighPassFilter::charge_factor(44100);
+    assert!((charge - 0.996).abs() < 0.001, "charge factor {}", charge);
+    assert_eq!(HighPassFilter::charge_factor(0), 1.0);
+
+    let mut filter = HighPassFilter::default();
+    let first = filter.apply(1.0, charge);
+    assert_eq!(first, 1.0, "a step passes through at first");
+    let mut out = first;
+    for _ in 0..44100 {
+        out = filter.apply(1.0, charge);
+    }
+    assert!(out.abs() < 0.001, "DC should decay away, got {}", out);
+}
+
+#[test]
+fn test_high_pass_filter_can_be_disabled() {
+    // Channel 3 DAC on with silent wave RAM: a constant DC level
+    let dc_apu = |high_pass: bool| {
+        let mut apu = powered_apu();
+        apu.high_pass_enabled = high_pass;
+        for address in 0xFF30..=0xFF3Fu16 {
+            apu.write_register(address, 0x00);
+        }
+        apu.write_register(0xFF24, 0x77);
+        apu.write_register(0xFF25, 0x44);
+        apu.write_register(0xFF1A, 0x80);
+        apu.write_register(0xFF1C, 0x20);
+        apu.write_register(0xFF1E, 0x80);
+        apu.tick_n_cycles(4_194_304 / 10);
+        *apu.sample_buffer.last().unwrap()
+    };
+    assert!(dc_apu(false) < -0.2);
+    assert!(dc_apu(true).abs() < 0.01);
+}
+
+#[test]
+fn test_debug_mute_and_filter_bypass() {
+    // Channel 3 holding a constant DC level, as above
+    let dc_apu = |setup: &dyn Fn(&mut Apu)| {
+        let mut apu = powered_apu();
+        setup(&mut apu);
+        for address in 0xFF30..=0xFF3Fu16 {
+            apu.write_register(address, 0x00);
+        }
+        apu.write_register(0xFF24, 0x77);
+        apu.write_register(0xFF25, 0x44);
+        apu.write_register(0xFF1A, 0x80);
+        apu.write_register(0xFF1C, 0x20);
+        apu.write_register(0xFF1E, 0x80);
+        apu.tick_n_cycles(4_194_304 / 10);
+        *apu.sample_buffer.last().unwrap()
+    };
+    assert!(dc_apu(&|apu| apu.bypass_hpf = true) < -0.2);
+    assert_eq!(dc_apu(&|apu| { apu.bypass_hpf = true; apu.channel_mute[2] = true; }), 0.0);
+    let half = dc_apu(&|apu| { apu.bypass_hpf = true; apu.master_volume = 0.5; });
+    assert!((half - dc_apu(&|apu| apu.bypass_hpf = true) / 2.0).abs() < 1e-6);
+}
@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Frames per latency measurement window
+const LATENCY_WINDOW: u32 = 60;
+/// Target change after a window with underruns, in frames
+const LATENCY_STEP_UP: f32 = 0.5;
+/// Target change after a window with slack to spare, in frames
+const LATENCY_STEP_DOWN: f32 = 0.25;
+const INITIAL_LATENCY_FRAMES: f32 = 2.0;
+
+/// Host audio queue shared with the cpal output callback (interleaved stereo).
+pub struct AudioState {
+    pub buffer: Arc<Mutex<VecDeque<f32>>>,
+    pub sample_rate: u32,
+    /// Times the output callback ran the queue dry part-way through
+    pub underruns: Arc<AtomicU32>,
+}
+
+impl AudioState {
+    pub fn new(sample_rate: u32) -> Self {
+        AudioState {
+            buffer: Arc::new(Mutex::new(VecDeque::new())),
+            sample_rate,
+            underruns: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    pub fn underrun_count(&self) -> u32 {
+        self.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Queued audio not yet played, in ~60 Hz frames.
+    pub fn audio_latency_frames(&self) -> f32 {
+        let samples_per_frame = self.sample_rate as f32 / 60.0;
+        if samples_per_frame == 0.0 {
+            return 0.0;
+        }
+        let queued = self.buffer.lock().map_or(0, |b| b.len());
+        (queued / 2) as f32 / samples_per_frame
+    }
+}
+
+/// Tunes how much audio is kept queued, RetroArch style: every 60 frames
+/// the target grows if the device underran and shrinks if the queue never
+/// ran low, staying within the configured range.
+pub struct LatencyTracker {
+    /// Most audio kept queued, in ~60 Hz frames
+    pub target_frames: f32,
+    /// Samples produced minus samples played over the last window, in frames
+    pub drift_frames: f32,
+    min_frames: f32,
+    max_frames: f32,
+    samples_per_frame: f32,
+    frames: u32,
+    produced: usize,
+    consumed: usize,
+    /// Queue length left after the previous frame's trim
+    last_queued: Option<usize>,
+    /// Lowest the queue got (just before new samples arrived) this window
+    lowest_queued: usize,
+    last_underruns: u32,
+}
+
+impl LatencyTracker {
+    pub fn new(sample_rate: u32, min_frames: f32, max_frames: f32) -> Self {
+        LatencyTracker {
+            target_frames: INITIAL_LATENCY_FRAMES.clamp(min_frames, max_frames),
+            drift_frames: 0.0,
+            min_frames,
+            max_frames,
+            // Interleaved stereo
+            samples_per_frame: sample_rate as f32 * 2.0 / 60.0,
+            frames: 0,
+            produced: 0,
+            consumed: 0,
+            last_queued: None,
+            lowest_queued: usize::MAX,
+            last_underruns: 0,
+        }
+    }
+
+    /// Record one emulated frame: `queued` samples were still waiting when
+    /// `produced` new ones arrived, and the device has underrun `underruns`
+    /// times in total. Returns how many samples the queue may keep.
+    pub fn record_frame(&mut self, queued: usize, produced: usize, underruns: u32) -> usize {
+        if let Some(last) = self.last_queued {
+            self.consumed += last.saturating_sub(queued);
+            self.produced += produced;
+        }
+        self.lowest_queued = self.lowest_queued.min(queued);
+        self.frames += 1;
+
+        if self.frames >= LATENCY_WINDOW {
+            self.drift_frames = (self.produced as f32 - self.consumed as f32) / self.samples_per_frame;
+            if underruns != self.last_underruns {
+                self.target_frames += LATENCY_STEP_UP;
+            } else if self.lowest_queued as f32 > self.samples_per_frame {
+                // Never closer than a frame to running dry: latency to spare
+                self.target_frames -= LATENCY_STEP_DOWN;
+            }
+            self.target_frames = self.target_frames.clamp(self.min_frames, self.max_frames);
+            self.last_underruns = underruns;
+            self.frames = 0;
+            self.produced = 0;
+            self.consumed = 0;
+            self.lowest_queued = usize::MAX;
+        }
+
+        let cap = self.max_queued();
+        self.last_queued = Some((queued + produced).min(cap));
+        cap
+    }
+
+    /// The target in samples, rounded down to whole stereo pairs.
+    pub fn max_queued(&self) -> usize {
+        (self.target_frames * self.samples_per_frame) as usize & !1
+    }
+
+    /// Forget the queue level after it was cleared (pause, rewind, fast-forward).
+    pub fn reset_queue(&mut self) {
+        self.last_queued = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_latency_frames() {
+        let audio = AudioState::new(44100);
+        assert_eq!(audio.audio_latency_frames(), 0.0);
+
+        // 2 frames of stereo audio: 2 * 735 sample pairs
+        audio.buffer.lock().unwrap().extend(std::iter::repeat_n(0.0, 2 * 735 * 2));
+        assert!((audio.audio_latency_frames() - 2.0).abs() < 0.01);
+
+        assert_eq!(AudioState::new(0).audio_latency_frames(), 0.0);
+    }
+
+    #[test]
+    fn test_latency_tracker_adapts_within_range() {
+        // 48 kHz: 1600 interleaved samples per frame
+        let mut tracker = LatencyTracker::new(48000, 0.5, 8.0);
+        assert_eq!(tracker.max_queued(), 3200);
+
+        // An underrun in the window raises the target
+        for _ in 0..LATENCY_WINDOW {
+            tracker.record_frame(0, 1600, 1);
+        }
+        assert_eq!(tracker.target_frames, 2.5);
+
+        // A steady queue of ~2 frames has slack, so the target comes back down
+        for _ in 0..LATENCY_WINDOW * 20 {
+            tracker.record_frame(3200, 1600, 1);
+        }
+        assert_eq!(tracker.target_frames, 0.5, "clamped to the minimum");
+        assert_eq!(tracker.record_frame(3200, 1600, 1), 800);
+
+        for underruns in 2..LATENCY_WINDOW * 40 {
+            tracker.record_frame(0, 1600, underruns);
+        }
+        assert_eq!(tracker.target_frames, 8.0, "clamped to the maximum");
+    }
+
+    #[test]
+    fn test_latency_tracker_measures_drift() {
+        let mut tracker = LatencyTracker::new(48000, 0.5, 8.0);
+        // The device plays 1500 of every 1600 samples produced
+        let mut queued = 1600;
+        for _ in 0..LATENCY_WINDOW {
+            queued = tracker.record_frame(queued, 1600, 0).min(queued + 1600) - 1500;
+        }
+        // 59 measured frames: the first only sets the baseline
+        assert!((tracker.drift_frames - 59.0 * 100.0 / 1600.0).abs() < 0.01, "{}", tracker.drift_frames);
+    }
+}
@@ -0,0 +1,116 @@
+#[derive(Default)]
+pub struct Benchmark {
+    pub frame_times: Vec<u64>,
+}
+
+/// Frame time statistics, all in nanoseconds.
+pub struct BenchmarkReport {
+    pub frames: usize,
+    pub mean: u64,
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+    pub min: u64,
+    pub max: u64,
+    pub total: u64,
+}
+
+/// One histogram bar: frames that took `start..end` ns.
+#[derive(Debug, PartialEq)]
+pub struct HistogramBucket {
+    pub start: u64,
+    pub end: u64,
+    pub count: usize,
+}
+
+impl Benchmark {
+    pub fn new() -> Self {
+        Benchmark { frame_times: Vec::new() }
+    }
+
+    pub fn add(&mut self, ns: u64) {
+        self.frame_times.push(ns);
+    }
+
+    pub fn report(&self) -> BenchmarkReport {
+        let mut sorted = self.frame_times.clone();
+        sorted.sort_unstable();
+        let total: u64 = sorted.iter().sum();
+        let mean = if sorted.is_empty() { 0 } else { total / sorted.len() as u64 };
+        BenchmarkReport {
+            frames: sorted.len(),
+            mean,
+            p50: percentile(&sorted, 50),
+            p95: percentile(&sorted, 95),
+            p99: percentile(&sorted, 99),
+            min: sorted.first().copied().unwrap_or(0),
+            max: sorted.last().copied().unwrap_or(0),
+            total,
+        }
+    }
+
+    /// Frame times split into `buckets` equal ranges from the fastest to
+    /// the slowest frame (the last range includes the slowest).
+    pub fn histogram(&self, buckets: usize) -> Vec<HistogramBucket> {
+        let (Some(&min), Some(&max)) = (self.frame_times.iter().min(), self.frame_times.iter().max()) else {
+            return Vec::new();
+        };
+        let buckets = buckets.max(1);
+        let width = (max - min).div_ceil(buckets as u64).max(1);
+        let mut histogram: Vec<HistogramBucket> = (0..buckets as u64)
+            .map(|i| HistogramBucket { start: min + i * width, end: min + (i + 1) * width, count: 0 })
+            .collect();
+        for &ns in &self.frame_times {
+            let idx = (((ns - min) / width) as usize).min(buckets - 1);
+            histogram[idx].count += 1;
+        }
+        histogram
+    }
+}
+
+// Linear interpolation between the two closest ranks of a sorted slice
+fn percentile(sorted: &[u64], pct: u64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let scaled = pct * (sorted.len() as u64 - 1);
+    let lo = (scaled / 100) as usize;
+    let frac = scaled % 100;
+    if frac == 0 || lo + 1 >= sorted.len() {
+        return sorted[lo];
+    }
+    sorted[lo] + (sorted[lo + 1] - sorted[lo]) * frac / 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_percentiles() {
+        let mut bench = Benchmark::new();
+        for i in 1..=100 {
+            bench.add(i * 1000);
+        }
+        let report = bench.report();
+        assert_eq!(report.frames, 100);
+        assert_eq!(report.p50, 50500);
+        assert_eq!(report.mean, 50500);
+        assert_eq!(report.total, 5_050_000);
+        assert!(report.p95 > report.p50 && report.p99 > report.p95);
+        assert_eq!((report.min, report.max), (1000, 100_000));
+    }
+
+    #[test]
+    fn test_histogram_buckets() {
+        let mut bench = Benchmark::new();
+        assert!(bench.histogram(4).is_empty());
+        for ns in [100, 110, 120, 190, 200, 500] {
+            bench.add(ns);
+        }
+        let histogram = bench.histogram(4);
+        assert_eq!(histogram.len(), 4);
+        assert_eq!(histogram[0], HistogramBucket { start: 100, end: 200, count: 4 });
+        assert_eq!(histogram.iter().map(|b| b.count).collect::<Vec<_>>(), vec![4, 1, 0, 1]);
+    }
+}
@@ -0,0 +1,292 @@
+use std::fmt;
+use crate::cpu::CPU;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegisterId {
+    A, F, B, C, D, E, H, L,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlagId {
+    Z, N, H, C,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakpointCondition {
+    PcEquals(u16),
+    MemoryEquals(u16, u8),
+    RegisterEquals(RegisterId, u8),
+    FlagSet(FlagId),
+}
+
+/// A breakpoint that fires when `condition` holds, optionally only while
+/// PC is at `pc`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConditionalBreakpoint {
+    pub pc: Option<u16>,
+    pub condition: BreakpointCondition,
+    /// Result of the last `check`, so a condition that stays true does not
+    /// break again straight after resuming
+    pub was_hit: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+/// Halts emulation when the CPU reads and/or writes an address in
+/// `start..=end`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub start: u16,
+    pub end: u16,
+    pub kind: WatchKind,
+}
+
+impl Watchpoint {
+    pub fn matches(&self, address: u16, is_write: bool) -> bool {
+        let kind_matches = match self.kind {
+            WatchKind::Read => !is_write,
+            WatchKind::Write => is_write,
+            WatchKind::ReadWrite => true,
+        };
+        kind_matches && (self.start..=self.end).contains(&address)
+    }
+
+    /// Parse `R XXXX`, `W XXXX-YYYY` or `RW XXXX[-YYYY]` (hex addresses).
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim().to_ascii_uppercase();
+        let (kind, range) = text.split_once(' ')
+            .ok_or_else(|| format!("Invalid watchpoint '{}'", text))?;
+        let kind = match kind {
+            "R" => WatchKind::Read,
+            "W" => WatchKind::Write,
+            "RW" => WatchKind::ReadWrite,
+            _ => return Err(format!("Unknown watch kind '{}'", kind)),
+        };
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (parse_hex_u16(start)?, parse_hex_u16(end)?),
+            None => {
+                let addr = parse_hex_u16(range)?;
+                (addr, addr)
+            }
+        };
+        if end < start {
+            return Err(format!("Empty watch range ${:04X}-${:04X}", start, end));
+        }
+        Ok(Watchpoint { start, end, kind })
+    }
+}
+
+impl BreakpointCondition {
+    pub fn is_met(&self, cpu: &CPU) -> bool {
+        match *self {
+            BreakpointCondition::PcEquals(addr) => cpu.pc == addr,
+            BreakpointCondition::MemoryEquals(addr, value) => cpu.bus.read_byte_no_tick(addr) == value,
+            BreakpointCondition::RegisterEquals(reg, value) => {
+                let r = &cpu.registers;
+                let current = match reg {
+                    RegisterId::A => r.a,
+                    RegisterId::F => u8::from(r.f.clone()),
+                    RegisterId::B => r.b,
+                    RegisterId::C => r.c,
+                    RegisterId::D => r.d,
+                    RegisterId::E => r.e,
+                    RegisterId::H => r.h,
+                    RegisterId::L => r.l,
+                };
+                current == value
+            }
+            BreakpointCondition::FlagSet(flag) => {
+                let f = &cpu.registers.f;
+                match flag {
+                    FlagId::Z => f.zero,
+                    FlagId::N => f.subtract,
+                    FlagId::H => f.half_carry,
+                    FlagId::C => f.carry,
+                }
+            }
+        }
+    }
+
+    /// Parse a single term: `PC==0150`, `[FF44]==90`, `A==12` or a bare
+    /// flag name (`Z`, `N`, `H`, `C`). Numbers are hex, with an optional
+    /// `0x` or `$` prefix.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim().to_ascii_uppercase();
+        if let Some((lhs, rhs)) = text.split_once("==") {
+            let lhs = lhs.trim();
+            let rhs = rhs.trim();
+            if lhs == "PC" {
+                return Ok(BreakpointCondition::PcEquals(parse_hex_u16(rhs)?));
+            }
+            if let Some(addr) = lhs.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                return Ok(BreakpointCondition::MemoryEquals(parse_hex_u16(addr)?, parse_hex_u8(rhs)?));
+            }
+            let reg = match lhs {
+                "A" => RegisterId::A,
+                "F" => RegisterId::F,
+                "B" => RegisterId::B,
+                "C" => RegisterId::C,
+                "D" => RegisterId::D,
+                "E" => RegisterId::E,
+                "H" => RegisterId::H,
+                "L" => RegisterId::L,
+                _ => return Err(format!("Unknown operand '{}'", lhs)),
+            };
+            return Ok(BreakpointCondition::RegisterEquals(reg, parse_hex_u8(rhs)?));
+        }
+        match text.as_str() {
+            "Z" => Ok(BreakpointCondition::FlagSet(FlagId::Z)),
+            "N" => Ok(BreakpointCondition::FlagSet(FlagId::N)),
+            "H" => Ok(BreakpointCondition::FlagSet(FlagId::H)),
+            "C" => Ok(BreakpointCondition::FlagSet(FlagId::C)),
+            _ => Err(format!("Invalid condition '{}'", text)),
+        }
+    }
+}
+
+impl ConditionalBreakpoint {
+    pub fn is_hit(&self, cpu: &CPU) -> bool {
+        self.pc.is_none_or(|pc| cpu.pc == pc) && self.condition.is_met(cpu)
+    }
+
+    /// True only when the breakpoint goes from not hit to hit.
+    pub fn check(&mut self, cpu: &CPU) -> bool {
+        let hit = self.is_hit(cpu);
+        let edge = hit && !self.was_hit;
+        self.was_hit = hit;
+        edge
+    }
+
+    /// Parse `COND` or `PC==XXXX && COND`. In the second form the PC term
+    /// restricts where the condition is checked.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Some((first, second)) = text.split_once("&&") {
+            let pc = match BreakpointCondition::parse(first)? {
+                BreakpointCondition::PcEquals(addr) => addr,
+                _ => return Err("Only 'PC==XXXX && COND' can be combined".to_string()),
+            };
+            let condition = BreakpointCondition::parse(second)?;
+            return Ok(ConditionalBreakpoint { pc: Some(pc), condition, was_hit: false });
+        }
+        Ok(ConditionalBreakpoint { pc: None, condition: BreakpointCondition::parse(text)?, was_hit: false })
+    }
+}
+
+impl fmt::Display for BreakpointCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BreakpointCondition::PcEquals(addr) => write!(f, "PC==${:04X}", addr),
+            BreakpointCondition::MemoryEquals(addr, value) => write!(f, "[${:04X}]==${:02X}", addr, value),
+            BreakpointCondition::RegisterEquals(reg, value) => write!(f, "{:?}==${:02X}", reg, value),
+            BreakpointCondition::FlagSet(flag) => write!(f, "{:?}", flag),
+        }
+    }
+}
+
+impl fmt::Display for ConditionalBreakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.pc {
+            Some(pc) => write!(f, "PC==${:04X} && {}", pc, self.condition),
+            None => write!(f, "{}", self.condition),
+        }
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            WatchKind::Read => "R",
+            WatchKind::Write => "W",
+            WatchKind::ReadWrite => "RW",
+        };
+        if self.start == self.end {
+            write!(f, "{} ${:04X}", kind, self.start)
+        } else {
+            write!(f, "{} ${:04X}-${:04X}", kind, self.start, self.end)
+        }
+    }
+}
+
+fn strip_hex_prefix(text: &str) -> &str {
+    text.strip_prefix("0X").or_else(|| text.strip_prefix('$')).unwrap_or(text)
+}
+
+fn parse_hex_u16(text: &str) -> Result<u16, String> {
+    u16::from_str_radix(strip_hex_prefix(text.trim()), 16)
+        .map_err(|_| format!("Invalid address '{}'", text.trim()))
+}
+
+fn parse_hex_u8(text: &str) -> Result<u8, String> {
+    u8::from_str_radix(strip_hex_prefix(text.trim()), 16)
+        .map_err(|_| format!("Invalid value '{}'", text.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conditions() {
+        assert_eq!(BreakpointCondition::parse("PC==0x150"), Ok(BreakpointCondition::PcEquals(0x0150)));
+        assert_eq!(BreakpointCondition::parse("[0xFF44]==0x90"), Ok(BreakpointCondition::MemoryEquals(0xFF44, 0x90)));
+        assert_eq!(BreakpointCondition::parse("a==$3f"), Ok(BreakpointCondition::RegisterEquals(RegisterId::A, 0x3F)));
+        assert_eq!(BreakpointCondition::parse("C"), Ok(BreakpointCondition::FlagSet(FlagId::C)));
+        assert!(BreakpointCondition::parse("SP==0").is_err());
+        assert!(BreakpointCondition::parse("[FF44]==100").is_err());
+        assert!(BreakpointCondition::parse("").is_err());
+    }
+
+    #[test]
+    fn test_parse_pc_gated() {
+        let bp = ConditionalBreakpoint::parse("PC==0x0150 && [C000]==01").unwrap();
+        assert_eq!(bp.pc, Some(0x0150));
+        assert_eq!(bp.condition, BreakpointCondition::MemoryEquals(0xC000, 0x01));
+        assert_eq!(bp.to_string(), "PC==$0150 && [$C000]==$01");
+        assert!(ConditionalBreakpoint::parse("A==1 && B==2").is_err());
+    }
+
+    #[test]
+    fn test_conditions_evaluate_against_cpu() {
+        let mut cpu = CPU::default();
+        cpu.pc = 0x0200;
+        cpu.registers.b = 0x42;
+        cpu.registers.f.zero = true;
+        cpu.bus.write_byte(0xC000, 0x07);
+
+        assert!(ConditionalBreakpoint::parse("PC==0200").unwrap().is_hit(&cpu));
+        assert!(ConditionalBreakpoint::parse("B==42").unwrap().is_hit(&cpu));
+        assert!(ConditionalBreakpoint::parse("Z").unwrap().is_hit(&cpu));
+        assert!(!ConditionalBreakpoint::parse("C").unwrap().is_hit(&cpu));
+        assert!(ConditionalBreakpoint::parse("[C000]==07").unwrap().is_hit(&cpu));
+        assert!(!ConditionalBreakpoint::parse("PC==0100 && B==42").unwrap().is_hit(&cpu));
+    }
+
+    #[test]
+    fn test_parse_watchpoints() {
+        let wp = Watchpoint::parse("w ff40-ff4b").unwrap();
+        assert_eq!(wp, Watchpoint { start: 0xFF40, end: 0xFF4B, kind: WatchKind::Write });
+        assert_eq!(wp.to_string(), "W $FF40-$FF4B");
+        assert_eq!(Watchpoint::parse("RW $C000").unwrap().to_string(), "RW $C000");
+        assert!(Watchpoint::parse("X C000").is_err());
+        assert!(Watchpoint::parse("R D000-C000").is_err());
+
+        assert!(wp.matches(0xFF44, true));
+        assert!(!wp.matches(0xFF44, false));
+        assert!(!wp.matches(0xFF4C, true));
+    }
+
+    #[test]
+    fn test_watchpoint_hit_on_bus_access() {
+        let mut cpu = CPU::default();
+        cpu.bus.watchpoints.push(Watchpoint::parse("R C010").unwrap());
+        cpu.bus.write_byte(0xC010, 0x12);
+        assert_eq!(cpu.bus.watchpoint_hit, None, "writes do not trigger a read watchpoint");
+        cpu.bus.read_byte(0xC010);
+        assert_eq!(cpu.bus.watchpoint_hit, Some(cpu.bus.watchpoints[0]));
+    }
+}
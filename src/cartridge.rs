@@ -1,5 +1,11 @@
+use std::collections::HashMap;
+#[cfg(feature = "native")]
 use std::fs;
+#[cfg(feature = "native")]
+use std::io::Read;
+#[cfg(feature = "native")]
 use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
 use std::time::SystemTime;
 
 enum Mbc {
@@ -10,6 +16,10 @@ enum Mbc {
         ram_enabled: bool,
         banking_mode: bool,
     },
+    Mbc2 {
+        rom_bank: u8,
+        ram_enabled: bool,
+    },
     Mbc3 {
         rom_bank: u8,
         ram_bank: u8,
@@ -22,9 +32,20 @@ enum Mbc {
         ram_bank: u8,
         ram_enabled: bool,
     },
+    // Two 8 KB ROM windows ($4000/$6000) and two 4 KB RAM windows ($A000/$B000),
+    // index 0 = bank A, 1 = bank B
+    Mbc6 {
+        rom_bank: [u8; 2],
+        rom_flash: [bool; 2],
+        ram_bank: [u8; 2],
+        ram_enabled: bool,
+        flash_enabled: bool,
+        flash_write_enabled: bool,
+    },
 }
 
-struct Rtc {
+/// MBC3 real-time clock.
+pub struct Rtc {
     seconds: u8,
     minutes: u8,
     hours: u8,
@@ -34,12 +55,129 @@ struct Rtc {
     base_timestamp: u64,
 }
 
+/// Wall-clock seconds for the RTC. wasm32-unknown-unknown has no
+/// `SystemTime`, so the browser build asks JavaScript instead; other
+/// wasm32 builds see a stopped clock at 0.
+#[cfg(not(target_arch = "wasm32"))]
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+fn unix_now() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+fn unix_now() -> u64 {
+    0
+}
+
+/// Layout of the RTC block appended to a battery save.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SavFormat {
+    /// RAM only
+    NoRtc,
+    /// RAM + 44 bytes: 10 x u32 registers + u32 timestamp (VBA)
+    Vba,
+    /// RAM + 48 bytes: 10 x u32 registers + u64 timestamp (mGBA, BGB)
+    Mgba,
+    Unknown,
+}
+
+#[cfg(feature = "native")]
+fn sav_format_for_len(file_len: usize, ram_size: usize) -> SavFormat {
+    match file_len.checked_sub(ram_size) {
+        Some(0) => SavFormat::NoRtc,
+        Some(44) => SavFormat::Vba,
+        Some(48) => SavFormat::Mgba,
+        _ => SavFormat::Unknown,
+    }
+}
+
+// Both layouts start with current then latched registers, one u32 LE each
+fn rtc_registers_from_save(data: &[u8]) -> ([u8; 5], [u8; 5]) {
+    let reg = |i: usize| data[i * 4];
+    (
+        [reg(0), reg(1), reg(2), reg(3), reg(4)],
+        [reg(5), reg(6), reg(7), reg(8), reg(9)],
+    )
+}
+
+fn rtc_registers_to_save(rtc: &Rtc, buf: &mut [u8], now: u64) {
+    let current = rtc.current_registers(now);
+    for (i, &value) in current.iter().chain(rtc.latched.iter()).enumerate() {
+        buf[i * 4..i * 4 + 4].copy_from_slice(&(value as u32).to_le_bytes());
+    }
+}
+
+/// Parse a VBA RTC block (registers plus a u32 save time).
+pub fn import_rtc_vba(data: &[u8; 44]) -> Rtc {
+    let (regs, latched) = rtc_registers_from_save(data);
+    let saved_at = u32::from_le_bytes([data[40], data[41], data[42], data[43]]) as u64;
+    Rtc::from_saved_registers(regs, latched, saved_at)
+}
+
+/// Parse an mGBA/BGB RTC block (registers plus a u64 save time).
+pub fn import_rtc_mgba(data: &[u8; 48]) -> Rtc {
+    let (regs, latched) = rtc_registers_from_save(data);
+    let mut ts = [0u8; 8];
+    ts.copy_from_slice(&data[40..48]);
+    Rtc::from_saved_registers(regs, latched, u64::from_le_bytes(ts))
+}
+
+// Seconds of clock time held in a seconds/minutes/hours/days_low/days_high set
+fn rtc_register_seconds(regs: &[u8; 5]) -> u64 {
+    let days = ((regs[4] as u64 & 0x01) << 8) | regs[3] as u64;
+    days * 86400 + regs[2] as u64 * 3600 + regs[1] as u64 * 60 + regs[0] as u64
+}
+
+/// Seconds an mGBA timestamp may trail the .sav's modification time. mGBA
+/// stamps the block when it writes the file, so the two normally agree.
+#[cfg(feature = "native")]
+const SAVE_TIME_SLACK: u64 = 60;
+
+/// Builds before the mGBA layout appended `Rtc::to_bytes`: the raw register
+/// fields from the last RTC write and the unix time at which the clock read
+/// zero. That start time lies well before the file was written, and adding
+/// the raw registers (last write) or the latched ones (last latch) to it
+/// still gives a time no later than the write. An mGBA block is stamped with
+/// the save time itself, so only a clock younger than `SAVE_TIME_SLACK`, or a
+/// file whose modification time was reset by copying, is taken for the old
+/// layout.
+#[cfg(feature = "native")]
+fn is_legacy_rtc_block(data: &[u8; 48], modified: u64) -> bool {
+    let (regs, latched) = rtc_registers_from_save(data);
+    let mut ts = [0u8; 8];
+    ts.copy_from_slice(&data[40..48]);
+    let start = u64::from_le_bytes(ts);
+    start.saturating_add(SAVE_TIME_SLACK) < modified
+        && start.saturating_add(rtc_register_seconds(&regs)) <= modified
+        && start.saturating_add(rtc_register_seconds(&latched)) <= modified
+}
+
+pub fn export_rtc_vba(rtc: &Rtc) -> [u8; 44] {
+    let now = unix_now();
+    let mut buf = [0u8; 44];
+    rtc_registers_to_save(rtc, &mut buf, now);
+    buf[40..44].copy_from_slice(&(now as u32).to_le_bytes());
+    buf
+}
+
+pub fn export_rtc_mgba(rtc: &Rtc) -> [u8; 48] {
+    let now = unix_now();
+    let mut buf = [0u8; 48];
+    rtc_registers_to_save(rtc, &mut buf, now);
+    buf[40..48].copy_from_slice(&now.to_le_bytes());
+    buf
+}
+
 impl Rtc {
     fn new() -> Self {
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
+        let now = unix_now();
         Rtc {
             seconds: 0,
             minutes: 0,
@@ -52,22 +190,22 @@ impl Rtc {
     }
 
     fn latch(&mut self) {
+        self.latched = self.current_registers(unix_now());
+    }
+
+    // Live register values at unix time `now`
+    fn current_registers(&self, now: u64) -> [u8; 5] {
         if self.days_high & 0x40 != 0 {
             // Halted: use stored values directly
-            self.latched = [
+            return [
                 self.seconds,
                 self.minutes,
                 self.hours,
                 self.days_low,
                 self.days_high,
             ];
-            return;
         }
 
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
         let elapsed = now.saturating_sub(self.base_timestamp);
 
         let total_seconds = elapsed;
@@ -81,10 +219,11 @@ impl Rtc {
         let day_overflow = if days > 0x1FF { 0x80 } else { 0 };
         let day_high = (self.days_high & 0x40) | day_overflow | day_msb;
 
-        self.latched = [s, m, h, day_low, day_high];
+        [s, m, h, day_low, day_high]
     }
 
-    fn read(&self, reg: u8) -> u8 {
+    /// Latched value of RTC register $08-$0C.
+    pub fn read(&self, reg: u8) -> u8 {
         match reg {
             0x08 => self.latched[0],
             0x09 => self.latched[1],
@@ -137,6 +276,20 @@ impl Rtc {
         Rtc { seconds, minutes, hours, days_low, days_high, latched, base_timestamp }
     }
 
+    // Rebuild from registers captured at unix time `saved_at` (save file layout)
+    fn from_saved_registers(regs: [u8; 5], latched: [u8; 5], saved_at: u64) -> Self {
+        let total_seconds = rtc_register_seconds(&regs);
+        Rtc {
+            seconds: regs[0],
+            minutes: regs[1],
+            hours: regs[2],
+            days_low: regs[3],
+            days_high: regs[4],
+            latched,
+            base_timestamp: saved_at.saturating_sub(total_seconds),
+        }
+    }
+
     fn write(&mut self, reg: u8, value: u8) {
         // When writing RTC registers, update stored values and reset base_timestamp
         match reg {
@@ -148,10 +301,7 @@ impl Rtc {
             _ => {}
         }
         // Rebase timestamp from current register values
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
+        let now = unix_now();
         let days = ((self.days_high as u32 & 0x01) << 8) | self.days_low as u32;
         let total_seconds =
             days as u64 * 86400 + self.hours as u64 * 3600 + self.minutes as u64 * 60 + self.seconds as u64;
@@ -165,8 +315,79 @@ pub struct Cartridge {
     pub title: String,
     pub cartridge_type: u8,
     mbc: Mbc,
+    #[cfg(feature = "native")]
     has_battery: bool,
     rom_path: Option<String>,
+    /// Log writes to the $0000-$7FFF MBC register range
+    pub debug_mbc: bool,
+    /// One flag per ROM bank, set when the CPU reads the bank through $4000-$7FFF
+    accessed_rom_banks: Vec<bool>,
+    /// Original ROM bytes overwritten by `patch_rom`, keyed by ROM offset
+    patch_backup: HashMap<usize, u8>,
+    /// Header checks, made once when the ROM is loaded
+    pub flags: CartridgeFlags,
+    /// RTC layout of the loaded .sav, kept when saving
+    #[cfg(feature = "native")]
+    sav_format: SavFormat,
+}
+
+/// Result of checking the ROM header when the cartridge is loaded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CartridgeFlags {
+    /// Header checksum ($014D) matches $0134-$014C
+    pub has_valid_header: bool,
+    /// Nintendo logo at $0104-$0133 is intact
+    pub has_valid_logo: bool,
+    /// Global checksum ($014E-$014F) matches the whole ROM; the boot ROM never checks this
+    pub has_valid_global_checksum: bool,
+}
+
+impl CartridgeFlags {
+    fn from_rom(rom: &[u8]) -> Self {
+        CartridgeFlags {
+            has_valid_header: header_checksum(rom) == rom[0x014D],
+            has_valid_logo: rom[0x0104..0x0134] == NINTENDO_LOGO,
+            has_valid_global_checksum: global_checksum(rom) == u16::from_be_bytes([rom[0x014E], rom[0x014F]]),
+        }
+    }
+}
+
+/// Logo bitmap the boot ROM compares against $0104-$0133
+const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+fn header_title(rom: &[u8]) -> String {
+    String::from_utf8_lossy(&rom[0x0134..0x0144])
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+/// x = x - byte - 1 over $0134-$014C, as the boot ROM computes it.
+fn header_checksum(rom: &[u8]) -> u8 {
+    rom[0x0134..=0x014C].iter().fold(0u8, |sum, &b| sum.wrapping_sub(b).wrapping_sub(1))
+}
+
+/// Sum of every ROM byte except the two checksum bytes themselves.
+fn global_checksum(rom: &[u8]) -> u16 {
+    rom.iter()
+        .enumerate()
+        .filter(|&(addr, _)| addr != 0x014E && addr != 0x014F)
+        .fold(0u16, |sum, (_, &b)| sum.wrapping_add(b as u16))
+}
+
+// ROM size from header byte $0148; 0 for unknown codes
+fn rom_size_from_code(code: u8) -> usize {
+    const BANK: usize = 16 * 1024;
+    match code {
+        0x00..=0x08 => 0x8000 << code,
+        0x52 => 72 * BANK,
+        0x53 => 80 * BANK,
+        0x54 => 96 * BANK,
+        _ => 0,
+    }
 }
 
 fn ram_size_from_code(code: u8) -> usize {
@@ -181,10 +402,93 @@ fn ram_size_from_code(code: u8) -> usize {
     }
 }
 
+#[cfg(feature = "native")]
 fn has_battery(cartridge_type: u8) -> bool {
     matches!(cartridge_type, 0x03 | 0x06 | 0x09 | 0x0D | 0x0F | 0x10 | 0x13 | 0x1B | 0x1E)
 }
 
+// MBC2: 512 x 4-bit built-in RAM, stored one nibble per byte (upper nibble 0xF)
+const MBC2_RAM_SIZE: usize = 512;
+
+fn is_mbc2(cartridge_type: u8) -> bool {
+    matches!(cartridge_type, 0x05 | 0x06)
+}
+
+#[cfg(feature = "native")]
+/// Pack MBC2 nibbles two per byte for the .sav file: high = nibble[2n], low = nibble[2n+1].
+fn pack_mbc2_ram(ram: &[u8]) -> Vec<u8> {
+    ram.chunks(2)
+        .map(|pair| (pair[0] & 0x0F) << 4 | pair.get(1).map_or(0, |b| b & 0x0F))
+        .collect()
+}
+
+#[cfg(feature = "native")]
+/// Inverse of `pack_mbc2_ram`. Also accepts the unpacked one-nibble-per-byte
+/// layout that other emulators write.
+fn unpack_mbc2_ram(data: &[u8], ram: &mut [u8]) {
+    if data.len() >= ram.len() {
+        for (dst, &src) in ram.iter_mut().zip(data) {
+            *dst = 0xF0 | (src & 0x0F);
+        }
+        return;
+    }
+    for (i, &byte) in data.iter().enumerate().take(ram.len() / 2) {
+        ram[i * 2] = 0xF0 | (byte >> 4);
+        ram[i * 2 + 1] = 0xF0 | (byte & 0x0F);
+    }
+}
+
+fn cartridge_type_name(code: u8) -> &'static str {
+    match code {
+        0x00 => "ROM ONLY",
+        0x01 => "MBC1",
+        0x02 => "MBC1+RAM",
+        0x03 => "MBC1+RAM+BATTERY",
+        0x05 => "MBC2",
+        0x06 => "MBC2+BATTERY",
+        0x08 => "ROM+RAM",
+        0x09 => "ROM+RAM+BATTERY",
+        0x0B => "MMM01",
+        0x0C => "MMM01+RAM",
+        0x0D => "MMM01+RAM+BATTERY",
+        0x0F => "MBC3+TIMER+BATTERY",
+        0x10 => "MBC3+TIMER+RAM+BATTERY",
+        0x11 => "MBC3",
+        0x12 => "MBC3+RAM",
+        0x13 => "MBC3+RAM+BATTERY",
+        0x19 => "MBC5",
+        0x1A => "MBC5+RAM",
+        0x1B => "MBC5+RAM+BATTERY",
+        0x1C => "MBC5+RUMBLE",
+        0x1D => "MBC5+RUMBLE+RAM",
+        0x1E => "MBC5+RUMBLE+RAM+BATTERY",
+        0x20 => "MBC6",
+        0x22 => "MBC7+SENSOR+RUMBLE+RAM+BATTERY",
+        0xFC => "POCKET CAMERA",
+        0xFD => "BANDAI TAMA5",
+        0xFE => "HuC3",
+        0xFF => "HuC1+RAM+BATTERY",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Decoded cartridge header fields ($0100-$014F).
+pub struct CartridgeInfo {
+    pub title: String,
+    pub manufacturer_code: String,
+    pub cgb_flag: u8,
+    pub sgb_flag: u8,
+    pub cartridge_type: u8,
+    pub cartridge_type_name: &'static str,
+    pub rom_size: usize,
+    pub ram_size: usize,
+    pub destination_code: u8,
+    pub version: u8,
+    pub header_checksum_valid: bool,
+    pub global_checksum: u16,
+}
+
+#[cfg(feature = "native")]
 fn sav_path(rom_path: &str) -> std::path::PathBuf {
     let path = Path::new(rom_path);
     let parent = path.parent().unwrap_or(Path::new("."));
@@ -201,6 +505,10 @@ fn mbc_from_type(cartridge_type: u8) -> Mbc {
             ram_enabled: false,
             banking_mode: false,
         },
+        0x05..=0x06 => Mbc::Mbc2 {
+            rom_bank: 1,
+            ram_enabled: false,
+        },
         0x0F..=0x13 => Mbc::Mbc3 {
             rom_bank: 1,
             ram_bank: 0,
@@ -213,62 +521,160 @@ fn mbc_from_type(cartridge_type: u8) -> Mbc {
             ram_bank: 0,
             ram_enabled: false,
         },
+        0x20 => Mbc::Mbc6 {
+            rom_bank: [2, 3],
+            rom_flash: [false; 2],
+            ram_bank: [0, 1],
+            ram_enabled: false,
+            flash_enabled: false,
+            flash_write_enabled: false,
+        },
         _ => Mbc::NoMbc,
     }
 }
 
 impl Cartridge {
+    #[cfg(feature = "native")]
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Cartridge, String> {
+        Cartridge::from_file_checked(path, false)
+    }
+
+    /// Like `from_file`, but a bad header checksum or logo is an error
+    /// when `strict` is set instead of a warning.
+    #[cfg(feature = "native")]
+    pub fn from_file_checked<P: AsRef<Path>>(path: P, strict: bool) -> Result<Cartridge, String> {
         let rom_path_str = path.as_ref().to_string_lossy().into_owned();
         let data = fs::read(path).map_err(|e| format!("Failed to read ROM: {}", e))?;
+        let mut cartridge = Cartridge::from_bytes(&data)?;
+        cartridge.check_header(strict)?;
+        cartridge.rom_path = Some(rom_path_str);
+        if let Err(e) = cartridge.load() {
+            eprintln!("{}", e);
+        }
+        Ok(cartridge)
+    }
+
+    /// Title from the header of the ROM at `path`, without loading its save.
+    #[cfg(feature = "native")]
+    pub fn read_title<P: AsRef<Path>>(path: P) -> Option<String> {
+        let data = fs::read(path).ok()?;
+        (data.len() >= 0x150).then(|| header_title(&data))
+    }
+
+    /// Build a cartridge from an in-memory ROM image. Without a ROM path there
+    /// is no .sav to load, and `save` is a no-op.
+    pub fn from_bytes(data: &[u8]) -> Result<Cartridge, String> {
         if data.len() < 0x150 {
             return Err("ROM too small to contain header".to_string());
         }
 
-        let title_bytes = &data[0x0134..0x0144];
-        let title = String::from_utf8_lossy(title_bytes)
-            .trim_end_matches('\0')
-            .to_string();
+        let title = header_title(data);
 
         let cartridge_type = data[0x0147];
         let ram_code = data[0x0149];
-        let ram_size = ram_size_from_code(ram_code);
-        let mut mbc = mbc_from_type(cartridge_type);
-        let battery = has_battery(cartridge_type);
-
-        let mut ram = vec![0u8; ram_size];
-
-        // Load .sav file if battery-backed
-        if battery {
-            let sav = sav_path(&rom_path_str);
-            if sav.exists() {
-                if let Ok(sav_data) = fs::read(&sav) {
-                    let copy_len = sav_data.len().min(ram.len());
-                    ram[..copy_len].copy_from_slice(&sav_data[..copy_len]);
-
-                    // MBC3: restore RTC from 48 bytes after RAM
-                    if sav_data.len() >= ram.len() + 48 {
-                        if let Mbc::Mbc3 { ref mut rtc, .. } = mbc {
-                            *rtc = Rtc::from_bytes(&sav_data[ram.len()..ram.len() + 48]);
-                        }
-                    }
-
-                    eprintln!("Loaded save from {}", sav.display());
-                }
-            }
-        }
+        // MBC2 RAM is built in; the header RAM size is 0
+        let ram_size = if is_mbc2(cartridge_type) { MBC2_RAM_SIZE } else { ram_size_from_code(ram_code) };
+        let ram = if is_mbc2(cartridge_type) { vec![0xF0; ram_size] } else { vec![0u8; ram_size] };
+        let num_banks = (data.len() / 0x4000).max(2);
 
         Ok(Cartridge {
-            rom: data,
+            rom: data.to_vec(),
             ram,
             title,
             cartridge_type,
-            mbc,
-            has_battery: battery,
-            rom_path: Some(rom_path_str),
+            mbc: mbc_from_type(cartridge_type),
+            #[cfg(feature = "native")]
+            has_battery: has_battery(cartridge_type),
+            rom_path: None,
+            debug_mbc: false,
+            accessed_rom_banks: vec![false; num_banks],
+            patch_backup: HashMap::new(),
+            flags: CartridgeFlags::from_rom(data),
+            #[cfg(feature = "native")]
+            sav_format: SavFormat::Mgba,
         })
     }
 
+    /// Report the header problems a real boot ROM would lock up on.
+    #[cfg(feature = "native")]
+    fn check_header(&self, strict: bool) -> Result<(), String> {
+        let mut problems = Vec::new();
+        if !self.flags.has_valid_header {
+            problems.push("header checksum mismatch");
+        }
+        if !self.flags.has_valid_logo {
+            problems.push("Nintendo logo mismatch");
+        }
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let message = format!("Invalid ROM header: {}", problems.join(", "));
+        if strict {
+            return Err(message);
+        }
+        eprintln!("Warning: {}", message);
+        Ok(())
+    }
+
+    /// Load battery RAM (and MBC3 RTC) from the .sav next to the ROM, if any.
+    #[cfg(feature = "native")]
+    pub fn load(&mut self) -> Result<(), String> {
+        let rom_path = match &self.rom_path {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        if !self.has_battery {
+            return Ok(());
+        }
+        let sav = sav_path(rom_path);
+        if !sav.exists() {
+            return Ok(());
+        }
+        // Length and modification time both come from the one open handle
+        let mut file = fs::File::open(&sav).map_err(|e| format!("Failed to read save: {}", e))?;
+        let modified = file.metadata().and_then(|meta| meta.modified()).ok()
+            .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map_or_else(unix_now, |age| age.as_secs());
+        let mut sav_data = Vec::new();
+        file.read_to_end(&mut sav_data).map_err(|e| format!("Failed to read save: {}", e))?;
+        let format = sav_format_for_len(sav_data.len(), self.ram.len());
+
+        if is_mbc2(self.cartridge_type) {
+            unpack_mbc2_ram(&sav_data, &mut self.ram);
+            eprintln!("Loaded save from {}", sav.display());
+            return Ok(());
+        }
+
+        let copy_len = sav_data.len().min(self.ram.len());
+        self.ram[..copy_len].copy_from_slice(&sav_data[..copy_len]);
+
+        // MBC3: restore RTC from the block after RAM
+        if let Mbc::Mbc3 { ref mut rtc, .. } = self.mbc {
+            let block = &sav_data[copy_len..];
+            let bad_block = |_| format!("Bad RTC block length {} in {}", block.len(), sav.display());
+            match format {
+                SavFormat::Vba => {
+                    *rtc = import_rtc_vba(block.try_into().map_err(bad_block)?);
+                    self.sav_format = SavFormat::Vba;
+                }
+                SavFormat::Mgba => {
+                    let block: &[u8; 48] = block.try_into().map_err(bad_block)?;
+                    *rtc = if is_legacy_rtc_block(block, modified) {
+                        eprintln!("Old RTC block in {}, next save writes the mGBA layout", sav.display());
+                        Rtc::from_bytes(block)
+                    } else {
+                        import_rtc_mgba(block)
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        eprintln!("Loaded save from {}", sav.display());
+        Ok(())
+    }
+
+    #[cfg(feature = "native")]
     pub fn save(&self) -> Result<(), String> {
         let rom_path = match &self.rom_path {
             Some(p) => p,
@@ -286,18 +692,38 @@ impl Cartridge {
             fs::create_dir_all(parent).map_err(|e| format!("Failed to create save directory: {}", e))?;
         }
 
-        let mut data = self.ram.clone();
+        let mut data = if is_mbc2(self.cartridge_type) {
+            pack_mbc2_ram(&self.ram)
+        } else {
+            self.ram.clone()
+        };
 
-        // MBC3: append 48 bytes of RTC state
+        // MBC3: append the RTC block in the layout the save was loaded with
         if let Mbc::Mbc3 { ref rtc, .. } = self.mbc {
-            data.extend_from_slice(&rtc.to_bytes());
+            match self.sav_format {
+                SavFormat::Vba => data.extend_from_slice(&export_rtc_vba(rtc)),
+                _ => data.extend_from_slice(&export_rtc_mgba(rtc)),
+            }
         }
 
-        fs::write(&sav, &data).map_err(|e| format!("Failed to write save: {}", e))?;
+        // Write a temp file and rename it over the old save, so a crash
+        // mid-write never leaves a truncated .sav behind
+        let tmp = sav.with_extension("sav.tmp");
+        fs::write(&tmp, &data).map_err(|e| format!("Failed to write save: {}", e))?;
+        fs::rename(&tmp, &sav).map_err(|e| format!("Failed to replace save: {}", e))?;
         eprintln!("Saved to {}", sav.display());
         Ok(())
     }
 
+    /// Classify an existing .sav file by its size relative to cartridge RAM.
+    #[cfg(feature = "native")]
+    pub fn detect_sav_format(&self, path: &Path) -> SavFormat {
+        match fs::metadata(path) {
+            Ok(meta) => sav_format_for_len(meta.len() as usize, self.ram.len()),
+            Err(_) => SavFormat::Unknown,
+        }
+    }
+
     fn num_rom_banks(&self) -> usize {
         (self.rom.len() / 0x4000).max(2)
     }
@@ -308,16 +734,115 @@ impl Cartridge {
             Mbc::Mbc1 { rom_bank, ram_bank, ram_enabled, banking_mode } => {
                 self.read_mbc1(address, *rom_bank, *ram_bank, *ram_enabled, *banking_mode)
             }
+            Mbc::Mbc2 { rom_bank, ram_enabled } => {
+                self.read_mbc2(address, *rom_bank, *ram_enabled)
+            }
             Mbc::Mbc3 { rom_bank, ram_bank, ram_enabled, rtc, .. } => {
                 self.read_mbc3(address, *rom_bank, *ram_bank, *ram_enabled, rtc)
             }
             Mbc::Mbc5 { rom_bank, ram_bank, ram_enabled } => {
                 self.read_mbc5(address, *rom_bank, *ram_bank, *ram_enabled)
             }
+            Mbc::Mbc6 { rom_bank, rom_flash, ram_bank, ram_enabled, .. } => {
+                self.read_mbc6(address, *rom_bank, *rom_flash, *ram_bank, *ram_enabled)
+            }
+        }
+    }
+
+    /// ROM bank currently mapped at $4000-$7FFF.
+    pub fn active_rom_bank(&self) -> usize {
+        let num_banks = self.num_rom_banks();
+        match &self.mbc {
+            Mbc::NoMbc => 1,
+            Mbc::Mbc1 { rom_bank, ram_bank, .. } => {
+                let mut bank = ((*ram_bank as usize) << 5) | *rom_bank as usize;
+                if bank & 0x1F == 0 {
+                    bank |= 1;
+                }
+                bank % num_banks
+            }
+            Mbc::Mbc2 { rom_bank, .. } => *rom_bank as usize % num_banks,
+            Mbc::Mbc3 { rom_bank, .. } => *rom_bank as usize % num_banks,
+            Mbc::Mbc5 { rom_bank, .. } => *rom_bank as usize % num_banks,
+            // 16 KB bank holding the 8 KB bank A window
+            Mbc::Mbc6 { rom_bank, .. } => (rom_bank[0] as usize / 2) % num_banks,
+        }
+    }
+
+    // Map a $0000-$7FFF CPU address to a ROM offset using the current banking
+    fn rom_offset(&self, address: u16) -> Option<usize> {
+        if let Mbc::Mbc6 { rom_bank, rom_flash, .. } = &self.mbc {
+            if let 0x4000..=0x7FFF = address {
+                let window = (address as usize - 0x4000) / 0x2000;
+                if rom_flash[window] {
+                    return None;
+                }
+                let offset = self.mbc6_rom_offset(rom_bank[window], address);
+                return if offset < self.rom.len() { Some(offset) } else { None };
+            }
+        }
+        let offset = match address {
+            0x0000..=0x3FFF => address as usize,
+            0x4000..=0x7FFF => self.active_rom_bank() * 0x4000 + (address as usize - 0x4000),
+            _ => return None,
+        };
+        if offset < self.rom.len() { Some(offset) } else { None }
+    }
+
+    /// Overwrite ROM bytes at `address` (bank 0, or the bank mapped at $4000-$7FFF).
+    pub fn patch_rom(&mut self, address: u16, bytes: &[u8]) -> Result<(), String> {
+        let offsets = (0..bytes.len())
+            .map(|i| {
+                let addr = address as usize + i;
+                if addr > 0x7FFF {
+                    return None;
+                }
+                self.rom_offset(addr as u16)
+            })
+            .collect::<Option<Vec<usize>>>()
+            .ok_or_else(|| format!("ROM patch at ${:04X} (+{}) is outside ROM", address, bytes.len()))?;
+        for (&offset, &byte) in offsets.iter().zip(bytes) {
+            self.patch_backup.entry(offset).or_insert(self.rom[offset]);
+            self.rom[offset] = byte;
+        }
+        Ok(())
+    }
+
+    /// Restore the original bytes for `len` patched addresses starting at `address`.
+    pub fn revert_rom_patch(&mut self, address: u16, len: usize) {
+        for i in 0..len {
+            let addr = address as usize + i;
+            if addr > 0x7FFF {
+                break;
+            }
+            if let Some(offset) = self.rom_offset(addr as u16) {
+                if let Some(original) = self.patch_backup.remove(&offset) {
+                    self.rom[offset] = original;
+                }
+            }
+        }
+    }
+
+    /// Which ROM banks have been read through the switchable $4000-$7FFF window.
+    pub fn accessed_rom_banks(&self) -> Vec<bool> {
+        self.accessed_rom_banks.clone()
+    }
+
+    /// Mark the mapped bank as accessed if `address` is in $4000-$7FFF. Called
+    /// for CPU reads only, so debugger peeks don't show up in the bank map.
+    pub fn record_rom_read(&mut self, address: u16) {
+        if let 0x4000..=0x7FFF = address {
+            let bank = self.active_rom_bank();
+            if let Some(flag) = self.accessed_rom_banks.get_mut(bank) {
+                *flag = true;
+            }
         }
     }
 
     pub fn write_byte(&mut self, address: u16, value: u8) {
+        if self.debug_mbc && address <= 0x7FFF {
+            eprintln!("{}", self.describe_mbc_write(address, value));
+        }
         match &mut self.mbc {
             Mbc::NoMbc => {} // writes ignored
             Mbc::Mbc1 { ref mut rom_bank, ref mut ram_bank, ref mut ram_enabled, ref mut banking_mode } => {
@@ -341,6 +866,26 @@ impl Cartridge {
                     _ => {}
                 }
             }
+            Mbc::Mbc2 { ref mut rom_bank, ref mut ram_enabled } => {
+                match address {
+                    // Address bit 8 selects between RAM enable (clear) and ROM bank (set)
+                    0x0000..=0x3FFF => {
+                        if address & 0x0100 == 0 {
+                            *ram_enabled = (value & 0x0F) == 0x0A;
+                        } else {
+                            let bank = value & 0x0F;
+                            *rom_bank = if bank == 0 { 1 } else { bank };
+                        }
+                    }
+                    0xA000..=0xBFFF if *ram_enabled => {
+                        let offset = (address as usize - 0xA000) & (MBC2_RAM_SIZE - 1);
+                        if let Some(nibble) = self.ram.get_mut(offset) {
+                            *nibble = 0xF0 | (value & 0x0F);
+                        }
+                    }
+                    _ => {}
+                }
+            }
             Mbc::Mbc3 { ref mut rom_bank, ref mut ram_bank, ref mut ram_enabled, ref mut rtc, ref mut rtc_latch } => {
                 match address {
                     0x0000..=0x1FFF => *ram_enabled = (value & 0x0F) == 0x0A,
@@ -357,19 +902,49 @@ impl Cartridge {
                     }
                     0xA000..=0xBFFF => {
                         if *ram_enabled {
-                            if *ram_bank <= 0x03 {
-                                let offset = *ram_bank as usize * 0x2000 + (address as usize - 0xA000);
-                                if offset < self.ram.len() {
-                                    self.ram[offset] = value;
+                            match *ram_bank {
+                                0x00..=0x03 => {
+                                    let offset = *ram_bank as usize * 0x2000 + (address as usize - 0xA000);
+                                    if offset < self.ram.len() {
+                                        self.ram[offset] = value;
+                                    }
                                 }
-                            } else if *ram_bank >= 0x08 && *ram_bank <= 0x0C {
-                                rtc.write(*ram_bank, value);
+                                0x08..=0x0C => rtc.write(*ram_bank, value),
+                                _ => {} // 0x04-0x07 and 0x0D+ are unmapped
                             }
                         }
                     }
                     _ => {}
                 }
             }
+            Mbc::Mbc6 {
+                ref mut rom_bank,
+                ref mut rom_flash,
+                ref mut ram_bank,
+                ref mut ram_enabled,
+                ref mut flash_enabled,
+                ref mut flash_write_enabled,
+            } => {
+                match address {
+                    0x0000..=0x03FF => *ram_enabled = (value & 0x0F) == 0x0A,
+                    0x0400..=0x07FF => ram_bank[0] = value,
+                    0x0800..=0x0BFF => ram_bank[1] = value,
+                    0x0C00..=0x0FFF => *flash_enabled = value & 0x01 != 0,
+                    0x1000 => *flash_write_enabled = value & 0x01 != 0,
+                    0x2000..=0x27FF => rom_bank[0] = value,
+                    0x2800..=0x2FFF => rom_flash[0] = value == 0x08,
+                    0x3000..=0x37FF => rom_bank[1] = value,
+                    0x3800..=0x3FFF => rom_flash[1] = value == 0x08,
+                    0xA000..=0xBFFF if *ram_enabled => {
+                        let window = (address as usize - 0xA000) / 0x1000;
+                        let offset = ram_bank[window] as usize * 0x1000 + (address as usize & 0x0FFF);
+                        if offset < self.ram.len() {
+                            self.ram[offset] = value;
+                        }
+                    }
+                    _ => {} // flash programming is not emulated
+                }
+            }
             Mbc::Mbc5 { ref mut rom_bank, ref mut ram_bank, ref mut ram_enabled } => {
                 match address {
                     0x0000..=0x1FFF => *ram_enabled = (value & 0x0F) == 0x0A,
@@ -394,6 +969,40 @@ impl Cartridge {
         }
     }
 
+    /// Human-readable description of a write to the MBC register range.
+    fn describe_mbc_write(&self, address: u16, value: u8) -> String {
+        let register = match (&self.mbc, address) {
+            (Mbc::NoMbc, _) => "write to ROM (no MBC, likely a ROM bug)",
+            (Mbc::Mbc1 { .. }, 0x0000..=0x1FFF) => "MBC1 RAM enable",
+            (Mbc::Mbc1 { .. }, 0x2000..=0x3FFF) => "MBC1 ROM bank",
+            (Mbc::Mbc1 { .. }, 0x4000..=0x5FFF) => "MBC1 RAM bank / upper ROM bank",
+            (Mbc::Mbc1 { .. }, _) => "MBC1 banking mode",
+            (Mbc::Mbc2 { .. }, 0x0000..=0x3FFF) if address & 0x0100 == 0 => "MBC2 RAM enable",
+            (Mbc::Mbc2 { .. }, 0x0000..=0x3FFF) => "MBC2 ROM bank",
+            (Mbc::Mbc2 { .. }, _) => "MBC2 unmapped register",
+            (Mbc::Mbc3 { .. }, 0x0000..=0x1FFF) => "MBC3 RAM/RTC enable",
+            (Mbc::Mbc3 { .. }, 0x2000..=0x3FFF) => "MBC3 ROM bank",
+            (Mbc::Mbc3 { .. }, 0x4000..=0x5FFF) => "MBC3 RAM bank / RTC select",
+            (Mbc::Mbc3 { .. }, _) => "MBC3 RTC latch",
+            (Mbc::Mbc5 { .. }, 0x0000..=0x1FFF) => "MBC5 RAM enable",
+            (Mbc::Mbc5 { .. }, 0x2000..=0x2FFF) => "MBC5 ROM bank (low)",
+            (Mbc::Mbc5 { .. }, 0x3000..=0x3FFF) => "MBC5 ROM bank (bit 8)",
+            (Mbc::Mbc5 { .. }, 0x4000..=0x5FFF) => "MBC5 RAM bank",
+            (Mbc::Mbc5 { .. }, _) => "MBC5 unmapped register",
+            (Mbc::Mbc6 { .. }, 0x0000..=0x03FF) => "MBC6 RAM enable",
+            (Mbc::Mbc6 { .. }, 0x0400..=0x07FF) => "MBC6 RAM bank A",
+            (Mbc::Mbc6 { .. }, 0x0800..=0x0BFF) => "MBC6 RAM bank B",
+            (Mbc::Mbc6 { .. }, 0x0C00..=0x0FFF) => "MBC6 flash enable",
+            (Mbc::Mbc6 { .. }, 0x1000) => "MBC6 flash write enable",
+            (Mbc::Mbc6 { .. }, 0x2000..=0x27FF) => "MBC6 ROM bank A",
+            (Mbc::Mbc6 { .. }, 0x2800..=0x2FFF) => "MBC6 bank A ROM/flash select",
+            (Mbc::Mbc6 { .. }, 0x3000..=0x37FF) => "MBC6 ROM bank B",
+            (Mbc::Mbc6 { .. }, 0x3800..=0x3FFF) => "MBC6 bank B ROM/flash select",
+            (Mbc::Mbc6 { .. }, _) => "MBC6 unmapped register",
+        };
+        format!("[MBC] ${:04X} {}: 0x{:02X}", address, register, value)
+    }
+
     // --- NoMbc ---
 
     fn read_no_mbc(&self, address: u16) -> u8 {
@@ -444,6 +1053,28 @@ impl Cartridge {
         }
     }
 
+    // --- MBC2 ---
+
+    fn read_mbc2(&self, address: u16, rom_bank: u8, ram_enabled: bool) -> u8 {
+        match address {
+            0x0000..=0x3FFF => {
+                let addr = address as usize;
+                if addr < self.rom.len() { self.rom[addr] } else { 0xFF }
+            }
+            0x4000..=0x7FFF => {
+                let bank = (rom_bank as usize) % self.num_rom_banks();
+                let addr = bank * 0x4000 + (address as usize - 0x4000);
+                if addr < self.rom.len() { self.rom[addr] } else { 0xFF }
+            }
+            0xA000..=0xBFFF if ram_enabled => {
+                // 512 nibbles mirrored across $A000-$BFFF, upper nibble reads as 1s
+                let offset = (address as usize - 0xA000) & (MBC2_RAM_SIZE - 1);
+                0xF0 | self.ram.get(offset).copied().unwrap_or(0xFF)
+            }
+            _ => 0xFF,
+        }
+    }
+
     // --- MBC3 ---
 
     fn read_mbc3(&self, address: u16, rom_bank: u8, ram_bank: u8, ram_enabled: bool, rtc: &Rtc) -> u8 {
@@ -461,13 +1092,13 @@ impl Cartridge {
                 if !ram_enabled {
                     return 0xFF;
                 }
-                if ram_bank <= 0x03 {
-                    let offset = ram_bank as usize * 0x2000 + (address as usize - 0xA000);
-                    if offset < self.ram.len() { self.ram[offset] } else { 0xFF }
-                } else if ram_bank >= 0x08 && ram_bank <= 0x0C {
-                    rtc.read(ram_bank)
-                } else {
-                    0xFF
+                match ram_bank {
+                    0x00..=0x03 => {
+                        let offset = ram_bank as usize * 0x2000 + (address as usize - 0xA000);
+                        if offset < self.ram.len() { self.ram[offset] } else { 0xFF }
+                    }
+                    0x08..=0x0C => rtc.read(ram_bank),
+                    _ => 0xFF, // 0x04-0x07 and 0x0D+ are unmapped
                 }
             }
             _ => 0xFF,
@@ -498,15 +1129,72 @@ impl Cartridge {
             _ => 0xFF,
         }
     }
+
+    // --- MBC6 ---
+
+    fn mbc6_rom_offset(&self, bank: u8, address: u16) -> usize {
+        let num_banks = (self.rom.len() / 0x2000).max(1);
+        (bank as usize % num_banks) * 0x2000 + (address as usize & 0x1FFF)
+    }
+
+    fn read_mbc6(&self, address: u16, rom_bank: [u8; 2], rom_flash: [bool; 2], ram_bank: [u8; 2], ram_enabled: bool) -> u8 {
+        match address {
+            0x0000..=0x3FFF => {
+                let addr = address as usize;
+                if addr < self.rom.len() { self.rom[addr] } else { 0xFF }
+            }
+            0x4000..=0x7FFF => {
+                let window = (address as usize - 0x4000) / 0x2000;
+                if rom_flash[window] {
+                    return 0xFF; // flash is not emulated, reads as erased
+                }
+                let addr = self.mbc6_rom_offset(rom_bank[window], address);
+                if addr < self.rom.len() { self.rom[addr] } else { 0xFF }
+            }
+            0xA000..=0xBFFF if ram_enabled => {
+                let window = (address as usize - 0xA000) / 0x1000;
+                let offset = ram_bank[window] as usize * 0x1000 + (address as usize & 0x0FFF);
+                if offset < self.ram.len() { self.ram[offset] } else { 0xFF }
+            }
+            _ => 0xFF,
+        }
+    }
 }
 
 impl Cartridge {
+    pub fn info(&self) -> CartridgeInfo {
+        let header = |addr: usize| self.rom.get(addr).copied().unwrap_or(0);
+
+        let manufacturer_code = (0x013F..=0x0142)
+            .map(header)
+            .filter(|b| b.is_ascii_graphic())
+            .map(|b| b as char)
+            .collect();
+
+        CartridgeInfo {
+            title: self.title.clone(),
+            manufacturer_code,
+            cgb_flag: header(0x0143),
+            sgb_flag: header(0x0146),
+            cartridge_type: self.cartridge_type,
+            cartridge_type_name: cartridge_type_name(self.cartridge_type),
+            rom_size: rom_size_from_code(header(0x0148)),
+            ram_size: ram_size_from_code(header(0x0149)),
+            destination_code: header(0x014A),
+            version: header(0x014C),
+            header_checksum_valid: header_checksum(&self.rom) == header(0x014D),
+            global_checksum: ((header(0x014E) as u16) << 8) | header(0x014F) as u16,
+        }
+    }
+
     pub fn mbc_type_tag(&self) -> u8 {
         match &self.mbc {
             Mbc::NoMbc => 0,
             Mbc::Mbc1 { .. } => 1,
+            Mbc::Mbc2 { .. } => 2,
             Mbc::Mbc3 { .. } => 3,
             Mbc::Mbc5 { .. } => 5,
+            Mbc::Mbc6 { .. } => 6,
         }
     }
 
@@ -531,6 +1219,10 @@ impl Cartridge {
                 write_bool(buf, *ram_enabled);
                 write_bool(buf, *banking_mode);
             }
+            Mbc::Mbc2 { rom_bank, ram_enabled } => {
+                write_u8(buf, *rom_bank);
+                write_bool(buf, *ram_enabled);
+            }
             Mbc::Mbc3 { rom_bank, ram_bank, ram_enabled, rtc, rtc_latch } => {
                 write_u8(buf, *rom_bank);
                 write_u8(buf, *ram_bank);
@@ -543,6 +1235,16 @@ impl Cartridge {
                 write_u8(buf, *ram_bank);
                 write_bool(buf, *ram_enabled);
             }
+            Mbc::Mbc6 { rom_bank, rom_flash, ram_bank, ram_enabled, flash_enabled, flash_write_enabled } => {
+                for i in 0..2 {
+                    write_u8(buf, rom_bank[i]);
+                    write_bool(buf, rom_flash[i]);
+                    write_u8(buf, ram_bank[i]);
+                }
+                write_bool(buf, *ram_enabled);
+                write_bool(buf, *flash_enabled);
+                write_bool(buf, *flash_write_enabled);
+            }
         }
     }
 
@@ -560,6 +1262,10 @@ impl Cartridge {
                 *ram_enabled = read_bool(data, cursor);
                 *banking_mode = read_bool(data, cursor);
             }
+            Mbc::Mbc2 { rom_bank, ram_enabled } => {
+                *rom_bank = read_u8(data, cursor);
+                *ram_enabled = read_bool(data, cursor);
+            }
             Mbc::Mbc3 { rom_bank, ram_bank, ram_enabled, rtc, rtc_latch } => {
                 *rom_bank = read_u8(data, cursor);
                 *ram_bank = read_u8(data, cursor);
@@ -573,6 +1279,16 @@ impl Cartridge {
                 *ram_bank = read_u8(data, cursor);
                 *ram_enabled = read_bool(data, cursor);
             }
+            Mbc::Mbc6 { rom_bank, rom_flash, ram_bank, ram_enabled, flash_enabled, flash_write_enabled } => {
+                for i in 0..2 {
+                    rom_bank[i] = read_u8(data, cursor);
+                    rom_flash[i] = read_bool(data, cursor);
+                    ram_bank[i] = read_u8(data, cursor);
+                }
+                *ram_enabled = read_bool(data, cursor);
+                *flash_enabled = read_bool(data, cursor);
+                *flash_write_enabled = read_bool(data, cursor);
+            }
         }
     }
 }
@@ -585,8 +1301,455 @@ impl Default for Cartridge {
             title: String::new(),
             cartridge_type: 0,
             mbc: Mbc::NoMbc,
+            #[cfg(feature = "native")]
             has_battery: false,
             rom_path: None,
+            debug_mbc: false,
+            accessed_rom_banks: vec![false; 2],
+            patch_backup: HashMap::new(),
+            flags: CartridgeFlags::default(),
+            #[cfg(feature = "native")]
+            sav_format: SavFormat::Mgba,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cartridge_with_type(cartridge_type: u8) -> Cartridge {
+        Cartridge {
+            cartridge_type,
+            mbc: mbc_from_type(cartridge_type),
+            ..Cartridge::default()
         }
     }
+
+    fn vba_rtc_block(days: u8, saved_at: u32) -> [u8; 44] {
+        let mut data = [0u8; 44];
+        data[3 * 4] = days; // current days low
+        data[8 * 4] = days; // latched days low
+        data[40..44].copy_from_slice(&saved_at.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_import_rtc_vba_days() {
+        let now = unix_now() as u32;
+        let mut rtc = import_rtc_vba(&vba_rtc_block(2, now));
+        assert_eq!(rtc.read(0x0B), 2);
+        rtc.latch();
+        assert_eq!(rtc.read(0x0B), 2);
+
+        // A save from one day ago advances by a day on load
+        let mut rtc = import_rtc_vba(&vba_rtc_block(2, now - 86400));
+        rtc.latch();
+        assert_eq!(rtc.read(0x0B), 3);
+    }
+
+    #[test]
+    fn test_rtc_export_round_trip() {
+        let rtc = import_rtc_vba(&vba_rtc_block(5, unix_now() as u32));
+        let mut mgba = import_rtc_mgba(&export_rtc_mgba(&rtc));
+        mgba.latch();
+        assert_eq!(mgba.read(0x0B), 5);
+        let mut vba = import_rtc_vba(&export_rtc_vba(&rtc));
+        vba.latch();
+        assert_eq!(vba.read(0x0B), 5);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_sav_format_from_len() {
+        assert_eq!(sav_format_for_len(0x2000, 0x2000), SavFormat::NoRtc);
+        assert_eq!(sav_format_for_len(0x2000 + 44, 0x2000), SavFormat::Vba);
+        assert_eq!(sav_format_for_len(0x2000 + 48, 0x2000), SavFormat::Mgba);
+        assert_eq!(sav_format_for_len(0x1000, 0x2000), SavFormat::Unknown);
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x0134..0x0139].copy_from_slice(b"TETRA");
+        rom[0x0147] = 0x01;
+        let cart = Cartridge::from_bytes(&rom).unwrap();
+        assert_eq!(cart.title, "TETRA");
+        assert_eq!(cart.mbc_type_tag(), 1);
+        assert!(cart.rom_path().is_none());
+
+        assert!(Cartridge::from_bytes(&rom[..0x14F]).is_err());
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_mbc2_save_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut cart = Cartridge {
+            ram: vec![0xF0; MBC2_RAM_SIZE],
+            has_battery: true,
+            rom_path: Some(dir.join("mbc2.gb").to_string_lossy().into_owned()),
+            ..cartridge_with_type(0x06)
+        };
+        for (i, byte) in cart.ram.iter_mut().enumerate() {
+            *byte = 0xF0 | (i % 16) as u8;
+        }
+        cart.save().unwrap();
+        let sav = sav_path(cart.rom_path().unwrap());
+        assert_eq!(fs::metadata(&sav).unwrap().len(), 256);
+
+        cart.ram.fill(0);
+        cart.load().unwrap();
+        for (i, &byte) in cart.ram.iter().enumerate() {
+            assert_eq!(byte & 0x0F, (i % 16) as u8, "nibble {}", i);
+            assert_eq!(byte & 0xF0, 0xF0, "upper nibble {}", i);
+        }
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_battery_save_round_trip_through_from_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        // (cartridge type, persists): MBC1, MBC3 and MBC5 with battery, then MBC1 without
+        for (cartridge_type, persists) in [(0x03, true), (0x13, true), (0x1B, true), (0x02, false)] {
+            let mut rom = vec![0u8; 0x8000];
+            rom[0x0147] = cartridge_type;
+            rom[0x0149] = 0x02; // 8 KiB
+            let rom_path = dir.join(format!("type_{:02x}.gb", cartridge_type));
+            fs::write(&rom_path, &rom).unwrap();
+
+            let mut cart = Cartridge::from_file(&rom_path).unwrap();
+            cart.write_byte(0x0000, 0x0A); // enable RAM
+            cart.write_byte(0xA123, 0x5A);
+            cart.save().unwrap();
+            let sav = sav_path(cart.rom_path().unwrap());
+            assert_eq!(sav.exists(), persists, "type {:02X}", cartridge_type);
+            assert!(!sav.with_extension("sav.tmp").exists());
+
+            let mut cart = Cartridge::from_file(&rom_path).unwrap();
+            cart.write_byte(0x0000, 0x0A);
+            let expected = if persists { 0x5A } else { 0x00 };
+            assert_eq!(cart.read_byte(0xA123), expected, "type {:02X}", cartridge_type);
+        }
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_mbc3_rtc_keeps_running_between_sessions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x0147] = 0x10; // MBC3+TIMER+RAM+BATTERY
+        rom[0x0149] = 0x02;
+        let rom_path = dir.join("rtc.gb");
+        fs::write(&rom_path, &rom).unwrap();
+
+        let cart = Cartridge::from_file(&rom_path).unwrap();
+        cart.save().unwrap();
+        let sav = sav_path(cart.rom_path().unwrap());
+        let mut data = fs::read(&sav).unwrap();
+        assert_eq!(data.len(), 0x2000 + 48);
+
+        // Pretend the save was written two days ago
+        let saved_at = u64::from_le_bytes(data[0x2000 + 40..].try_into().unwrap());
+        data[0x2000 + 40..].copy_from_slice(&(saved_at - 2 * 86400).to_le_bytes());
+        fs::write(&sav, &data).unwrap();
+
+        let mut cart = Cartridge::from_file(&rom_path).unwrap();
+        cart.write_byte(0x0000, 0x0A); // enable RAM/RTC
+        cart.write_byte(0x6000, 0x00);
+        cart.write_byte(0x6000, 0x01); // latch
+        cart.write_byte(0x4000, 0x0B); // days low
+        assert_eq!(cart.read_byte(0xA000), 2);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_mbc3_vba_save_keeps_vba_layout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x0147] = 0x10; // MBC3+TIMER+RAM+BATTERY
+        rom[0x0149] = 0x02;
+        let rom_path = dir.join("rtc.gb");
+        fs::write(&rom_path, &rom).unwrap();
+
+        let mut data = vec![0u8; 0x2000];
+        data.extend_from_slice(&vba_rtc_block(2, unix_now() as u32));
+        let sav = sav_path(rom_path.to_str().unwrap());
+        fs::create_dir_all(sav.parent().unwrap()).unwrap();
+        fs::write(&sav, &data).unwrap();
+
+        let cart = Cartridge::from_file(&rom_path).unwrap();
+        assert_eq!(cart.detect_sav_format(&sav), SavFormat::Vba);
+        cart.save().unwrap();
+        let data = fs::read(&sav).unwrap();
+        assert_eq!(data.len(), 0x2000 + 44);
+        let mut rtc = import_rtc_vba(data[0x2000..].try_into().unwrap());
+        rtc.latch();
+        assert_eq!(rtc.read(0x0B), 2);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_mbc3_loads_old_mgba_rtc_block() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x0147] = 0x10; // MBC3+TIMER+RAM+BATTERY
+        rom[0x0149] = 0x02;
+        let rom_path = dir.join("rtc.gb");
+        fs::write(&rom_path, &rom).unwrap();
+
+        // mGBA block saved ten days ago with the day counter at 2
+        let saved_at = unix_now() - 10 * 86400;
+        let mut block = [0u8; 48];
+        block[..44].copy_from_slice(&vba_rtc_block(2, 0));
+        block[40..48].copy_from_slice(&saved_at.to_le_bytes());
+        let mut data = vec![0u8; 0x2000];
+        data.extend_from_slice(&block);
+        let sav = sav_path(rom_path.to_str().unwrap());
+        fs::create_dir_all(sav.parent().unwrap()).unwrap();
+        fs::write(&sav, &data).unwrap();
+        let file = fs::File::options().write(true).open(&sav).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(saved_at)).unwrap();
+
+        let mut cart = Cartridge::from_file(&rom_path).unwrap();
+        cart.write_byte(0x0000, 0x0A); // enable RAM/RTC
+        cart.write_byte(0x6000, 0x00);
+        cart.write_byte(0x6000, 0x01); // latch
+        cart.write_byte(0x4000, 0x0B); // days low
+        assert_eq!(cart.read_byte(0xA000), 12);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_mbc3_migrates_baseline_rtc_block() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x0147] = 0x10; // MBC3+TIMER+RAM+BATTERY
+        rom[0x0149] = 0x02;
+        let rom_path = dir.join("rtc.gb");
+        fs::write(&rom_path, &rom).unwrap();
+
+        // Baseline Rtc::to_bytes block: the clock started five days ago and
+        // the game set the day counter to 2 three days ago, latching since
+        let now = unix_now();
+        let legacy = Rtc {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days_low: 2,
+            days_high: 0,
+            latched: [0, 0, 0, 4, 0],
+            base_timestamp: now - 5 * 86400,
+        };
+        let mut data = vec![0u8; 0x2000];
+        data.extend_from_slice(&legacy.to_bytes());
+        let sav = sav_path(rom_path.to_str().unwrap());
+        fs::create_dir_all(sav.parent().unwrap()).unwrap();
+        fs::write(&sav, &data).unwrap();
+
+        // The clock still reads five days, as it did in the baseline
+        let mut cart = Cartridge::from_file(&rom_path).unwrap();
+        cart.write_byte(0x0000, 0x0A); // enable RAM/RTC
+        cart.write_byte(0x4000, 0x0B); // days low
+        assert_eq!(cart.read_byte(0xA000), 4, "latched value kept");
+        cart.write_byte(0x6000, 0x00);
+        cart.write_byte(0x6000, 0x01); // latch
+        assert_eq!(cart.read_byte(0xA000), 5);
+
+        // Saving rewrites it in the mGBA layout, which reads back the same
+        cart.save().unwrap();
+        let data = fs::read(&sav).unwrap();
+        let mut rtc = import_rtc_mgba(data[0x2000..].try_into().unwrap());
+        rtc.latch();
+        assert_eq!(rtc.read(0x0B), 5);
+        let mut cart = Cartridge::from_file(&rom_path).unwrap();
+        cart.write_byte(0x0000, 0x0A);
+        cart.write_byte(0x6000, 0x00);
+        cart.write_byte(0x6000, 0x01);
+        cart.write_byte(0x4000, 0x0B);
+        assert_eq!(cart.read_byte(0xA000), 5);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_mbc2_unpack_accepts_unpacked_layout() {
+        let data: Vec<u8> = (0..MBC2_RAM_SIZE).map(|i| (i % 16) as u8).collect();
+        let mut ram = vec![0u8; MBC2_RAM_SIZE];
+        unpack_mbc2_ram(&data, &mut ram);
+        assert_eq!(ram[17], 0xF1);
+        assert_eq!(pack_mbc2_ram(&ram)[0], 0x01);
+    }
+
+    #[test]
+    fn test_mbc2_banking_and_nibble_ram() {
+        let mut rom = vec![0u8; 16 * 0x4000];
+        rom[5 * 0x4000] = 0x55;
+        let mut cart = Cartridge {
+            rom,
+            ram: vec![0xF0; MBC2_RAM_SIZE],
+            accessed_rom_banks: vec![false; 16],
+            ..cartridge_with_type(0x06)
+        };
+
+        // Bit 8 set: ROM bank select
+        cart.write_byte(0x2100, 0x05);
+        assert_eq!(cart.active_rom_bank(), 5);
+        assert_eq!(cart.read_byte(0x4000), 0x55);
+        cart.write_byte(0x2100, 0x00);
+        assert_eq!(cart.active_rom_bank(), 1);
+
+        // RAM disabled until enabled with bit 8 clear
+        assert_eq!(cart.read_byte(0xA000), 0xFF);
+        cart.write_byte(0x0100, 0x0A);
+        assert_eq!(cart.read_byte(0xA000), 0xFF);
+        cart.write_byte(0x0000, 0x0A);
+        cart.write_byte(0xA003, 0xAB);
+        assert_eq!(cart.read_byte(0xA003), 0xFB);
+        // 512-byte mirror
+        assert_eq!(cart.read_byte(0xA203), 0xFB);
+        assert_eq!(cart.read_byte(0xBE03), 0xFB);
+
+        cart.write_byte(0x0000, 0x00);
+        assert_eq!(cart.read_byte(0xA003), 0xFF);
+    }
+
+    #[test]
+    fn test_mbc6_independent_windows() {
+        let mut rom = vec![0u8; 16 * 0x2000];
+        for bank in 0..16 {
+            rom[bank * 0x2000] = bank as u8;
+        }
+        let mut cart = Cartridge {
+            rom,
+            ram: vec![0; 0x2000],
+            ..cartridge_with_type(0x20)
+        };
+
+        cart.write_byte(0x2000, 0x07);
+        cart.write_byte(0x3000, 0x0C);
+        assert_eq!(cart.read_byte(0x4000), 0x07);
+        assert_eq!(cart.read_byte(0x6000), 0x0C);
+
+        // Mapping bank A to flash leaves bank B on ROM
+        cart.write_byte(0x2800, 0x08);
+        assert_eq!(cart.read_byte(0x4000), 0xFF);
+        assert_eq!(cart.read_byte(0x6000), 0x0C);
+
+        // 4 KB RAM windows are banked separately
+        cart.write_byte(0x0000, 0x0A);
+        cart.write_byte(0x0400, 0x01);
+        cart.write_byte(0x0800, 0x01);
+        cart.write_byte(0xA010, 0x5A);
+        assert_eq!(cart.read_byte(0xB010), 0x5A);
+        assert_eq!(cart.ram[0x1010], 0x5A);
+    }
+
+    #[test]
+    fn test_mbc3_unmapped_ram_bank() {
+        let mut cart = Cartridge {
+            ram: vec![0x42; 4 * 0x2000],
+            ..cartridge_with_type(0x13)
+        };
+        cart.write_byte(0x0000, 0x0A); // enable RAM
+        cart.write_byte(0x4000, 0x05);
+        assert_eq!(cart.read_byte(0xA000), 0xFF);
+        cart.write_byte(0xA000, 0x99);
+        assert!(cart.ram.iter().all(|&b| b == 0x42));
+
+        cart.write_byte(0x4000, 0x01);
+        assert_eq!(cart.read_byte(0xA000), 0x42);
+    }
+
+    #[test]
+    fn test_accessed_rom_banks_tracks_reads() {
+        let mut cart = Cartridge {
+            rom: vec![0; 16 * 0x4000],
+            accessed_rom_banks: vec![false; 16],
+            ..cartridge_with_type(0x01)
+        };
+        for bank in [1u8, 3, 5] {
+            cart.write_byte(0x2000, bank);
+            cart.record_rom_read(0x4000);
+        }
+        cart.record_rom_read(0x0000); // bank 0 is not tracked
+        assert_eq!(cart.active_rom_bank(), 5);
+
+        let accessed = cart.accessed_rom_banks();
+        assert_eq!(accessed.len(), 16);
+        assert!(accessed[1] && accessed[3] && accessed[5]);
+        assert!(!accessed[2]);
+    }
+
+    #[test]
+    fn test_info_decodes_header() {
+        let mut cart = cartridge_with_type(0x13);
+        cart.rom[0x0147] = 0x13;
+        cart.rom[0x0148] = 0x05; // 1 MiB
+        cart.rom[0x0149] = 0x03; // 32 KiB
+        cart.rom[0x014D] = 0xCC; // x = x - byte - 1 over $0134-$014C
+        cart.title = "POKEMON".into();
+
+        let info = cart.info();
+        assert_eq!(info.cartridge_type_name, "MBC3+RAM+BATTERY");
+        assert_eq!(info.title, "POKEMON");
+        assert_eq!(info.rom_size, 1024 * 1024);
+        assert_eq!(info.ram_size, 32 * 1024);
+        assert!(info.header_checksum_valid);
+
+        cart.rom[0x014D] = 0x00;
+        assert!(!cart.info().header_checksum_valid);
+
+        // Unofficial and garbage size codes must not overflow the shift
+        cart.rom[0x0148] = 0x52;
+        assert_eq!(cart.info().rom_size, 72 * 16 * 1024);
+        cart.rom[0x0148] = 0xFF;
+        assert_eq!(cart.info().rom_size, 0);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_flags_check_header_logo_and_global_checksum() {
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x0104..0x0134].copy_from_slice(&NINTENDO_LOGO);
+        rom[0x0134..0x013A].copy_from_slice(b"TETRIS");
+        rom[0x014D] = header_checksum(&rom);
+        let sum = global_checksum(&rom);
+        rom[0x014E..0x0150].copy_from_slice(&sum.to_be_bytes());
+
+        let cart = Cartridge::from_bytes(&rom).unwrap();
+        assert_eq!(cart.flags, CartridgeFlags {
+            has_valid_header: true,
+            has_valid_logo: true,
+            has_valid_global_checksum: true,
+        });
+        assert!(cart.check_header(true).is_ok());
+
+        rom[0x0110] ^= 0xFF;
+        rom[0x014D] ^= 0x01;
+        let cart = Cartridge::from_bytes(&rom).unwrap();
+        assert!(!cart.flags.has_valid_header);
+        assert!(!cart.flags.has_valid_logo);
+        assert!(!cart.flags.has_valid_global_checksum);
+        assert_eq!(
+            cart.check_header(true).unwrap_err(),
+            "Invalid ROM header: header checksum mismatch, Nintendo logo mismatch"
+        );
+        assert!(cart.check_header(false).is_ok(), "only a warning when not strict");
+    }
+
+    #[test]
+    fn test_debug_mbc_describes_rom_bank_write() {
+        let mut cart = cartridge_with_type(0x01);
+        cart.debug_mbc = true;
+        let msg = cart.describe_mbc_write(0x2000, 0x05);
+        assert!(msg.contains("ROM bank"), "unexpected message: {}", msg);
+        cart.write_byte(0x2000, 0x05);
+        assert_eq!(cart.describe_mbc_write(0x0000, 0x0A), "[MBC] $0000 MBC1 RAM enable: 0x0A");
+    }
 }
@@ -1,15 +1,87 @@
+#[cfg(feature = "native")]
 use minifb::Key;
 use serde::{Serialize, Deserialize};
+#[cfg(feature = "native")]
 use std::path::PathBuf;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Config {
     pub controls: Controls,
     pub display: Display,
     pub speed: Speed,
+    #[serde(default)]
+    pub rewind: RewindConfig,
+    #[serde(default)]
+    pub audio: AudioConfig,
+    #[serde(default)]
+    pub gamepad: GamepadConfig,
+    /// Log writes to cartridge MBC registers (also enabled by --debug-mbc)
+    #[serde(default)]
+    pub debug_mbc: bool,
+    /// Extra palettes from `[[palette]]` tables, appended to the built-in list
+    #[serde(default, rename = "palette", skip_serializing_if = "Vec::is_empty")]
+    pub palettes: Vec<PaletteConfig>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[cfg(feature = "native")]
+/// Length of `RecentRoms::paths`
+pub const MAX_RECENT_ROMS: usize = 10;
+
+/// Most recently opened ROMs, newest first; offered when started without a
+/// ROM. Kept in `recent_roms.txt`, one path per line, so updating the list
+/// never rewrites the user's config.toml.
+#[cfg(feature = "native")]
+#[derive(Default)]
+pub struct RecentRoms {
+    pub paths: Vec<PathBuf>,
+}
+
+#[cfg(feature = "native")]
+impl RecentRoms {
+    fn path() -> PathBuf {
+        config_dir().join("recent_roms.txt")
+    }
+
+    /// The saved list, or an empty one if there is none yet.
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::path()).map_or_else(|_| RecentRoms::default(), |text| Self::parse(&text))
+    }
+
+    fn parse(text: &str) -> Self {
+        let paths = text.lines().filter(|line| !line.trim().is_empty()).map(PathBuf::from).take(MAX_RECENT_ROMS).collect();
+        RecentRoms { paths }
+    }
+
+    fn to_text(&self) -> String {
+        self.paths.iter().map(|p| format!("{}\n", p.display())).collect()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Error creating config directory: {}", e))?;
+        }
+        std::fs::write(&path, self.to_text()).map_err(|e| format!("Error writing {}: {}", path.display(), e))
+    }
+
+    /// Move `path` to the front of the list, dropping the oldest entries
+    /// past `MAX_RECENT_ROMS`.
+    pub fn add(&mut self, path: PathBuf) {
+        self.paths.retain(|p| *p != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(MAX_RECENT_ROMS);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PaletteConfig {
+    pub name: String,
+    /// Four "#RRGGBB" strings, lightest to darkest
+    pub colors: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Controls {
     pub up: String,
     pub down: String,
@@ -21,19 +93,163 @@ pub struct Controls {
     pub start: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Display {
     pub scale: String,
     pub palette: String,
     pub scanlines: bool,
+    /// Draw the FPS counter over the top-left corner of the screen
+    #[serde(default)]
+    pub show_fps: bool,
+    /// Four "#RRGGBB" strings for the "Custom" palette edited in the F7 window
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_palette: Vec<String>,
+    #[serde(default)]
+    pub color_correction: ColorCorrection,
+    #[serde(default)]
+    pub scale_filter: ScaleFilter,
+    /// Curved-screen and bloom post-processing; off when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crt_filter: Option<CrtFilterConfig>,
+    /// Fixed window size with the screen letterboxed inside it; replaces `scale`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixed_window: Option<FixedWindowMode>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FixedWindowMode {
+    pub width: usize,
+    pub height: usize,
+    /// "#RRGGBB" fill around the screen
+    #[serde(default = "default_border_color")]
+    pub border_color: String,
+}
+
+fn default_border_color() -> String {
+    "#000000".into()
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CrtFilterConfig {
+    /// Barrel distortion strength, 0.0 (flat) to 0.5
+    pub curvature: f32,
+    /// Phosphor bloom radius in pixels, 0 (off) to 8
+    pub glow_radius: u8,
+}
+
+impl Default for CrtFilterConfig {
+    fn default() -> Self {
+        CrtFilterConfig { curvature: 0.1, glow_radius: 2 }
+    }
+}
+
+/// How the 320x288 frame is enlarged to the window size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScaleFilter {
+    /// Hard pixel edges, scaled by minifb
+    #[default]
+    Nearest,
+    /// Smoothed edges, scaled in software to the window size
+    Bilinear,
+}
+
+/// LCD response applied to the palette colors before display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorCorrection {
+    /// Palette colors as-is
+    #[default]
+    None,
+    /// Original DMG panel: dim, low contrast, green-tinted
+    Dmg,
+    /// CGB panel: desaturated with some channel bleed
+    Cgb,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Speed {
     /// 0 = uncapped, 2 = 2x, 4 = 4x, etc.
     pub fast_forward_multiplier: u32,
 }
 
+/// Off by default: the snapshot history costs memory and a little speed.
+/// Missing keys in a partial `[rewind]` table take the defaults.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RewindConfig {
+    pub enabled: bool,
+    /// Memory budget for the snapshot history
+    pub max_megabytes: usize,
+}
+
+impl Default for RewindConfig {
+    fn default() -> Self {
+        RewindConfig { enabled: false, max_megabytes: 64 }
+    }
+}
+
+/// Gamepad button for each Game Boy button, using gilrs `Button` names
+/// ("South", "East", "DPadUp", ...). Applied alongside `[controls]`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadConfig {
+    pub up: String,
+    pub down: String,
+    pub left: String,
+    pub right: String,
+    pub a: String,
+    pub b: String,
+    pub select: String,
+    pub start: String,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        GamepadConfig {
+            up: "DPadUp".into(),
+            down: "DPadDown".into(),
+            left: "DPadLeft".into(),
+            right: "DPadRight".into(),
+            a: "East".into(),
+            b: "South".into(),
+            select: "Select".into(),
+            start: "Start".into(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AudioConfig {
+    /// Block DC on the output like the hardware's coupling capacitor
+    pub high_pass_filter: bool,
+    /// Output device name as reported by the OS; empty = system default
+    #[serde(default)]
+    pub device: String,
+    /// Range the queued audio is tuned within, in frames (0.5-8)
+    #[serde(default = "default_min_latency_frames")]
+    pub min_latency_frames: f32,
+    #[serde(default = "default_max_latency_frames")]
+    pub max_latency_frames: f32,
+}
+
+fn default_min_latency_frames() -> f32 {
+    0.5
+}
+
+fn default_max_latency_frames() -> f32 {
+    8.0
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig {
+            high_pass_filter: true,
+            device: String::new(),
+            min_latency_frames: default_min_latency_frames(),
+            max_latency_frames: default_max_latency_frames(),
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -51,28 +267,57 @@ impl Default for Config {
                 scale: "4x".into(),
                 palette: "Classic".into(),
                 scanlines: false,
+                show_fps: false,
+                custom_palette: Vec::new(),
+                color_correction: ColorCorrection::None,
+                scale_filter: ScaleFilter::Nearest,
+                crt_filter: None,
+                fixed_window: None,
             },
             speed: Speed {
                 fast_forward_multiplier: 0,
             },
+            rewind: RewindConfig::default(),
+            audio: AudioConfig::default(),
+            gamepad: GamepadConfig::default(),
+            debug_mbc: false,
+            palettes: Vec::new(),
         }
     }
 }
 
+#[cfg(feature = "native")]
+/// `contents` with `display.custom_palette` set to `colors`.
+fn set_custom_palette(contents: &str, colors: &[String]) -> Result<String, String> {
+    let mut doc = contents.parse::<toml_edit::DocumentMut>().map_err(|e| format!("Error parsing config: {}", e))?;
+    let array: toml_edit::Array = colors.iter().map(String::as_str).collect();
+    doc["display"]["custom_palette"] = toml_edit::value(array);
+    Ok(doc.to_string())
+}
+
+#[cfg(feature = "native")]
+fn config_dir() -> PathBuf {
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("gb_rust")
+}
+
 impl Config {
+    #[cfg(feature = "native")]
     fn config_path() -> PathBuf {
-        let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
-        path.push("gb_rust");
-        path.push("config.toml");
-        path
+        config_dir().join("config.toml")
     }
 
+    #[cfg(feature = "native")]
     pub fn load() -> Self {
         let path = Self::config_path();
         if path.exists() {
             match std::fs::read_to_string(&path) {
-                Ok(contents) => match toml::from_str(&contents) {
-                    Ok(config) => return config,
+                Ok(contents) => match toml::from_str::<Config>(&contents) {
+                    Ok(mut config) => {
+                        for msg in config.validate_and_fix() {
+                            eprintln!("Config: {}", msg);
+                        }
+                        return config;
+                    }
                     Err(e) => eprintln!("Error parsing {}: {}; using defaults", path.display(), e),
                 },
                 Err(e) => eprintln!("Error reading {}: {}; using defaults", path.display(), e),
@@ -85,22 +330,191 @@ impl Config {
         Config::default()
     }
 
+    #[cfg(feature = "native")]
     fn write_defaults(&self) {
+        match self.save() {
+            Ok(()) => eprintln!("Wrote default config to {}", Self::config_path().display()),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    /// Write the config file, creating its directory if needed.
+    #[cfg(feature = "native")]
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::config_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Error creating config directory: {}", e))?;
+        }
+        let contents = toml::to_string_pretty(self).map_err(|e| format!("Error serializing config: {}", e))?;
+        std::fs::write(&path, contents).map_err(|e| format!("Error writing {}: {}", path.display(), e))
+    }
+
+    /// Store the F7 custom palette as `display.custom_palette`, editing only
+    /// that key so the rest of config.toml (and its comments) stays as written.
+    #[cfg(feature = "native")]
+    pub fn save_custom_palette(colors: &[String]) -> Result<(), String> {
         let path = Self::config_path();
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => toml::to_string_pretty(&Config::default()).map_err(|e| format!("Error serializing config: {}", e))?,
+        };
+        let contents = set_custom_palette(&contents, colors)?;
         if let Some(parent) = path.parent() {
-            if let Err(e) = std::fs::create_dir_all(parent) {
-                eprintln!("Error creating config directory: {}", e);
-                return;
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Error creating config directory: {}", e))?;
+        }
+        std::fs::write(&path, contents).map_err(|e| format!("Error writing {}: {}", path.display(), e))
+    }
+
+    /// Replace out-of-range values with defaults. Returns one message per fix.
+    pub fn validate_and_fix(&mut self) -> Vec<String> {
+        let defaults = Config::default();
+        let mut fixes = Vec::new();
+
+        if !matches!(self.display.scale.as_str(), "2x" | "4x" | "8x") {
+            // A number off the steps clamps to the nearest one; anything else
+            // falls back to the default
+            let fixed = match self.display.scale.trim().trim_end_matches(['x', 'X']).parse::<f32>() {
+                Ok(n) => {
+                    let nearest = [2.0, 4.0, 8.0f32]
+                        .into_iter()
+                        .min_by(|a, b| (a - n).abs().total_cmp(&(b - n).abs()))
+                        .unwrap();
+                    format!("{}x", nearest)
+                }
+                Err(_) => defaults.display.scale.clone(),
+            };
+            fixes.push(format!(
+                "display.scale '{}' is not one of 2x/4x/8x; using '{}'",
+                self.display.scale, fixed
+            ));
+            self.display.scale = fixed;
+        }
+
+        if self.rewind.max_megabytes == 0 {
+            fixes.push(format!(
+                "rewind.max_megabytes must be at least 1; using {}",
+                defaults.rewind.max_megabytes
+            ));
+            self.rewind.max_megabytes = defaults.rewind.max_megabytes;
+        }
+
+        let audio = &self.audio;
+        let range = default_min_latency_frames()..=default_max_latency_frames();
+        if !range.contains(&audio.min_latency_frames) || !range.contains(&audio.max_latency_frames)
+            || audio.min_latency_frames > audio.max_latency_frames
+        {
+            fixes.push(format!(
+                "audio latency range {}-{} must lie within 0.5-8 frames; using 0.5-8",
+                audio.min_latency_frames, audio.max_latency_frames
+            ));
+            self.audio.min_latency_frames = default_min_latency_frames();
+            self.audio.max_latency_frames = default_max_latency_frames();
+        }
+
+        if let Some(crt) = &mut self.display.crt_filter {
+            let default_crt = CrtFilterConfig::default();
+            if !(0.0..=0.5).contains(&crt.curvature) {
+                fixes.push(format!(
+                    "display.crt_filter.curvature must be 0.0-0.5; using {}",
+                    default_crt.curvature
+                ));
+                crt.curvature = default_crt.curvature;
+            }
+            if crt.glow_radius > 8 {
+                fixes.push(format!(
+                    "display.crt_filter.glow_radius must be 0-8; using {}",
+                    default_crt.glow_radius
+                ));
+                crt.glow_radius = default_crt.glow_radius;
             }
         }
-        let contents = toml::to_string_pretty(self).expect("Failed to serialize config");
-        if let Err(e) = std::fs::write(&path, contents) {
-            eprintln!("Error writing {}: {}", path.display(), e);
-        } else {
-            eprintln!("Wrote default config to {}", path.display());
+
+        if let Some(fixed) = &mut self.display.fixed_window {
+            if fixed.width < 160 || fixed.height < 144 {
+                fixes.push(format!(
+                    "display.fixed_window {}x{} is smaller than the 160x144 screen; using 160x144 minimum",
+                    fixed.width, fixed.height
+                ));
+                fixed.width = fixed.width.max(160);
+                fixed.height = fixed.height.max(144);
+            }
+            if crate::filters::parse_hex_color(&fixed.border_color).is_err() {
+                fixes.push(format!("display.fixed_window.border_color '{}' is not #RRGGBB; using black", fixed.border_color));
+                fixed.border_color = default_border_color();
+            }
+        }
+
+        let before = self.palettes.len();
+        self.palettes.retain(|p| {
+            p.colors.len() == 4 && p.colors.iter().all(|c| crate::filters::parse_hex_color(c).is_ok())
+        });
+        if self.palettes.len() != before {
+            fixes.push(format!("dropped {} [[palette]] entries with invalid colors", before - self.palettes.len()));
+        }
+
+        let custom = &self.display.custom_palette;
+        if !custom.is_empty()
+            && (custom.len() != 4 || !custom.iter().all(|c| crate::filters::parse_hex_color(c).is_ok()))
+        {
+            fixes.push("display.custom_palette must be 4 \"#RRGGBB\" colors; ignoring it".to_string());
+            self.display.custom_palette.clear();
         }
+
+        let known_palette = crate::filters::PALETTES.iter().any(|(name, _)| *name == self.display.palette)
+            || self.palettes.iter().any(|p| p.name == self.display.palette)
+            || (self.display.palette == crate::filters::CUSTOM_PALETTE && !self.display.custom_palette.is_empty());
+        if !known_palette {
+            fixes.push(format!(
+                "display.palette '{}' is unknown; using '{}'",
+                self.display.palette, defaults.display.palette
+            ));
+            self.display.palette = defaults.display.palette.clone();
+        }
+
+        #[cfg(feature = "native")]
+        let controls = [
+            ("up", &mut self.controls.up, &defaults.controls.up),
+            ("down", &mut self.controls.down, &defaults.controls.down),
+            ("left", &mut self.controls.left, &defaults.controls.left),
+            ("right", &mut self.controls.right, &defaults.controls.right),
+            ("a", &mut self.controls.a, &defaults.controls.a),
+            ("b", &mut self.controls.b, &defaults.controls.b),
+            ("select", &mut self.controls.select, &defaults.controls.select),
+            ("start", &mut self.controls.start, &defaults.controls.start),
+        ];
+        #[cfg(feature = "native")]
+        for (field, value, default) in controls {
+            if key_name_to_minifb(value).is_none() {
+                fixes.push(format!("controls.{} '{}' is not a known key; using '{}'", field, value, default));
+                *value = default.clone();
+            }
+        }
+
+        #[cfg(feature = "native")]
+        let gamepad = [
+            ("up", &mut self.gamepad.up, &defaults.gamepad.up),
+            ("down", &mut self.gamepad.down, &defaults.gamepad.down),
+            ("left", &mut self.gamepad.left, &defaults.gamepad.left),
+            ("right", &mut self.gamepad.right, &defaults.gamepad.right),
+            ("a", &mut self.gamepad.a, &defaults.gamepad.a),
+            ("b", &mut self.gamepad.b, &defaults.gamepad.b),
+            ("select", &mut self.gamepad.select, &defaults.gamepad.select),
+            ("start", &mut self.gamepad.start, &defaults.gamepad.start),
+        ];
+        #[cfg(feature = "native")]
+        for (field, value, default) in gamepad {
+            if button_name_to_gilrs(value).is_none() {
+                fixes.push(format!("gamepad.{} '{}' is not a known button; using '{}'", field, value, default));
+                *value = default.clone();
+            }
+        }
+
+        fixes
     }
 
+
     pub fn scale_index(&self) -> usize {
         match self.display.scale.as_str() {
             "2x" => 0,
@@ -116,10 +530,12 @@ impl Config {
             "DMG Green" => 1,
             "Grayscale" => 2,
             "Pocket" => 3,
+            "GBL" => 4,
             _ => 0,
         }
     }
 
+    #[cfg(feature = "native")]
     pub fn joypad_key_map(&self) -> Vec<(Key, crate::joypad::JoypadKey)> {
         use crate::joypad::JoypadKey;
         let pairs = [
@@ -136,8 +552,45 @@ impl Config {
             key_name_to_minifb(name).map(|k| (k, *jk))
         }).collect()
     }
+
+    #[cfg(feature = "native")]
+    pub fn gamepad_button_map(&self) -> Vec<(gilrs::Button, crate::joypad::JoypadKey)> {
+        use crate::joypad::JoypadKey;
+        let pairs = [
+            (&self.gamepad.right, JoypadKey::Right),
+            (&self.gamepad.left, JoypadKey::Left),
+            (&self.gamepad.up, JoypadKey::Up),
+            (&self.gamepad.down, JoypadKey::Down),
+            (&self.gamepad.a, JoypadKey::A),
+            (&self.gamepad.b, JoypadKey::B),
+            (&self.gamepad.select, JoypadKey::Select),
+            (&self.gamepad.start, JoypadKey::Start),
+        ];
+        pairs.iter().filter_map(|(name, jk)| {
+            button_name_to_gilrs(name).map(|b| (b, *jk))
+        }).collect()
+    }
+}
+
+#[cfg(feature = "native")]
+pub fn button_name_to_gilrs(name: &str) -> Option<gilrs::Button> {
+    use gilrs::Button;
+    match name {
+        "South" => Some(Button::South), "East" => Some(Button::East),
+        "North" => Some(Button::North), "West" => Some(Button::West),
+        "C" => Some(Button::C), "Z" => Some(Button::Z),
+        "LeftTrigger" => Some(Button::LeftTrigger), "LeftTrigger2" => Some(Button::LeftTrigger2),
+        "RightTrigger" => Some(Button::RightTrigger), "RightTrigger2" => Some(Button::RightTrigger2),
+        "Select" => Some(Button::Select), "Start" => Some(Button::Start), "Mode" => Some(Button::Mode),
+        "LeftThumb" => Some(Button::LeftThumb), "RightThumb" => Some(Button::RightThumb),
+        "DPadUp" => Some(Button::DPadUp), "DPadDown" => Some(Button::DPadDown),
+        "DPadLeft" => Some(Button::DPadLeft), "DPadRight" => Some(Button::DPadRight),
+        _ => None,
+    }
 }
 
+/// `None` for an unknown name; `validate_and_fix` reports it
+#[cfg(feature = "native")]
 pub fn key_name_to_minifb(name: &str) -> Option<Key> {
     match name {
         "A" => Some(Key::A), "B" => Some(Key::B), "C" => Some(Key::C),
@@ -174,9 +627,209 @@ pub fn key_name_to_minifb(name: &str) -> Option<Key> {
         "Backslash" | "\\" => Some(Key::Backslash),
         "Minus" | "-" => Some(Key::Minus),
         "Equal" | "=" => Some(Key::Equal),
-        _ => {
-            eprintln!("Unknown key name in config: '{}'", name);
-            None
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_set_custom_palette_keeps_other_settings() {
+        let contents = "# my settings\n[display]\nscale = \"8x\"  # big\npalette = \"Custom\"\n\n[speed]\nfast_forward_multiplier = 3\n";
+        let colors: Vec<String> = ["#000000", "#555555", "#AAAAAA", "#FFFFFF"].iter().map(|s| s.to_string()).collect();
+        let updated = set_custom_palette(contents, &colors).unwrap();
+        assert!(updated.starts_with("# my settings\n[display]\nscale = \"8x\"  # big\n"));
+        assert!(updated.contains("fast_forward_multiplier = 3"));
+        let table: toml::Table = toml::from_str(&updated).unwrap();
+        assert_eq!(table["display"]["custom_palette"][2].as_str(), Some("#AAAAAA"));
+    }
+
+    #[test]
+    fn test_validate_and_fix_invalid_scale() {
+        let mut config = Config::default();
+        config.display.scale = "99x".into();
+        let fixes = config.validate_and_fix();
+        assert_eq!(config.display.scale, "8x");
+        assert_eq!(config.scale_index(), 2);
+        assert_eq!(fixes.len(), 1);
+        assert!(fixes[0].contains("display.scale"), "unexpected message: {}", fixes[0]);
+
+        for (scale, expected) in [("16x", "8x"), ("1x", "2x"), ("5", "4x"), ("7x", "8x"), ("huge", "4x")] {
+            config.display.scale = scale.into();
+            config.validate_and_fix();
+            assert_eq!(config.display.scale, expected, "from '{}'", scale);
         }
     }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_validate_and_fix_palette_and_controls() {
+        let mut config = Config::default();
+        config.display.palette = "Missing".into();
+        config.controls.a = "NotAKey".into();
+        config.palettes.push(PaletteConfig { name: "Bad".into(), colors: vec!["#12".into()] });
+
+        let fixes = config.validate_and_fix();
+        assert_eq!(fixes.len(), 3);
+        assert_eq!(config.display.palette, "Classic");
+        assert_eq!(config.controls.a, "Z");
+        assert!(config.palettes.is_empty());
+        assert!(config.validate_and_fix().is_empty());
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_default_controls_map_all_buttons() {
+        let config = Config::default();
+        let expected = [
+            (&config.controls.up, Key::Up),
+            (&config.controls.down, Key::Down),
+            (&config.controls.left, Key::Left),
+            (&config.controls.right, Key::Right),
+            (&config.controls.a, Key::Z),
+            (&config.controls.b, Key::X),
+            (&config.controls.select, Key::Backspace),
+            (&config.controls.start, Key::Enter),
+        ];
+        for (name, key) in expected {
+            assert_eq!(key_name_to_minifb(name), Some(key), "binding '{}'", name);
+        }
+        assert_eq!(config.joypad_key_map().len(), 8);
+        assert_eq!(key_name_to_minifb("Return"), Some(Key::Enter));
+        assert_eq!(key_name_to_minifb("Esc"), Some(Key::Escape));
+        assert_eq!(key_name_to_minifb("NotAKey"), None);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_controls_load_from_toml() {
+        let mut text = toml::to_string(&Config::default()).unwrap();
+        text = text.replace("a = \"Z\"", "a = \"K\"");
+        let config: Config = toml::from_str(&text).unwrap();
+        let keys: Vec<Key> = config.joypad_key_map().iter().map(|&(k, _)| k).collect();
+        assert!(keys.contains(&Key::K));
+        assert!(!keys.contains(&Key::Z));
+    }
+
+    #[test]
+    fn test_default_config_round_trips_through_toml() {
+        let mut config = Config::default();
+        config.speed.fast_forward_multiplier = 4;
+        config.audio.device = "USB Audio".into();
+        let text = toml::to_string_pretty(&config).unwrap();
+        for section in ["[controls]", "[display]", "[speed]", "[rewind]", "[audio]", "[gamepad]"] {
+            assert!(text.contains(section), "missing {}", section);
+        }
+
+        let loaded: Config = toml::from_str(&text).unwrap();
+        assert_eq!(loaded.speed.fast_forward_multiplier, 4);
+        assert_eq!(loaded.audio.device, "USB Audio");
+        assert_eq!(loaded.display.scale, "4x");
+
+        // Configs written before the device setting existed still load
+        let old = text.replace("device = \"USB Audio\"\n", "");
+        let loaded: Config = toml::from_str(&old).unwrap();
+        assert!(loaded.audio.device.is_empty());
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_add_recent_rom_dedupes_and_caps() {
+        let mut recent = RecentRoms::default();
+        for i in 0..12 {
+            recent.add(PathBuf::from(format!("rom{}.gb", i)));
+        }
+        assert_eq!(recent.paths.len(), MAX_RECENT_ROMS);
+        assert_eq!(recent.paths[0], PathBuf::from("rom11.gb"));
+
+        recent.add(PathBuf::from("rom5.gb"));
+        assert_eq!(recent.paths.len(), MAX_RECENT_ROMS);
+        assert_eq!(recent.paths[0], PathBuf::from("rom5.gb"));
+        assert_eq!(recent.paths.iter().filter(|p| p.ends_with("rom5.gb")).count(), 1);
+
+        let loaded = RecentRoms::parse(&recent.to_text());
+        assert_eq!(loaded.paths, recent.paths);
+        assert!(!toml::to_string(&Config::default()).unwrap().contains("recent"));
+    }
+
+    #[test]
+    fn test_crt_filter_config_is_optional_and_validated() {
+        let text = toml::to_string(&Config::default()).unwrap();
+        assert!(!text.contains("crt_filter"));
+
+        let text = text.replace("[display]\n", "[display.crt_filter]\ncurvature = 2.0\nglow_radius = 3\n\n[display]\n");
+        let mut config: Config = toml::from_str(&text).unwrap();
+        assert_eq!(config.validate_and_fix().len(), 1);
+        let crt = config.display.crt_filter.as_ref().unwrap();
+        assert_eq!(crt.curvature, CrtFilterConfig::default().curvature);
+        assert_eq!(crt.glow_radius, 3);
+    }
+
+    #[test]
+    fn test_audio_latency_range_validation() {
+        let text = toml::to_string(&Config::default()).unwrap();
+        assert!(text.contains("max_latency_frames = 8.0"));
+        let text = text.replace("min_latency_frames = 0.5", "min_latency_frames = 4.0");
+        let mut config: Config = toml::from_str(&text).unwrap();
+        assert!(config.validate_and_fix().is_empty());
+        assert_eq!(config.audio.min_latency_frames, 4.0);
+
+        config.audio.max_latency_frames = 2.0;
+        assert_eq!(config.validate_and_fix().len(), 1, "min above max");
+        assert_eq!((config.audio.min_latency_frames, config.audio.max_latency_frames), (0.5, 8.0));
+    }
+
+    #[test]
+    fn test_fixed_window_config_validation() {
+        let text = toml::to_string(&Config::default()).unwrap();
+        let text = text.replace("[display]\n", "[display.fixed_window]\nwidth = 100\nheight = 720\n\n[display]\n");
+        let mut config: Config = toml::from_str(&text).unwrap();
+        assert_eq!(config.display.fixed_window.as_ref().unwrap().border_color, "#000000");
+
+        config.display.fixed_window.as_mut().unwrap().border_color = "red".into();
+        assert_eq!(config.validate_and_fix().len(), 2);
+        let fixed = config.display.fixed_window.as_ref().unwrap();
+        assert_eq!((fixed.width, fixed.height), (160, 720));
+        assert_eq!(fixed.border_color, "#000000");
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_gamepad_config_validation() {
+        let text = toml::to_string(&Config::default()).unwrap();
+        let text = text.replace("[gamepad]\n", "[gamepad]\na = \"Turbo\"\n");
+        let text = text.replacen("a = \"East\"\n", "", 1);
+        let mut config: Config = toml::from_str(&text).unwrap();
+        assert_eq!(config.gamepad.start, "Start");
+
+        let fixes = config.validate_and_fix();
+        assert_eq!(fixes.len(), 1);
+        assert!(fixes[0].contains("gamepad.a"));
+        assert_eq!(config.gamepad.a, "East");
+        assert!(config.gamepad_button_map().contains(&(gilrs::Button::East, crate::joypad::JoypadKey::A)));
+    }
+
+    #[test]
+    fn test_rewind_config_defaults_when_missing() {
+        let mut text = toml::to_string(&Config::default()).unwrap();
+        let start = text.find("[rewind]").unwrap();
+        let end = text[start..].find("\n\n").map_or(text.len(), |i| start + i + 2);
+        text.replace_range(start..end, "");
+        let mut config: Config = toml::from_str(&text).unwrap();
+        assert!(!config.rewind.enabled);
+
+        // A partial table keeps the defaults for the missing keys
+        text.push_str("\n[rewind]\nenabled = true\n");
+        let config_partial: Config = toml::from_str(&text).unwrap();
+        assert!(config_partial.rewind.enabled);
+        assert_eq!(config_partial.rewind.max_megabytes, 64);
+
+        config.rewind.max_megabytes = 0;
+        assert_eq!(config.validate_and_fix().len(), 1);
+        assert_eq!(config.rewind.max_megabytes, 64);
+    }
 }
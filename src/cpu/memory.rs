@@ -3,11 +3,35 @@ use crate::timer::Timer;
 use crate::ppu::Ppu;
 use crate::joypad::Joypad;
 use crate::apu::Apu;
+use crate::cpu::HardwareModel;
+use crate::breakpoint::Watchpoint;
+use crate::printer::Printer;
+
+/// Callback invoked with (address, value) after a hooked write.
+pub type WriteHook = fn(u16, u8);
+
+/// What the CGB infrared port ($FF56) is connected to. A link to a second
+/// emulator instance is out of scope; loopback is enough to exercise the RP
+/// register protocol.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IrConfig {
+    /// Nothing in range: the receive bit never changes
+    #[default]
+    Disconnected,
+    /// Reflect the LED bit straight back into the receive bit
+    Loopback,
+}
 
 pub struct MemoryBus {
     pub cartridge: Cartridge,
-    pub vram: [u8; 0x2000],
-    pub wram: [u8; 0x2000],
+    /// Two 8 KB VRAM banks; bank 1 (tile attributes, extra tiles) is CGB only
+    pub vram: [[u8; 0x2000]; 2],
+    /// VBK ($FF4F): bank mapped at $8000-$9FFF
+    pub vram_bank: u8,
+    /// 4 KB WRAM banks: bank 0 at $C000, `wram_bank` at $D000 (DMG: always 1)
+    pub wram: [[u8; 0x1000]; 8],
+    /// SVBK ($FF70), 1-7
+    pub wram_bank: u8,
     pub oam: [u8; 0xA0],
     pub io: [u8; 0x80],
     pub hram: [u8; 0x7F],
@@ -18,14 +42,44 @@ pub struct MemoryBus {
     pub joypad: Joypad,
     pub apu: Apu,
     pub cycles_ticked: u8,
+    pub model: HardwareModel,
+    /// OAM DMA ($FF46): one byte copied per M-cycle, 160 in total
+    pub oam_dma_active: bool,
+    pub oam_dma_source: u8,
+    pub oam_dma_cycles_remaining: u16,
+    /// CGB VRAM DMA ($FF51-$FF55): source/destination of the next block,
+    /// blocks remaining minus one, and whether HBlank DMA is running
+    pub hdma_source: u16,
+    pub hdma_dest: u16,
+    pub hdma_length: u8,
+    pub hdma_active: bool,
+    /// Bytes sent over the serial port (test ROMs print results this way)
+    pub serial_output: Vec<u8>,
+    /// Game Boy Printer on the link port; takes the place of `serial_output`
+    pub printer: Option<Printer>,
+    /// CGB infrared port RP ($FF56): bit 0 = LED, bit 1 = receive, bits 6-7 = read enable
+    pub ir_register: u8,
+    /// KEY1 ($FF4D): bit 0 = speed switch armed, bit 7 = double speed
+    pub key1_prepare: bool,
+    /// CPU runs at twice the PPU/APU rate; mirrored into `Timer::double_speed`
+    pub double_speed: bool,
+    pub ir_config: IrConfig,
+    /// Debugger watchpoints, checked on CPU reads and writes
+    pub watchpoints: Vec<Watchpoint>,
+    /// The watchpoint that last matched; cleared by the debugger
+    pub watchpoint_hit: Option<Watchpoint>,
+    // Scripting hooks called after a write to a matching address
+    write_hooks: Vec<(u16, WriteHook)>,
 }
 
 impl MemoryBus {
     pub fn new(cartridge: Cartridge) -> Self {
         MemoryBus {
             cartridge,
-            vram: [0; 0x2000],
-            wram: [0; 0x2000],
+            vram: [[0; 0x2000]; 2],
+            vram_bank: 0,
+            wram: [[0; 0x1000]; 8],
+            wram_bank: 1,
             oam: [0; 0xA0],
             io: [0; 0x80],
             hram: [0; 0x7F],
@@ -36,6 +90,54 @@ impl MemoryBus {
             joypad: Joypad::default(),
             apu: Apu::default(),
             cycles_ticked: 0,
+            model: HardwareModel::Dmg,
+            oam_dma_active: false,
+            oam_dma_source: 0,
+            oam_dma_cycles_remaining: 0,
+            hdma_source: 0,
+            hdma_dest: 0,
+            hdma_length: 0xFF,
+            hdma_active: false,
+            serial_output: Vec::new(),
+            printer: None,
+            ir_register: 0,
+            key1_prepare: false,
+            double_speed: false,
+            ir_config: IrConfig::Disconnected,
+            watchpoints: Vec::new(),
+            watchpoint_hit: None,
+            write_hooks: Vec::new(),
+        }
+    }
+
+    // (bank, offset) for a $C000-$FDFF address; echo RAM mirrors $C000-$DDFF
+    fn wram_location(&self, address: u16) -> (usize, usize) {
+        let offset = (address as usize - 0xC000) & 0x1FFF;
+        if offset < 0x1000 {
+            (0, offset)
+        } else {
+            (self.wram_bank as usize, offset - 0x1000)
+        }
+    }
+
+    /// CGB-only registers are only mapped in full CGB mode.
+    pub fn is_cgb(&self) -> bool {
+        self.model == HardwareModel::Cgb
+    }
+
+    /// Register `callback` to run after every write to `addr`.
+    pub fn register_write_hook(&mut self, addr: u16, callback: WriteHook) {
+        self.write_hooks.push((addr, callback));
+    }
+
+    /// Remove all write hooks registered on `addr`.
+    pub fn unregister_write_hook(&mut self, addr: u16) {
+        self.write_hooks.retain(|&(hook_addr, _)| hook_addr != addr);
+    }
+
+    fn check_watchpoints(&mut self, address: u16, is_write: bool) {
+        if let Some(wp) = self.watchpoints.iter().find(|wp| wp.matches(address, is_write)) {
+            self.watchpoint_hit = Some(*wp);
         }
     }
 
@@ -45,16 +147,26 @@ impl MemoryBus {
             self.if_register |= 0x04;
             self.timer.interrupt = false;
         }
+        self.step_oam_dma();
         self.cycles_ticked += 4;
     }
 
+    /// Advance OAM DMA for cycles not covered by bus accesses.
+    pub fn tick_oam_dma(&mut self, t_cycles: u8) {
+        for _ in 0..t_cycles / 4 {
+            self.step_oam_dma();
+        }
+    }
+
     pub fn read_byte_no_tick(&self, address: u16) -> u8 {
         match address {
             0x0000..=0x7FFF => self.cartridge.read_byte(address),
-            0x8000..=0x9FFF => self.vram[(address - 0x8000) as usize],
+            0x8000..=0x9FFF => self.vram[self.vram_bank as usize][(address - 0x8000) as usize],
             0xA000..=0xBFFF => self.cartridge.read_byte(address),
-            0xC000..=0xDFFF => self.wram[(address - 0xC000) as usize],
-            0xE000..=0xFDFF => self.wram[(address - 0xE000) as usize],
+            0xC000..=0xFDFF => {
+                let (bank, offset) = self.wram_location(address);
+                self.wram[bank][offset]
+            }
             0xFE00..=0xFE9F => self.oam[(address - 0xFE00) as usize],
             0xFEA0..=0xFEFF => 0xFF,
             0xFF00..=0xFF7F => self.read_io(address),
@@ -63,26 +175,79 @@ impl MemoryBus {
         }
     }
 
+    /// Read `count` bytes starting at `start` without ticking, wrapping at $FFFF.
+    pub fn read_range(&self, start: u16, count: u16) -> Vec<u8> {
+        let end = start as usize + count as usize;
+        if count > 0 && end <= 0x10000 {
+            let last = (end - 1) as u16;
+            match (start, last) {
+                (0x8000..=0x9FFF, 0x8000..=0x9FFF) => {
+                    return self.vram[self.vram_bank as usize][(start - 0x8000) as usize..end - 0x8000].to_vec();
+                }
+                (0xC000..=0xCFFF, 0xC000..=0xCFFF) | (0xD000..=0xDFFF, 0xD000..=0xDFFF) => {
+                    let (bank, offset) = self.wram_location(start);
+                    return self.wram[bank][offset..offset + count as usize].to_vec();
+                }
+                _ => {}
+            }
+        }
+        (0..count).map(|i| self.read_byte_no_tick(start.wrapping_add(i))).collect()
+    }
+
+    /// Write `data` starting at `start` without ticking, wrapping at $FFFF.
+    pub fn write_range(&mut self, start: u16, data: &[u8]) {
+        let end = start as usize + data.len();
+        // Fast path when the whole range sits in one WRAM bank
+        let same_bank = start >= 0xC000 && end <= 0xE000 && (start as usize & 0xF000) == ((end - 1) & 0xF000);
+        if !data.is_empty() && same_bank {
+            let (bank, offset) = self.wram_location(start);
+            self.wram[bank][offset..offset + data.len()].copy_from_slice(data);
+            return;
+        }
+        for (i, &byte) in data.iter().enumerate() {
+            self.write_byte_no_tick(start.wrapping_add(i as u16), byte);
+        }
+    }
+
     pub fn read_byte(&mut self, address: u16) -> u8 {
-        let value = self.read_byte_no_tick(address);
+        // During OAM DMA the CPU only sees HRAM
+        let value = if self.oam_dma_active && !(0xFF80..=0xFFFE).contains(&address) {
+            0xFF
+        } else {
+            self.cartridge.record_rom_read(address);
+            self.read_byte_no_tick(address)
+        };
+        self.check_watchpoints(address, false);
         self.tick_m_cycle();
         value
     }
 
     pub fn write_byte(&mut self, address: u16, byte: u8) {
+        self.write_byte_no_tick(address, byte);
+        self.check_watchpoints(address, true);
+        for &(hook_addr, callback) in &self.write_hooks {
+            if hook_addr == address {
+                callback(address, byte);
+            }
+        }
+        self.tick_m_cycle();
+    }
+
+    pub fn write_byte_no_tick(&mut self, address: u16, byte: u8) {
         match address {
             0x0000..=0x7FFF => self.cartridge.write_byte(address, byte),
-            0x8000..=0x9FFF => self.vram[(address - 0x8000) as usize] = byte,
+            0x8000..=0x9FFF => self.vram[self.vram_bank as usize][(address - 0x8000) as usize] = byte,
             0xA000..=0xBFFF => self.cartridge.write_byte(address, byte),
-            0xC000..=0xDFFF => self.wram[(address - 0xC000) as usize] = byte,
-            0xE000..=0xFDFF => self.wram[(address - 0xE000) as usize] = byte,
+            0xC000..=0xFDFF => {
+                let (bank, offset) = self.wram_location(address);
+                self.wram[bank][offset] = byte;
+            }
             0xFE00..=0xFE9F => self.oam[(address - 0xFE00) as usize] = byte,
             0xFEA0..=0xFEFF => { /* unusable */ }
             0xFF00..=0xFF7F => self.write_io(address, byte),
             0xFF80..=0xFFFE => self.hram[(address - 0xFF80) as usize] = byte,
             0xFFFF => self.ie_register = byte,
         }
-        self.tick_m_cycle();
     }
 
     fn read_io(&self, address: u16) -> u8 {
@@ -105,6 +270,21 @@ impl MemoryBus {
             0xFF49 => self.ppu.obp1,
             0xFF4A => self.ppu.wy,
             0xFF4B => self.ppu.wx,
+            0xFF4D if self.is_cgb() => 0x7E | (self.double_speed as u8) << 7 | self.key1_prepare as u8,
+            0xFF4F if self.is_cgb() => 0xFE | self.vram_bank,
+            0xFF51..=0xFF54 if self.is_cgb() => 0xFF, // HDMA1-4 are write-only
+            0xFF55 if self.is_cgb() => {
+                if self.hdma_active { self.hdma_length & 0x7F } else { 0x80 | self.hdma_length }
+            }
+            0xFF68 if self.is_cgb() => self.ppu.read_bcps(),
+            0xFF70 if self.is_cgb() => 0xF8 | self.wram_bank,
+            0xFF69 if self.is_cgb() => self.ppu.read_bcpd(),
+            0xFF6A if self.is_cgb() => self.ppu.read_ocps(),
+            0xFF6B if self.is_cgb() => self.ppu.read_ocpd(),
+            // Bits 2-5 unused. Bit 1 is the read-only receive bit, so it
+            // stays live rather than reading as 1 (an 0x3E mask would hide
+            // whatever `IrConfig::Loopback` sends back)
+            0xFF56 if self.is_cgb() => self.ir_register | 0x3C,
             _ => self.io[(address - 0xFF00) as usize],
         }
     }
@@ -117,10 +297,14 @@ impl MemoryBus {
                 self.io[0x02] = byte;
                 // If transfer requested (bit 7) with internal clock (bit 0)
                 if byte & 0x81 == 0x81 {
-                    let outgoing = self.io[0x01];
-                    eprint!("{}", outgoing as char);
-                    // No link partner: receive 0xFF, complete immediately
-                    self.io[0x01] = 0xFF;
+                    // Completes immediately; with no link partner 0xFF is received
+                    self.io[0x01] = match &mut self.printer {
+                        Some(printer) => printer.exchange(self.io[0x01]),
+                        None => {
+                            self.serial_output.push(self.io[0x01]);
+                            0xFF
+                        }
+                    };
                     self.io[0x02] &= 0x7F; // clear bit 7 (transfer complete)
                     self.if_register |= 0x08; // request serial interrupt (bit 3)
                 }
@@ -140,15 +324,88 @@ impl MemoryBus {
             0xFF49 => self.ppu.obp1 = byte,
             0xFF4A => self.ppu.wy = byte,
             0xFF4B => self.ppu.wx = byte,
+            0xFF4D if self.is_cgb() => self.key1_prepare = byte & 0x01 != 0,
+            0xFF4F if self.is_cgb() => self.vram_bank = byte & 0x01,
+            0xFF51 if self.is_cgb() => self.hdma_source = (self.hdma_source & 0x00FF) | ((byte as u16) << 8),
+            0xFF52 if self.is_cgb() => self.hdma_source = (self.hdma_source & 0xFF00) | (byte & 0xF0) as u16,
+            0xFF53 if self.is_cgb() => self.hdma_dest = (self.hdma_dest & 0x00FF) | (((byte & 0x1F) as u16) << 8),
+            0xFF54 if self.is_cgb() => self.hdma_dest = (self.hdma_dest & 0xFF00) | (byte & 0xF0) as u16,
+            0xFF55 if self.is_cgb() => self.write_hdma5(byte),
+            0xFF68 if self.is_cgb() => self.ppu.bcps = byte & 0xBF,
+            0xFF70 if self.is_cgb() => self.wram_bank = (byte & 0x07).max(1),
+            0xFF69 if self.is_cgb() => self.ppu.write_bcpd(byte),
+            0xFF6A if self.is_cgb() => self.ppu.ocps = byte & 0xBF,
+            0xFF6B if self.is_cgb() => self.ppu.write_ocpd(byte),
+            0xFF56 if self.is_cgb() => self.write_ir(byte),
             _ => self.io[(address - 0xFF00) as usize] = byte,
         }
     }
 
+    fn write_hdma5(&mut self, byte: u8) {
+        if self.hdma_active && byte & 0x80 == 0 {
+            // Bit 7 clear while HBlank DMA runs: stop it, keep the remaining length
+            self.hdma_active = false;
+            return;
+        }
+        self.hdma_length = byte & 0x7F;
+        if byte & 0x80 != 0 {
+            self.hdma_active = true;
+        } else {
+            // General-purpose DMA: copy everything now
+            while self.hdma_length != 0xFF {
+                self.hdma_copy_block();
+            }
+        }
+    }
+
+    /// Copy one HBlank DMA block; called at the start of each HBlank.
+    pub fn tick_hdma(&mut self) {
+        if !self.hdma_active {
+            return;
+        }
+        self.hdma_copy_block();
+        if self.hdma_length == 0xFF {
+            self.hdma_active = false;
+        }
+    }
+
+    // Copy 16 bytes into the current VRAM bank and advance both pointers
+    fn hdma_copy_block(&mut self) {
+        for i in 0..16 {
+            let byte = self.read_byte_no_tick(self.hdma_source.wrapping_add(i));
+            let dest = ((self.hdma_dest + i) & 0x1FFF) as usize;
+            self.vram[self.vram_bank as usize][dest] = byte;
+        }
+        self.hdma_source = self.hdma_source.wrapping_add(16);
+        self.hdma_dest = (self.hdma_dest + 16) & 0x1FF0;
+        self.hdma_length = self.hdma_length.wrapping_sub(1);
+    }
+
+    fn write_ir(&mut self, byte: u8) {
+        // Receive bit is read-only; loopback drives it from the LED
+        let received = match self.ir_config {
+            IrConfig::Disconnected => self.ir_register & 0x02,
+            IrConfig::Loopback => (byte & 0x01) << 1,
+        };
+        self.ir_register = (byte & 0xC1) | received;
+    }
+
     fn oam_dma(&mut self, byte: u8) {
-        let base = (byte as u16) << 8;
-        for i in 0..0xA0u16 {
-            let val = self.read_byte_no_tick(base + i);
-            self.oam[i as usize] = val;
+        self.oam_dma_source = byte;
+        self.oam_dma_cycles_remaining = 0xA0;
+        self.oam_dma_active = true;
+    }
+
+    fn step_oam_dma(&mut self) {
+        if !self.oam_dma_active {
+            return;
+        }
+        let i = 0xA0 - self.oam_dma_cycles_remaining;
+        let val = self.read_byte_no_tick(((self.oam_dma_source as u16) << 8) + i);
+        self.oam[i as usize] = val;
+        self.oam_dma_cycles_remaining -= 1;
+        if self.oam_dma_cycles_remaining == 0 {
+            self.oam_dma_active = false;
         }
     }
 }
@@ -156,13 +413,27 @@ impl MemoryBus {
 impl MemoryBus {
     pub fn save_state(&self, buf: &mut Vec<u8>) {
         use crate::savestate::*;
-        write_bytes(buf, &self.vram);
-        write_bytes(buf, &self.wram);
+        write_bytes(buf, &self.vram[0]);
+        write_bytes(buf, &self.vram[1]);
+        write_u8(buf, self.vram_bank);
+        for bank in &self.wram {
+            write_bytes(buf, bank);
+        }
+        write_u8(buf, self.wram_bank);
         write_bytes(buf, &self.oam);
         write_bytes(buf, &self.io);
         write_bytes(buf, &self.hram);
         write_u8(buf, self.ie_register);
         write_u8(buf, self.if_register);
+        write_bool(buf, self.oam_dma_active);
+        write_u8(buf, self.oam_dma_source);
+        write_u16_le(buf, self.oam_dma_cycles_remaining);
+        write_u16_le(buf, self.hdma_source);
+        write_u16_le(buf, self.hdma_dest);
+        write_u8(buf, self.hdma_length);
+        write_bool(buf, self.hdma_active);
+        write_bool(buf, self.key1_prepare);
+        write_bool(buf, self.double_speed);
         self.timer.save_state(buf);
         self.ppu.save_state(buf);
         self.joypad.save_state(buf);
@@ -172,10 +443,16 @@ impl MemoryBus {
 
     pub fn load_state(&mut self, data: &[u8], cursor: &mut usize) {
         use crate::savestate::*;
-        let vram = read_bytes(data, cursor, 0x2000);
-        self.vram.copy_from_slice(vram);
-        let wram = read_bytes(data, cursor, 0x2000);
-        self.wram.copy_from_slice(wram);
+        for bank in self.vram.iter_mut() {
+            let vram = read_bytes(data, cursor, 0x2000);
+            bank.copy_from_slice(vram);
+        }
+        self.vram_bank = read_u8(data, cursor);
+        for bank in self.wram.iter_mut() {
+            let wram = read_bytes(data, cursor, 0x1000);
+            bank.copy_from_slice(wram);
+        }
+        self.wram_bank = read_u8(data, cursor);
         let oam = read_bytes(data, cursor, 0xA0);
         self.oam.copy_from_slice(oam);
         let io = read_bytes(data, cursor, 0x80);
@@ -184,7 +461,17 @@ impl MemoryBus {
         self.hram.copy_from_slice(hram);
         self.ie_register = read_u8(data, cursor);
         self.if_register = read_u8(data, cursor);
+        self.oam_dma_active = read_bool(data, cursor);
+        self.oam_dma_source = read_u8(data, cursor);
+        self.oam_dma_cycles_remaining = read_u16_le(data, cursor);
+        self.hdma_source = read_u16_le(data, cursor);
+        self.hdma_dest = read_u16_le(data, cursor);
+        self.hdma_length = read_u8(data, cursor);
+        self.hdma_active = read_bool(data, cursor);
+        self.key1_prepare = read_bool(data, cursor);
+        self.double_speed = read_bool(data, cursor);
         self.timer.load_state(data, cursor);
+        self.timer.double_speed = self.double_speed;
         self.ppu.load_state(data, cursor);
         self.joypad.load_state(data, cursor);
         self.apu.load_state(data, cursor);
@@ -7,6 +7,28 @@ use memory::*;
 use instruction::*;
 use crate::cartridge::Cartridge;
 
+/// Hardware the emulated game sees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareModel {
+    Dmg,
+    Cgb,
+    /// CGB running a DMG-only cartridge in compatibility mode
+    CgbInDmgMode,
+}
+
+impl HardwareModel {
+    /// Pick the model from the cartridge header CGB flag ($0143).
+    pub fn from_cgb_flag(cgb_flag: u8) -> Self {
+        match cgb_flag {
+            0x80 | 0xC0 => HardwareModel::Cgb,
+            _ => HardwareModel::Dmg,
+        }
+    }
+}
+
+/// M-cycles the CPU stays frozen while switching CGB speed
+const SPEED_SWITCH_CYCLES: u32 = 2050;
+
 pub struct CPU {
     pub registers: Registers,
     pub pc: u16,
@@ -16,30 +38,35 @@ pub struct CPU {
     pub halted: bool,
     ei_pending: bool,
     halt_bug: bool,
+    /// M-cycles left of a CGB speed switch started by STOP; the CPU is
+    /// frozen until it reaches zero
+    pub speed_switch_countdown: u32,
 }
 
 impl CPU {
+    /// Power-on CPU with zeroed registers; see `GameBoy::skip_bios` for post-boot state.
     pub fn new(cartridge: Cartridge) -> Self {
-        let mut cpu = CPU {
+        CPU::new_with_model(cartridge, HardwareModel::Dmg)
+    }
+
+    /// Like `new`, but the bus and PPU run as `model`. Registers still come
+    /// from `GameBoy::skip_bios` with `PostBootState::for_model`.
+    pub fn new_with_model(cartridge: Cartridge, model: HardwareModel) -> Self {
+        let mut bus = MemoryBus::new(cartridge);
+        bus.model = model;
+        bus.ppu.cgb_mode = model == HardwareModel::Cgb;
+        bus.apu.channel3.cgb_mode = model == HardwareModel::Cgb;
+        CPU {
             registers: Registers::default(),
             pc: 0x0100,
             sp: 0xFFFE,
-            bus: MemoryBus::new(cartridge),
+            bus,
             ime: false,
             halted: false,
             ei_pending: false,
             halt_bug: false,
-        };
-        // Post-boot register state (DMG)
-        cpu.registers.a = 0x01;
-        cpu.registers.f = FlagsRegister::from(0xB0);
-        cpu.registers.b = 0x00;
-        cpu.registers.c = 0x13;
-        cpu.registers.d = 0x00;
-        cpu.registers.e = 0xD8;
-        cpu.registers.h = 0x01;
-        cpu.registers.l = 0x4D;
-        cpu
+            speed_switch_countdown: 0,
+        }
     }
 
     pub fn save_state(&self, buf: &mut Vec<u8>) {
@@ -52,6 +79,7 @@ impl CPU {
             | (if self.ei_pending { 1 } else { 0 }) << 2
             | (if self.halt_bug { 1 } else { 0 }) << 3;
         write_u8(buf, flags);
+        write_u32_le(buf, self.speed_switch_countdown);
         self.bus.save_state(buf);
     }
 
@@ -65,10 +93,21 @@ impl CPU {
         self.halted = flags & 0x02 != 0;
         self.ei_pending = flags & 0x04 != 0;
         self.halt_bug = flags & 0x08 != 0;
+        self.speed_switch_countdown = read_u32_le(data, cursor);
         self.bus.load_state(data, cursor);
     }
 
     pub fn step(&mut self) -> u8 {
+        if self.speed_switch_countdown > 0 {
+            self.speed_switch_countdown -= 1;
+            if self.speed_switch_countdown == 0 {
+                self.bus.double_speed = !self.bus.double_speed;
+                self.bus.timer.double_speed = self.bus.double_speed;
+                self.bus.key1_prepare = false;
+            }
+            return 4;
+        }
+
         let interrupt_cycles = self.handle_interrupts();
         if interrupt_cycles > 0 {
             return interrupt_cycles;
@@ -586,6 +625,13 @@ impl CPU {
                 (self.pc.wrapping_add(1), 4)
             }
             Instruction::STOP => {
+                // STOP always resets DIV
+                self.bus.timer.reset_div(&mut self.bus.apu);
+                if self.bus.is_cgb() && self.bus.key1_prepare {
+                    // Pending interrupts are dropped when the switch starts
+                    self.bus.if_register = 0;
+                    self.speed_switch_countdown = SPEED_SWITCH_CYCLES;
+                }
                 (self.pc.wrapping_add(2), 4)
             }
             Instruction::RST(addr) => {
@@ -717,6 +763,11 @@ impl CPU {
         (msb << 8) | lsb
     }
 
+    // PUSH on hardware is fetch, internal delay, write high, write low. Here the
+    // two writes (and their M-cycle ticks) happen first and the internal cycle
+    // is ticked afterwards by GameBoy::step. SP is updated before each write,
+    // so bus hooks see the same SP sequence as hardware; save states only run
+    // between instructions and always capture the final SP.
     fn push(&mut self, value: u16) {
         self.sp = self.sp.wrapping_sub(1);
         self.bus.write_byte(self.sp, (value >> 8) as u8);
@@ -1042,6 +1093,7 @@ impl Default for CPU {
             halted: false,
             ei_pending: false,
             halt_bug: false,
+            speed_switch_countdown: 0,
         }
     }
 }
@@ -29,7 +29,8 @@ impl Registers {
 
     pub fn set_af(&mut self, value: u16) {
         self.a = ((value & 0xFF00) >> 8) as u8;
-        self.f = FlagsRegister::from((value & 0xFF) as u8);
+        // Lower nibble of F is hardwired to 0
+        self.f = FlagsRegister::from((value & 0xF0) as u8);
     }
 
     pub fn get_bc(&self) -> u16 {
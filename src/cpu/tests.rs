@@ -847,6 +847,75 @@ fn test_halt_normal_no_pending() {
     assert!(!cpu.halt_bug);
 }
 
+#[test]
+fn test_halt_decodes_and_advances_pc() {
+    // 0x76 must decode to HALT rather than falling through to an unknown opcode
+    assert!(matches!(Instruction::from_byte(0x76, false), Some(Instruction::HALT)));
+
+    let mut cpu = CPU::default();
+    cpu.pc = 0xC000;
+    cpu.bus.write_byte(0xC000, 0x76);
+
+    cpu.step();
+    assert!(cpu.halted);
+    assert_eq!(cpu.pc, 0xC001, "HALT is a one-byte instruction");
+}
+
+// ===============================================
+// Tests for STOP
+// ===============================================
+#[test]
+fn test_stop_resets_div() {
+    let mut cpu = CPU::default();
+    cpu.pc = 0xC000;
+    cpu.bus.write_byte(0xC000, 0x10); // STOP
+    cpu.bus.write_byte(0xC001, 0x00);
+    cpu.bus.timer.internal_counter = 0x1234;
+
+    cpu.step();
+    assert_eq!(cpu.bus.timer.internal_counter, 0);
+    assert_eq!(cpu.pc, 0xC002);
+}
+
+#[test]
+fn test_stop_speed_switch_freezes_cpu() {
+    let mut cpu = cgb_cpu();
+    cpu.pc = 0xC000;
+    cpu.bus.write_byte(0xC000, 0x10); // STOP
+    cpu.bus.write_byte(0xC001, 0x00);
+    cpu.bus.write_byte(0xC002, 0x3C); // INC A
+    cpu.bus.write_byte(0xFF4D, 0x01);
+    assert_eq!(cpu.bus.read_byte(0xFF4D), 0x7F);
+    cpu.bus.if_register = 0x05;
+
+    cpu.step();
+    assert_eq!(cpu.speed_switch_countdown, SPEED_SWITCH_CYCLES);
+    assert_eq!(cpu.bus.if_register, 0, "STOP clears IF");
+    for _ in 0..SPEED_SWITCH_CYCLES - 1 {
+        assert_eq!(cpu.step(), 4);
+    }
+    assert_eq!(cpu.bus.read_byte(0xFF4D), 0x7F, "still switching");
+    assert_eq!(cpu.pc, 0xC002);
+
+    cpu.step();
+    assert_eq!(cpu.bus.read_byte(0xFF4D), 0xFE, "double speed, switch disarmed");
+    cpu.step();
+    assert_eq!(cpu.registers.a, 1, "CPU runs again after the switch");
+}
+
+#[test]
+fn test_stop_without_key1_armed_keeps_speed() {
+    let mut cpu = cgb_cpu();
+    cpu.pc = 0xC000;
+    cpu.bus.write_byte(0xC000, 0x10); // STOP
+    cpu.bus.write_byte(0xC001, 0x00);
+    cpu.bus.if_register = 0x05;
+    cpu.step();
+    assert_eq!(cpu.speed_switch_countdown, 0);
+    assert_eq!(cpu.bus.if_register, 0x05);
+    assert_eq!(cpu.bus.read_byte(0xFF4D), 0x7E);
+}
+
 // ===============================================
 // Tests for delayed EI timing
 // ===============================================
@@ -893,6 +962,34 @@ fn test_serial_transfer_completes() {
     assert_eq!(cpu.bus.if_register & 0x08, 0x08);
 }
 
+#[test]
+fn test_serial_output_captured() {
+    let mut cpu = CPU::default();
+    for &byte in b"ok" {
+        cpu.bus.write_byte(0xFF01, byte);
+        cpu.bus.write_byte(0xFF02, 0x81);
+    }
+    // External clock: no partner drives it, so nothing is sent
+    cpu.bus.write_byte(0xFF01, b'!');
+    cpu.bus.write_byte(0xFF02, 0x80);
+    assert_eq!(cpu.bus.serial_output, b"ok");
+}
+
+#[test]
+fn test_serial_talks_to_printer() {
+    let mut cpu = CPU::default();
+    cpu.bus.printer = Some(crate::printer::Printer::new());
+    // INIT packet: magic, command 1, no compression, no data, checksum 1, then two 00s
+    let mut replies = Vec::new();
+    for &byte in &[0x88, 0x33, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00] {
+        cpu.bus.write_byte(0xFF01, byte);
+        cpu.bus.write_byte(0xFF02, 0x81);
+        replies.push(cpu.bus.read_byte(0xFF01));
+    }
+    assert_eq!(replies[8..], [0x81, 0x00], "device id then status");
+    assert!(cpu.bus.serial_output.is_empty());
+}
+
 #[test]
 fn test_serial_no_transfer_without_start() {
     let mut cpu = CPU::default();
@@ -914,3 +1011,451 @@ fn test_serial_sb_readwrite() {
     cpu.bus.write_byte(0xFF01, 0x00);
     assert_eq!(cpu.bus.read_byte(0xFF01), 0x00);
 }
+
+// ===============================================
+// Tests for memory write hooks
+// ===============================================
+thread_local! {
+    static HOOK_CALLS: std::cell::RefCell<Vec<(u16, u8)>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+fn record_hook(addr: u16, value: u8) {
+    HOOK_CALLS.with(|calls| calls.borrow_mut().push((addr, value)));
+}
+
+#[test]
+fn test_write_hook_called() {
+    let mut cpu = CPU::default();
+    cpu.bus.register_write_hook(0xFF80, record_hook);
+    cpu.bus.write_byte(0xFF81, 0x11); // different address, not hooked
+    cpu.bus.write_byte(0xFF80, 0x42);
+    HOOK_CALLS.with(|calls| assert_eq!(*calls.borrow(), vec![(0xFF80, 0x42)]));
+
+    cpu.bus.unregister_write_hook(0xFF80);
+    cpu.bus.write_byte(0xFF80, 0x43);
+    HOOK_CALLS.with(|calls| assert_eq!(calls.borrow().len(), 1));
+}
+
+// ===============================================
+// Tests for POP AF flag masking
+// ===============================================
+#[test]
+fn test_pop_af_masks_lower_nibble() {
+    let mut cpu = CPU::default();
+    cpu.pc = 0xC000;
+    cpu.sp = 0xFFFE;
+    cpu.push(0xFFFF);
+    cpu.bus.write_byte(0xC000, 0xF1); // POP AF
+
+    cpu.step();
+    assert_eq!(cpu.registers.a, 0xFF);
+    assert_eq!(cpu.registers.get_af() & 0xFF, 0xF0);
+    assert_eq!(cpu.sp, 0xFFFE);
+}
+
+// ===============================================
+// Tests for OAM DMA timing
+// ===============================================
+#[test]
+fn test_oam_dma_takes_160_m_cycles() {
+    let mut cpu = CPU::default();
+    let expected: Vec<u8> = (1..=0xA0).collect();
+    cpu.bus.write_range(0xC100, &expected);
+    cpu.bus.hram[0] = 0x77;
+    cpu.bus.write_byte(0xFF46, 0xC1);
+    assert!(cpu.bus.oam_dma_active);
+
+    // Only HRAM is visible to the CPU while the transfer runs
+    assert_eq!(cpu.bus.read_byte(0xC100), 0xFF);
+    assert_eq!(cpu.bus.read_byte(0xFF80), 0x77);
+    assert_eq!(cpu.bus.oam[0xA0 - 1], 0);
+
+    for _ in 0..0xA0 {
+        cpu.bus.tick_oam_dma(4);
+    }
+    assert!(!cpu.bus.oam_dma_active);
+    assert_eq!(cpu.bus.read_byte(0xC100), 0x01);
+    assert_eq!(cpu.bus.oam.to_vec(), expected);
+}
+
+// ===============================================
+// Tests for CGB VRAM banking ($FF4F)
+// ===============================================
+fn cgb_cpu() -> CPU {
+    let mut cpu = CPU::default();
+    cpu.bus.model = HardwareModel::Cgb;
+    cpu
+}
+
+#[test]
+fn test_vbk_switches_vram_bank() {
+    let mut cpu = cgb_cpu();
+    cpu.bus.write_byte(0x8000, 0x11);
+    cpu.bus.write_byte(0xFF4F, 0x01);
+    assert_eq!(cpu.bus.read_byte(0xFF4F), 0xFF);
+    assert_eq!(cpu.bus.read_byte(0x8000), 0x00);
+    cpu.bus.write_byte(0x8000, 0x22);
+
+    cpu.bus.write_byte(0xFF4F, 0xFE); // only bit 0 selects the bank
+    assert_eq!(cpu.bus.read_byte(0xFF4F), 0xFE);
+    assert_eq!(cpu.bus.read_byte(0x8000), 0x11);
+    assert_eq!(cpu.bus.vram[1][0], 0x22);
+}
+
+#[test]
+fn test_vbk_ignored_on_dmg() {
+    let mut cpu = CPU::default();
+    cpu.bus.write_byte(0xFF4F, 0x01);
+    cpu.bus.write_byte(0x8000, 0x33);
+    assert_eq!(cpu.bus.vram_bank, 0);
+    assert_eq!(cpu.bus.vram[0][0], 0x33);
+}
+
+#[test]
+fn test_cgb_palette_registers_mapped_on_cgb_only() {
+    let mut cpu = cgb_cpu();
+    cpu.bus.write_byte(0xFF68, 0x80);
+    cpu.bus.write_byte(0xFF69, 0x1F);
+    cpu.bus.write_byte(0xFF69, 0x7C);
+    assert_eq!(cpu.bus.ppu.bg_palette_color(0, 0), 0x7C1F);
+    cpu.bus.write_byte(0xFF6A, 0x02);
+    cpu.bus.write_byte(0xFF6B, 0xAB);
+    assert_eq!(cpu.bus.read_byte(0xFF6B), 0xAB);
+    assert_eq!(cpu.bus.ppu.obj_palette_ram[2], 0xAB);
+
+    let mut dmg = CPU::default();
+    dmg.bus.write_byte(0xFF68, 0x80);
+    dmg.bus.write_byte(0xFF69, 0x1F);
+    assert_eq!(dmg.bus.ppu.bg_palette_ram[0], 0x00);
+}
+
+#[test]
+fn test_gdma_copies_immediately() {
+    let mut cpu = cgb_cpu();
+    for i in 0..32u16 {
+        cpu.bus.write_byte(0xC000 + i, i as u8 + 1);
+    }
+    cpu.bus.write_byte(0xFF51, 0xC0);
+    cpu.bus.write_byte(0xFF52, 0x00);
+    cpu.bus.write_byte(0xFF53, 0x81); // VRAM $8100
+    cpu.bus.write_byte(0xFF54, 0x00);
+    cpu.bus.write_byte(0xFF55, 0x01); // 2 blocks, general-purpose
+
+    assert_eq!(cpu.bus.read_range(0x8100, 32), (1..=32).collect::<Vec<u8>>());
+    assert_eq!(cpu.bus.read_byte(0xFF55), 0xFF);
+}
+
+#[test]
+fn test_hdma_copies_one_block_per_hblank() {
+    let mut cpu = cgb_cpu();
+    cpu.bus.wram[0][..48].fill(0x5A);
+    cpu.bus.write_byte(0xFF51, 0xC0);
+    cpu.bus.write_byte(0xFF52, 0x00);
+    cpu.bus.write_byte(0xFF53, 0x00);
+    cpu.bus.write_byte(0xFF54, 0x00);
+    cpu.bus.write_byte(0xFF55, 0x82); // 3 blocks, HBlank
+    assert_eq!(cpu.bus.read_byte(0xFF55), 0x02);
+    assert_eq!(cpu.bus.vram[0][0], 0x00);
+
+    cpu.bus.tick_hdma();
+    assert_eq!(cpu.bus.vram[0][15], 0x5A);
+    assert_eq!(cpu.bus.vram[0][16], 0x00);
+    assert_eq!(cpu.bus.read_byte(0xFF55), 0x01);
+
+    // Writing bit 7 = 0 cancels the transfer
+    cpu.bus.write_byte(0xFF55, 0x00);
+    assert_eq!(cpu.bus.read_byte(0xFF55), 0x81);
+    cpu.bus.tick_hdma();
+    assert_eq!(cpu.bus.vram[0][16], 0x00);
+}
+
+#[test]
+fn test_svbk_switches_d000_bank() {
+    let mut cpu = cgb_cpu();
+    cpu.bus.write_byte(0xC000, 0x01);
+    cpu.bus.write_byte(0xD000, 0x11);
+    cpu.bus.write_byte(0xFF70, 0x03);
+    assert_eq!(cpu.bus.read_byte(0xFF70), 0xFB);
+    assert_eq!(cpu.bus.read_byte(0xD000), 0x00);
+    cpu.bus.write_byte(0xD000, 0x33);
+    // $C000 is always bank 0, echo RAM follows the selected bank
+    assert_eq!(cpu.bus.read_byte(0xC000), 0x01);
+    assert_eq!(cpu.bus.read_byte(0xF000), 0x33);
+
+    cpu.bus.write_byte(0xFF70, 0x00); // bank 0 selects bank 1
+    assert_eq!(cpu.bus.read_byte(0xD000), 0x11);
+    assert_eq!(cpu.bus.wram[3][0], 0x33);
+}
+
+#[test]
+fn test_svbk_ignored_on_dmg() {
+    let mut cpu = CPU::default();
+    cpu.bus.write_byte(0xFF70, 0x03);
+    cpu.bus.write_byte(0xD000, 0x44);
+    assert_eq!(cpu.bus.wram_bank, 1);
+    assert_eq!(cpu.bus.wram[1][0], 0x44);
+}
+
+#[test]
+fn test_save_state_keeps_both_vram_banks() {
+    let mut cpu = cgb_cpu();
+    cpu.bus.vram[0][0x10] = 0xAA;
+    cpu.bus.vram[1][0x10] = 0xBB;
+    cpu.bus.vram_bank = 1;
+    let mut buf = Vec::new();
+    cpu.bus.save_state(&mut buf);
+
+    let mut restored = cgb_cpu();
+    let mut cursor = 0;
+    restored.bus.load_state(&buf, &mut cursor);
+    assert_eq!(restored.bus.vram[0][0x10], 0xAA);
+    assert_eq!(restored.bus.vram[1][0x10], 0xBB);
+    assert_eq!(restored.bus.vram_bank, 1);
+    assert_eq!(cursor, buf.len());
+}
+
+// ===============================================
+// Tests for infrared port ($FF56)
+// ===============================================
+#[test]
+fn test_ir_loopback_receives_sent_pulse() {
+    let mut cpu = cgb_cpu();
+    cpu.bus.ir_config = IrConfig::Loopback;
+    cpu.bus.write_byte(0xFF56, 0x01);
+    assert_ne!(cpu.bus.read_byte(0xFF56) & 0x02, 0);
+
+    cpu.bus.write_byte(0xFF56, 0x00);
+    assert_eq!(cpu.bus.read_byte(0xFF56) & 0x02, 0);
+}
+
+#[test]
+fn test_ir_without_loopback_receives_nothing() {
+    let mut cpu = cgb_cpu();
+    cpu.bus.write_byte(0xFF56, 0x03);
+    assert_eq!(cpu.bus.read_byte(0xFF56) & 0x03, 0x01);
+}
+
+#[test]
+fn test_ir_port_absent_on_dmg() {
+    let mut cpu = CPU::default();
+    cpu.bus.ir_config = IrConfig::Loopback;
+    cpu.bus.write_byte(0xFF56, 0x01);
+    assert_eq!(cpu.bus.ir_register, 0);
+}
+
+// ===============================================
+// Tests for EI and interrupt dispatch
+// ===============================================
+#[test]
+fn test_ei_and_interrupt_dispatch() {
+    let vectors = [0x0040u16, 0x0048, 0x0050, 0x0058, 0x0060];
+    for (bit, &vector) in vectors.iter().enumerate() {
+        let mut cpu = CPU::default();
+        cpu.pc = 0xC000;
+        cpu.sp = 0xFFFE;
+        cpu.bus.write_byte(0xC000, 0xFB); // EI
+        cpu.bus.write_byte(0xC001, 0x00); // NOP
+        cpu.bus.ie_register = 1 << bit;
+        cpu.bus.if_register = 1 << bit;
+
+        cpu.step(); // EI: IME enabled after the next instruction
+        assert!(!cpu.ime);
+        cpu.step(); // NOP
+        assert!(cpu.ime);
+        assert_eq!(cpu.pc, 0xC002);
+
+        cpu.step(); // dispatch
+        assert_eq!(cpu.pc, vector, "vector for IF bit {}", bit);
+        assert!(!cpu.ime, "IME should be cleared on dispatch");
+        assert_eq!(cpu.bus.if_register & (1 << bit), 0, "IF bit {} should be cleared", bit);
+        assert_eq!(cpu.sp, 0xFFFC);
+        let ret = cpu.bus.read_byte(0xFFFC) as u16 | (cpu.bus.read_byte(0xFFFD) as u16) << 8;
+        assert_eq!(ret, 0xC002, "return address for IF bit {}", bit);
+    }
+}
+
+#[test]
+fn test_interrupt_priority_vblank_first() {
+    let mut cpu = CPU::default();
+    cpu.pc = 0xC000;
+    cpu.sp = 0xFFFE;
+    cpu.ime = true;
+    cpu.bus.ie_register = 0x1F;
+    cpu.bus.if_register = 0x05; // VBlank + Timer
+
+    cpu.step();
+    assert_eq!(cpu.pc, 0x0040);
+    assert_eq!(cpu.bus.if_register & 0x1F, 0x04, "Timer should remain pending");
+}
+
+// ===============================================
+// Tests for bulk memory reads
+// ===============================================
+#[test]
+fn test_read_range_vram_fast_path() {
+    let mut cpu = CPU::default();
+    for (i, byte) in cpu.bus.vram[0].iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    let expected: Vec<u8> = (0..64).collect();
+    assert_eq!(cpu.bus.read_range(0x8000, 64), expected);
+}
+
+#[test]
+fn test_only_cpu_reads_mark_rom_banks() {
+    let mut cpu = CPU::default();
+    cpu.bus.read_byte_no_tick(0x4000);
+    cpu.bus.read_range(0x4000, 16);
+    assert!(!cpu.bus.cartridge.accessed_rom_banks()[1]);
+    cpu.bus.read_byte(0x4000);
+    assert!(cpu.bus.cartridge.accessed_rom_banks()[1]);
+}
+
+#[test]
+fn test_read_range_wraps_at_ffff() {
+    let mut cpu = CPU::default();
+    cpu.bus.hram[0x7E] = 0x11; // $FFFE
+    cpu.bus.ie_register = 0x1F; // $FFFF
+    let rom = [cpu.bus.read_byte_no_tick(0x0000), cpu.bus.read_byte_no_tick(0x0001)];
+    assert_eq!(cpu.bus.read_range(0xFFFE, 4), vec![0x11, 0x1F, rom[0], rom[1]]);
+}
+
+#[test]
+fn test_read_range_spanning_regions() {
+    let mut cpu = CPU::default();
+    cpu.bus.vram[0][0] = 0x42;
+    let bytes = cpu.bus.read_range(0x7FFF, 2);
+    assert_eq!(bytes, vec![cpu.bus.read_byte_no_tick(0x7FFF), 0x42]);
+}
+
+#[test]
+fn test_write_range_wram_round_trip() {
+    let mut cpu = CPU::default();
+    let pattern: Vec<u8> = (0..32).map(|i| i * 7 + 1).collect();
+    cpu.bus.write_range(0xC000, &pattern);
+    assert_eq!(cpu.bus.read_range(0xC000, 32), pattern);
+}
+
+#[test]
+fn test_write_range_wraps_at_ffff() {
+    let mut cpu = CPU::default();
+    let rom0 = cpu.bus.read_byte_no_tick(0x0000);
+    cpu.bus.write_range(0xFFFF, &[0xAA, 0xBB]);
+    assert_eq!(cpu.bus.ie_register, 0xAA);
+    // $0000 is an MBC register write, ROM contents are unchanged
+    assert_eq!(cpu.bus.read_byte_no_tick(0x0000), rom0);
+}
+
+#[test]
+fn test_write_range_spanning_regions() {
+    let mut cpu = CPU::default();
+    cpu.bus.write_range(0xDFFF, &[0x12, 0x34]);
+    assert_eq!(cpu.bus.wram[1][0xFFF], 0x12);
+    // $E000 is echo RAM for $C000
+    assert_eq!(cpu.bus.wram[0][0x000], 0x34);
+}
+
+// ===============================================
+// Tests for PUSH stack layout
+// ===============================================
+#[test]
+fn test_push_bc_stack_layout() {
+    // $FE00-$FEFF is OAM/unusable on this bus, so use a WRAM stack
+    let mut cpu = CPU::default();
+    cpu.pc = 0xC000;
+    cpu.sp = 0xD000;
+    cpu.registers.set_bc(0x1234);
+    cpu.bus.write_byte(0xC000, 0xC5); // PUSH BC
+
+    let cycles = cpu.step();
+    assert_eq!(cycles, 16);
+    assert_eq!(cpu.sp, 0xCFFE);
+    assert_eq!(cpu.bus.read_byte(0xCFFF), 0x12);
+    assert_eq!(cpu.bus.read_byte(0xCFFE), 0x34);
+}
+
+#[test]
+fn test_interrupt_priority() {
+    let mut cpu = CPU::default();
+    cpu.pc = 0xC000;
+    cpu.sp = 0xD000;
+    cpu.ime = true;
+    cpu.bus.ie_register = 0x1F;
+    cpu.bus.if_register = 0x1F;
+    cpu.bus.write_byte(0xC000, 0x00); // NOP
+    for vector in [0x0040u16, 0x0048, 0x0050, 0x0058, 0x0060] {
+        cpu.bus.cartridge.patch_rom(vector, &[0xD9]).unwrap(); // RETI
+    }
+
+    let mut visited: Vec<u16> = Vec::new();
+    for bit in 0..5u8 {
+        cpu.step(); // dispatch
+        visited.push(cpu.pc);
+        assert_eq!(cpu.bus.if_register & (1 << bit), 0, "IF bit {} should be cleared", bit);
+        assert_eq!(cpu.bus.if_register & 0x1F, 0x1F & !((2u8 << bit) - 1), "higher bits still pending");
+        assert_eq!(cpu.sp, 0xCFFE);
+        let ret = cpu.bus.read_byte(0xCFFE) as u16 | (cpu.bus.read_byte(0xCFFF) as u16) << 8;
+        assert_eq!(ret, 0xC000, "return address for IF bit {}", bit);
+
+        cpu.step(); // RETI
+        assert_eq!(cpu.pc, 0xC000);
+        assert!(cpu.ime);
+    }
+    assert_eq!(visited, vec![0x0040, 0x0048, 0x0050, 0x0058, 0x0060]);
+}
+
+// ===============================================
+// Tests for opcode decoder completeness
+// ===============================================
+// Every unprefixed DMG opcode except the undefined ones and the CB prefix
+#[rustfmt::skip]
+const KNOWN_VALID_OPCODES: &[u8] = &[
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B,
+    0x0C, 0x0D, 0x0E, 0x0F, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+    0x18, 0x19, 0x1A, 0x1B, 0x1C, 0x1D, 0x1E, 0x1F, 0x20, 0x21, 0x22, 0x23,
+    0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x2B, 0x2C, 0x2D, 0x2E, 0x2F,
+    0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x3B,
+    0x3C, 0x3D, 0x3E, 0x3F, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47,
+    0x48, 0x49, 0x4A, 0x4B, 0x4C, 0x4D, 0x4E, 0x4F, 0x50, 0x51, 0x52, 0x53,
+    0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x5B, 0x5C, 0x5D, 0x5E, 0x5F,
+    0x60, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x6B,
+    0x6C, 0x6D, 0x6E, 0x6F, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77,
+    0x78, 0x79, 0x7A, 0x7B, 0x7C, 0x7D, 0x7E, 0x7F, 0x80, 0x81, 0x82, 0x83,
+    0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8A, 0x8B, 0x8C, 0x8D, 0x8E, 0x8F,
+    0x90, 0x91, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0x9B,
+    0x9C, 0x9D, 0x9E, 0x9F, 0xA0, 0xA1, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7,
+    0xA8, 0xA9, 0xAA, 0xAB, 0xAC, 0xAD, 0xAE, 0xAF, 0xB0, 0xB1, 0xB2, 0xB3,
+    0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xBB, 0xBC, 0xBD, 0xBE, 0xBF,
+    0xC0, 0xC1, 0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xCC,
+    0xCD, 0xCE, 0xCF, 0xD0, 0xD1, 0xD2, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9,
+    0xDA, 0xDC, 0xDE, 0xDF, 0xE0, 0xE1, 0xE2, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9,
+    0xEA, 0xEE, 0xEF, 0xF0, 0xF1, 0xF2, 0xF3, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9,
+    0xFA, 0xFB, 0xFE, 0xFF,
+];
+
+// Undefined on the LR35902, plus $CB (prefix, never decoded on its own)
+const KNOWN_INVALID_OPCODES: &[u8] = &[
+    0xCB, 0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD,
+];
+
+#[test]
+fn test_from_byte_not_prefixed_completeness() {
+    assert_eq!(KNOWN_VALID_OPCODES.len(), 244);
+    assert_eq!(KNOWN_VALID_OPCODES.len() + KNOWN_INVALID_OPCODES.len(), 256);
+    for byte in 0..=255u8 {
+        let decoded = Instruction::from_byte(byte, false);
+        if KNOWN_VALID_OPCODES.contains(&byte) {
+            assert!(decoded.is_some(), "opcode 0x{:02X} should decode", byte);
+        } else {
+            assert!(KNOWN_INVALID_OPCODES.contains(&byte), "opcode 0x{:02X} missing from both tables", byte);
+            assert!(decoded.is_none(), "opcode 0x{:02X} should not decode", byte);
+        }
+    }
+}
+
+#[test]
+fn test_from_byte_prefixed_completeness() {
+    for byte in 0..=255u8 {
+        assert!(Instruction::from_byte(byte, true).is_some(), "CB opcode 0x{:02X} should decode", byte);
+    }
+}
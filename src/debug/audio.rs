@@ -0,0 +1,196 @@
+use minifb::{Window, WindowOptions, Key, KeyRepeat, MouseButton, MouseMode};
+use super::font;
+use super::{BG_COLOR, TEXT_COLOR, HEADER_COLOR, HIGHLIGHT_COLOR};
+use crate::apu::Apu;
+
+const WIN_W: usize = 240;
+const WIN_H: usize = 140;
+const ROW_H: usize = 16;
+const TOP: usize = 28;
+const BOX: usize = 9;
+const MUTE_X: usize = 152;
+const SOLO_X: usize = 192;
+const SLIDER_X: usize = 64;
+const SLIDER_W: usize = 120;
+const VOLUME_ROW: usize = 4;
+const HPF_ROW: usize = 5;
+const LPF_ROW: usize = 6;
+
+const OFF_COLOR: u32 = 0x00505050;
+const SLIDER_COLOR: u32 = 0x0000C040;
+
+const CHANNEL_NAMES: [&str; 4] = ["CH1 SQUARE", "CH2 SQUARE", "CH3 WAVE", "CH4 NOISE"];
+
+/// Per-channel mute/solo, master volume and output filter bypass.
+pub struct AudioDebugPanel {
+    pub window: Window,
+    buf: Vec<u32>,
+    mouse_was_down: bool,
+}
+
+impl Default for AudioDebugPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioDebugPanel {
+    pub fn new() -> Self {
+        let window = Window::new(
+            "Audio Debug (1-4 mute)",
+            WIN_W,
+            WIN_H,
+            WindowOptions::default(),
+        ).expect("Failed to create audio debug window");
+        AudioDebugPanel {
+            window,
+            buf: vec![BG_COLOR; WIN_W * WIN_H],
+            mouse_was_down: false,
+        }
+    }
+
+    pub fn update(&mut self, apu: &mut Apu) {
+        self.handle_input(apu);
+
+        self.buf.fill(BG_COLOR);
+        font::draw_string(&mut self.buf, WIN_W, 4, 2, "AUDIO CHANNELS", HEADER_COLOR);
+        font::draw_string(&mut self.buf, WIN_W, MUTE_X - 4, TOP - 12, "MUTE", TEXT_COLOR);
+        font::draw_string(&mut self.buf, WIN_W, SOLO_X - 4, TOP - 12, "SOLO", TEXT_COLOR);
+
+        let playing = [apu.channel1.enabled, apu.channel2.enabled, apu.channel3.enabled, apu.channel4.enabled];
+        for (ch, name) in CHANNEL_NAMES.iter().enumerate() {
+            let y = TOP + ch * ROW_H;
+            let color = if apu.channel_mute[ch] { OFF_COLOR } else if playing[ch] { HIGHLIGHT_COLOR } else { TEXT_COLOR };
+            font::draw_string(&mut self.buf, WIN_W, 4, y, name, color);
+            self.draw_checkbox(MUTE_X, y, apu.channel_mute[ch]);
+            self.draw_checkbox(SOLO_X, y, is_solo(&apu.channel_mute, ch));
+        }
+
+        let y = TOP + VOLUME_ROW * ROW_H;
+        font::draw_string(&mut self.buf, WIN_W, 4, y, "VOLUME", TEXT_COLOR);
+        let filled = (apu.master_volume.clamp(0.0, 1.0) * SLIDER_W as f32) as usize;
+        self.fill_rect(SLIDER_X, y + 2, SLIDER_W, 4, OFF_COLOR);
+        self.fill_rect(SLIDER_X, y + 2, filled, 4, SLIDER_COLOR);
+        let percent = format!("{:3}%", (apu.master_volume * 100.0).round() as u32);
+        font::draw_string(&mut self.buf, WIN_W, SLIDER_X + SLIDER_W + 8, y, &percent, TEXT_COLOR);
+
+        let y = TOP + HPF_ROW * ROW_H;
+        self.draw_checkbox(4, y, apu.bypass_hpf);
+        font::draw_string(&mut self.buf, WIN_W, 4 + BOX + 6, y, "BYPASS HIGH-PASS", TEXT_COLOR);
+        let y = TOP + LPF_ROW * ROW_H;
+        self.draw_checkbox(4, y, apu.bypass_lpf);
+        font::draw_string(&mut self.buf, WIN_W, 4 + BOX + 6, y, "BYPASS LOW-PASS", TEXT_COLOR);
+
+        self.window.update_with_buffer(&self.buf, WIN_W, WIN_H).ok();
+    }
+
+    fn handle_input(&mut self, apu: &mut Apu) {
+        for (key, ch) in [(Key::Key1, 0), (Key::Key2, 1), (Key::Key3, 2), (Key::Key4, 3)] {
+            if self.window.is_key_pressed(key, KeyRepeat::No) {
+                apu.channel_mute[ch] = !apu.channel_mute[ch];
+            }
+        }
+
+        let mouse_down = self.window.get_mouse_down(MouseButton::Left);
+        let clicked = mouse_down && !self.mouse_was_down;
+        self.mouse_was_down = mouse_down;
+        if !mouse_down {
+            return;
+        }
+        let Some((x, y)) = self.window.get_mouse_pos(MouseMode::Discard) else {
+            return;
+        };
+        match hit_test(x as usize, y as usize) {
+            // The slider follows drags; everything else acts on the click
+            Some(Hit::Volume(volume)) => apu.master_volume = volume,
+            Some(Hit::Mute(ch)) if clicked => apu.channel_mute[ch] = !apu.channel_mute[ch],
+            Some(Hit::Solo(ch)) if clicked => toggle_solo(&mut apu.channel_mute, ch),
+            Some(Hit::BypassHpf) if clicked => apu.bypass_hpf = !apu.bypass_hpf,
+            Some(Hit::BypassLpf) if clicked => apu.bypass_lpf = !apu.bypass_lpf,
+            _ => {}
+        }
+    }
+
+    fn draw_checkbox(&mut self, x: usize, y: usize, checked: bool) {
+        self.fill_rect(x, y, BOX, BOX, TEXT_COLOR);
+        self.fill_rect(x + 1, y + 1, BOX - 2, BOX - 2, BG_COLOR);
+        if checked {
+            self.fill_rect(x + 2, y + 2, BOX - 4, BOX - 4, HIGHLIGHT_COLOR);
+        }
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize, color: u32) {
+        for py in y..(y + h).min(WIN_H) {
+            self.buf[py * WIN_W + x..py * WIN_W + (x + w).min(WIN_W)].fill(color);
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+}
+
+/// A channel is soloed when it is the only one left unmuted.
+fn is_solo(mute: &[bool; 4], ch: usize) -> bool {
+    !mute[ch] && mute.iter().enumerate().all(|(i, &m)| i == ch || m)
+}
+
+/// Solo `ch`, or unmute everything if it was already soloed.
+fn toggle_solo(mute: &mut [bool; 4], ch: usize) {
+    let solo = !is_solo(mute, ch);
+    for (i, m) in mute.iter_mut().enumerate() {
+        *m = solo && i != ch;
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Hit {
+    Mute(usize),
+    Solo(usize),
+    Volume(f32),
+    BypassHpf,
+    BypassLpf,
+}
+
+/// What lies under window pixel (x, y).
+fn hit_test(x: usize, y: usize) -> Option<Hit> {
+    if y < TOP {
+        return None;
+    }
+    let row = (y - TOP) / ROW_H;
+    match row {
+        0..=3 if (MUTE_X..MUTE_X + BOX).contains(&x) => Some(Hit::Mute(row)),
+        0..=3 if (SOLO_X..SOLO_X + BOX).contains(&x) => Some(Hit::Solo(row)),
+        VOLUME_ROW if (SLIDER_X..=SLIDER_X + SLIDER_W).contains(&x) => {
+            Some(Hit::Volume((x - SLIDER_X) as f32 / SLIDER_W as f32))
+        }
+        HPF_ROW => Some(Hit::BypassHpf),
+        LPF_ROW => Some(Hit::BypassLpf),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle_solo() {
+        let mut mute = [false, true, false, false];
+        toggle_solo(&mut mute, 2);
+        assert_eq!(mute, [true, true, false, true]);
+        assert!(is_solo(&mute, 2));
+        toggle_solo(&mut mute, 2);
+        assert_eq!(mute, [false; 4], "soloing again unmutes all");
+    }
+
+    #[test]
+    fn test_hit_test() {
+        assert_eq!(hit_test(MUTE_X + 1, TOP + 2 * ROW_H + 3), Some(Hit::Mute(2)));
+        assert_eq!(hit_test(SOLO_X, TOP), Some(Hit::Solo(0)));
+        assert_eq!(hit_test(SLIDER_X + SLIDER_W / 2, TOP + VOLUME_ROW * ROW_H), Some(Hit::Volume(0.5)));
+        assert_eq!(hit_test(4, TOP + LPF_ROW * ROW_H), Some(Hit::BypassLpf));
+        assert_eq!(hit_test(4, TOP + ROW_H), None);
+        assert_eq!(hit_test(MUTE_X, 2), None);
+    }
+}
@@ -0,0 +1,88 @@
+use minifb::{Window, WindowOptions};
+use super::font;
+use super::{BG_COLOR, TEXT_COLOR, HEADER_COLOR};
+use crate::cartridge::Cartridge;
+
+const WIN_W: usize = 392;
+const WIN_H: usize = 240;
+const BANKS_PER_ROW: usize = 32;
+const CELL: usize = 10;
+const CELL_STRIDE: usize = 12;
+
+const UNUSED_COLOR: u32 = 0x00505050;
+const ACCESSED_COLOR: u32 = 0x0000C040;
+const ACTIVE_COLOR: u32 = 0x00FF4444;
+
+pub struct BankMapViewer {
+    pub window: Window,
+    buf: Vec<u32>,
+}
+
+impl Default for BankMapViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankMapViewer {
+    pub fn new() -> Self {
+        let window = Window::new(
+            "ROM Bank Map",
+            WIN_W,
+            WIN_H,
+            WindowOptions::default(),
+        ).expect("Failed to create bank map window");
+        BankMapViewer {
+            window,
+            buf: vec![BG_COLOR; WIN_W * WIN_H],
+        }
+    }
+
+    pub fn update(&mut self, cartridge: &Cartridge) {
+        self.buf.fill(BG_COLOR);
+
+        let accessed = cartridge.accessed_rom_banks();
+        let active = cartridge.active_rom_bank();
+        let used = accessed.iter().filter(|&&a| a).count();
+
+        let header = format!("ROM BANKS {}/{} USED  ACTIVE:{:03X}", used, accessed.len(), active);
+        font::draw_string(&mut self.buf, WIN_W, 4, 2, &header, HEADER_COLOR);
+
+        for (bank, &was_accessed) in accessed.iter().enumerate() {
+            let color = if bank == active {
+                ACTIVE_COLOR
+            } else if was_accessed {
+                ACCESSED_COLOR
+            } else {
+                UNUSED_COLOR
+            };
+            let x = 4 + (bank % BANKS_PER_ROW) * CELL_STRIDE;
+            let y = 16 + (bank / BANKS_PER_ROW) * CELL_STRIDE;
+            fill_rect(&mut self.buf, WIN_W, x, y, CELL, CELL, color);
+        }
+
+        // Legend
+        let legend_y = WIN_H - 12;
+        let mut x = 4;
+        for (label, color) in [("UNUSED", UNUSED_COLOR), ("ACCESSED", ACCESSED_COLOR), ("ACTIVE", ACTIVE_COLOR)] {
+            fill_rect(&mut self.buf, WIN_W, x, legend_y, 8, 8, color);
+            x = font::draw_string(&mut self.buf, WIN_W, x + 12, legend_y, label, TEXT_COLOR) + 12;
+        }
+
+        self.window.update_with_buffer(&self.buf, WIN_W, WIN_H).ok();
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+}
+
+fn fill_rect(buf: &mut [u32], buf_w: usize, x: usize, y: usize, w: usize, h: usize, color: u32) {
+    for py in y..y + h {
+        for px in x..x + w {
+            if px < buf_w && py * buf_w + px < buf.len() {
+                buf[py * buf_w + px] = color;
+            }
+        }
+    }
+}
@@ -187,6 +187,21 @@ pub fn disassemble<F: Fn(u16) -> u8>(addr: u16, read_fn: F) -> (String, u8) {
     }
 }
 
+/// Scan ROM $0000-$7FFF for `CALL $target` and `CALL cc,$target` instructions.
+/// Returns the address of each matching opcode.
+pub fn find_callers(rom: &[u8], target: u16) -> Vec<u16> {
+    let [lo, hi] = target.to_le_bytes();
+    let end = rom.len().min(0x8000);
+    let mut callers = Vec::new();
+    for addr in 0..end.saturating_sub(2) {
+        let is_call = matches!(rom[addr], 0xCD | 0xC4 | 0xCC | 0xD4 | 0xDC);
+        if is_call && rom[addr + 1] == lo && rom[addr + 2] == hi {
+            callers.push(addr as u16);
+        }
+    }
+    callers
+}
+
 fn read_word<F: Fn(u16) -> u8>(addr: u16, read_fn: &F) -> u16 {
     let lo = read_fn(addr.wrapping_add(1)) as u16;
     let hi = read_fn(addr.wrapping_add(2)) as u16;
@@ -218,3 +233,19 @@ fn disassemble_cb(byte: u8) -> String {
         _ => unreachable!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_callers() {
+        let mut rom = vec![0u8; 256];
+        rom[0x10..0x13].copy_from_slice(&[0xCD, 0x42, 0x00]); // CALL $0042
+        rom[0x20..0x23].copy_from_slice(&[0xC4, 0x42, 0x00]); // CALL NZ,$0042
+        rom[0x30..0x33].copy_from_slice(&[0xCD, 0x43, 0x00]); // CALL $0043
+
+        let callers = find_callers(&rom, 0x0042);
+        assert_eq!(callers, vec![0x10, 0x20]);
+    }
+}
@@ -0,0 +1,208 @@
+use minifb::{Window, WindowOptions, Key, KeyRepeat};
+use super::font;
+use super::disasm;
+use super::{BG_COLOR, TEXT_COLOR, HEADER_COLOR, HIGHLIGHT_COLOR};
+use crate::gameboy::GameBoy;
+
+const WIN_W: usize = 320;
+const WIN_H: usize = 200;
+const LINES: usize = 20;
+const LINE_H: usize = 9;
+/// Instructions shown above the PC when following it
+const LINES_BEFORE_PC: usize = 6;
+const TITLE: &str = "Disassembly (Up/Down scroll, G goto, P follow PC)";
+
+pub struct DisasmViewer {
+    pub window: Window,
+    buf: Vec<u32>,
+    /// First address shown; `None` follows the PC
+    top: Option<u16>,
+    /// First address shown last frame while following the PC
+    follow_top: u16,
+    // Goto input state, echoed in the window title
+    goto_mode: bool,
+    goto_buf: String,
+}
+
+impl Default for DisasmViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DisasmViewer {
+    pub fn new() -> Self {
+        let window = Window::new(
+            TITLE,
+            WIN_W,
+            WIN_H,
+            WindowOptions::default(),
+        ).expect("Failed to create disassembly window");
+        DisasmViewer {
+            window,
+            buf: vec![BG_COLOR; WIN_W * WIN_H],
+            top: None,
+            follow_top: 0,
+            goto_mode: false,
+            goto_buf: String::new(),
+        }
+    }
+
+    pub fn update(&mut self, gb: &GameBoy) {
+        let read = |addr| gb.cpu.bus.read_byte_no_tick(addr);
+        self.handle_input(&read);
+
+        self.buf.fill(BG_COLOR);
+        let pc = gb.cpu.pc;
+        let top = self.top.unwrap_or_else(|| back_up(pc, LINES_BEFORE_PC, &read));
+        self.follow_top = top;
+
+        let header = if self.top.is_none() { format!("PC=${:04X}  FOLLOWING PC", pc) } else { format!("PC=${:04X}", pc) };
+        font::draw_string(&mut self.buf, WIN_W, 4, 2, &header, HEADER_COLOR);
+
+        let mut y = 14;
+        for (addr, text) in disassemble_lines(top, LINES, &read) {
+            let color = if addr == pc { HIGHLIGHT_COLOR } else { TEXT_COLOR };
+            font::draw_string(&mut self.buf, WIN_W, 4, y, &text, color);
+            y += LINE_H;
+        }
+
+        self.window.update_with_buffer(&self.buf, WIN_W, WIN_H).ok();
+    }
+
+    fn handle_input(&mut self, read: &impl Fn(u16) -> u8) {
+        if self.goto_mode {
+            for &(key, ch) in &[
+                (Key::Key0, '0'), (Key::Key1, '1'), (Key::Key2, '2'), (Key::Key3, '3'),
+                (Key::Key4, '4'), (Key::Key5, '5'), (Key::Key6, '6'), (Key::Key7, '7'),
+                (Key::Key8, '8'), (Key::Key9, '9'),
+                (Key::A, 'A'), (Key::B, 'B'), (Key::C, 'C'),
+                (Key::D, 'D'), (Key::E, 'E'), (Key::F, 'F'),
+            ] {
+                if self.window.is_key_pressed(key, KeyRepeat::No) && self.goto_buf.len() < 4 {
+                    self.goto_buf.push(ch);
+                }
+            }
+            if self.window.is_key_pressed(Key::Backspace, KeyRepeat::No) {
+                self.goto_buf.pop();
+            }
+            if self.window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+                if let Ok(addr) = u16::from_str_radix(&self.goto_buf, 16) {
+                    self.top = Some(addr);
+                }
+                self.goto_mode = false;
+            }
+            if self.window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+                self.goto_mode = false;
+            }
+            if self.goto_mode {
+                self.window.set_title(&format!("Goto address: ${}_", self.goto_buf));
+            } else {
+                self.window.set_title(TITLE);
+            }
+            return;
+        }
+
+        if self.window.is_key_pressed(Key::G, KeyRepeat::No) {
+            self.goto_mode = true;
+            self.goto_buf.clear();
+            self.window.set_title("Goto address: $_");
+            return;
+        }
+        if self.window.is_key_pressed(Key::P, KeyRepeat::No) {
+            self.top = None;
+        }
+        if self.window.is_key_pressed(Key::Up, KeyRepeat::Yes) {
+            let top = self.top.unwrap_or(self.follow_top);
+            self.top = Some(back_up(top, 1, read));
+        }
+        if self.window.is_key_pressed(Key::Down, KeyRepeat::Yes) {
+            let top = self.top.unwrap_or(self.follow_top);
+            let (_, size) = disasm::disassemble(top, read);
+            self.top = Some(top.wrapping_add(size as u16));
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+}
+
+/// `count` lines of "ADDR: MNEMONIC" starting at `start`, with JR targets
+/// resolved as "-> $XXXX".
+fn disassemble_lines(start: u16, count: usize, read: &impl Fn(u16) -> u8) -> Vec<(u16, String)> {
+    let mut lines = Vec::with_capacity(count);
+    let mut addr = start;
+    for _ in 0..count {
+        let (mnemonic, size) = disasm::disassemble(addr, read);
+        let text = match relative_jump_target(addr, read) {
+            Some(target) => format!("{:04X}: {:<14}-> ${:04X}", addr, mnemonic, target),
+            None => format!("{:04X}: {}", addr, mnemonic),
+        };
+        lines.push((addr, text));
+        addr = addr.wrapping_add(size as u16);
+    }
+    lines
+}
+
+/// Destination of a JR/JR cc at `addr`.
+fn relative_jump_target(addr: u16, read: &impl Fn(u16) -> u8) -> Option<u16> {
+    match read(addr) {
+        0x18 | 0x20 | 0x28 | 0x30 | 0x38 => {
+            let offset = read(addr.wrapping_add(1)) as i8;
+            Some(addr.wrapping_add(2).wrapping_add(offset as u16))
+        }
+        _ => None,
+    }
+}
+
+/// Address `count` instructions before `target`. Code can't be decoded
+/// backwards, so this decodes forwards from well before `target` (so the
+/// stream has time to fall into step with the real instruction boundaries)
+/// and keeps the furthest start that lands exactly on `target`.
+fn back_up(target: u16, count: usize, read: &impl Fn(u16) -> u8) -> u16 {
+    let max_back = (count * 3 + 16) as u16;
+    for back in (1..=max_back).rev() {
+        let start = target.wrapping_sub(back);
+        let mut addrs = vec![start];
+        let mut addr = start;
+        while addr != target && target.wrapping_sub(addr) <= back {
+            let (_, size) = disasm::disassemble(addr, read);
+            addr = addr.wrapping_add(size as u16);
+            addrs.push(addr);
+        }
+        if addr == target && addrs.len() > count {
+            return addrs[addrs.len() - 1 - count];
+        }
+    }
+    target
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mem(bytes: &[u8]) -> impl Fn(u16) -> u8 + '_ {
+        move |addr| bytes.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    #[test]
+    fn test_relative_jump_annotation() {
+        // 0000: NOP; 0001: JR -3 (back to $0000); 0003: LD A,$12
+        let bytes = [0x00, 0x18, 0xFD, 0x3E, 0x12];
+        let lines = disassemble_lines(0, 3, &mem(&bytes));
+        assert_eq!(lines[1].0, 0x0001);
+        assert!(lines[1].1.ends_with("-> $0000"), "{}", lines[1].1);
+        assert_eq!(lines[2], (0x0003, "0003: LD A,$12".to_string()));
+    }
+
+    #[test]
+    fn test_back_up_lands_on_instruction_boundaries() {
+        // LD HL,$2121 (3) ; LD A,$21 (2) ; NOP ; NOP
+        let bytes = [0x21, 0x21, 0x21, 0x3E, 0x21, 0x00, 0x00];
+        let read = mem(&bytes);
+        assert_eq!(back_up(5, 1, &read), 3);
+        assert_eq!(back_up(5, 2, &read), 0);
+        assert_eq!(back_up(6, 0, &read), 6);
+    }
+}
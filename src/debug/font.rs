@@ -1,6 +1,9 @@
 // Embedded 8x8 bitmap font covering ASCII 0x20 (' ') through 0x7E ('~')
 // Each character is 8 bytes (one byte per row, MSB = leftmost pixel)
 
+pub const CHAR_WIDTH: usize = 8;
+pub const CHAR_HEIGHT: usize = 8;
+
 #[rustfmt::skip]
 const FONT_DATA: [u8; 760] = [
     // 0x20 ' '
@@ -218,7 +221,39 @@ pub fn draw_string(buf: &mut [u32], buf_w: usize, x: usize, y: usize, s: &str, c
     let mut cx = x;
     for ch in s.bytes() {
         draw_char(buf, buf_w, cx, y, ch, color);
-        cx += 8;
+        cx += CHAR_WIDTH;
     }
     cx
 }
+
+/// Pixel (width, height) of `text` drawn on a single line.
+pub fn measure_string(text: &str) -> (usize, usize) {
+    (text.len() * CHAR_WIDTH, CHAR_HEIGHT)
+}
+
+/// Draw `text` horizontally centered on `cx`. Returns the end X like `draw_string`.
+pub fn draw_string_centered(buf: &mut [u32], buf_w: usize, cx: usize, y: usize, text: &str, color: u32) -> usize {
+    let (width, _) = measure_string(text);
+    draw_string(buf, buf_w, cx.saturating_sub(width / 2), y, text, color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_string() {
+        assert_eq!(measure_string("Hello"), (5 * CHAR_WIDTH, CHAR_HEIGHT));
+        assert_eq!(measure_string(""), (0, CHAR_HEIGHT));
+    }
+
+    #[test]
+    fn test_draw_string_centered_start() {
+        let mut buf = vec![0u32; 200 * 8];
+        // "H" row 0 is 0xC6, so its first lit pixel is the leftmost column
+        let end = draw_string_centered(&mut buf, 200, 100, 0, "Hello", 0xFFFFFF);
+        assert_eq!(end, 120);
+        assert_eq!(buf[80], 0xFFFFFF);
+        assert!(buf[..80].iter().all(|&p| p == 0));
+    }
+}
@@ -0,0 +1,206 @@
+use minifb::{Window, WindowOptions, Key, KeyRepeat, MouseButton, MouseMode};
+use super::font;
+use super::{BG_COLOR, TEXT_COLOR, HEADER_COLOR, HIGHLIGHT_COLOR};
+use crate::gameboy::GameBoy;
+
+const ROWS: usize = 24;
+const ROW_H: usize = 10;
+const TOP: usize = 16;
+/// Columns (in characters) where the hex bytes and the ASCII sidebar start
+const HEX_COL: usize = 6;
+const ASCII_COL: usize = HEX_COL + 16 * 3 + 1;
+const WIN_W: usize = (ASCII_COL + 16) * font::CHAR_WIDTH + 8;
+const WIN_H: usize = TOP + ROWS * ROW_H + 14;
+const VISIBLE: usize = ROWS * 16;
+
+/// Frames a changed byte stays highlighted
+const CHANGE_FRAMES: u8 = 30;
+const CHANGED_COLOR: u32 = 0x00FFFF00;
+
+pub struct MemoryEditor {
+    pub window: Window,
+    buf: Vec<u32>,
+    /// First address shown (multiple of 16)
+    pub base: u16,
+    /// Byte being edited and the hex digit typed so far
+    editing: Option<(u16, Option<u8>)>,
+    last_values: [u8; VISIBLE],
+    changed_age: [u8; VISIBLE],
+    tracked_base: Option<u16>,
+}
+
+impl Default for MemoryEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryEditor {
+    pub fn new() -> Self {
+        let window = Window::new(
+            "Memory Editor (PgUp/PgDn, Home/End, click to edit)",
+            WIN_W,
+            WIN_H,
+            WindowOptions::default(),
+        ).expect("Failed to create memory editor window");
+        MemoryEditor {
+            window,
+            buf: vec![BG_COLOR; WIN_W * WIN_H],
+            base: 0xC000,
+            editing: None,
+            last_values: [0; VISIBLE],
+            changed_age: [0; VISIBLE],
+            tracked_base: None,
+        }
+    }
+
+    pub fn update(&mut self, gb: &mut GameBoy) {
+        self.handle_input(gb);
+
+        // read_byte_no_tick takes &self, so viewing never has side effects
+        let mut values = [0u8; VISIBLE];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = gb.cpu.bus.read_byte_no_tick(self.base.wrapping_add(i as u16));
+        }
+        self.track_changes(&values);
+
+        self.buf.fill(BG_COLOR);
+        let header = match self.editing {
+            Some((addr, _)) => format!("EDIT ${:04X}  (hex digits, Esc to stop)", addr),
+            None => format!("BASE ${:04X}", self.base),
+        };
+        font::draw_string(&mut self.buf, WIN_W, 4, 2, &header, HEADER_COLOR);
+
+        let cw = font::CHAR_WIDTH;
+        for row in 0..ROWS {
+            let y = TOP + row * ROW_H;
+            let row_addr = self.base.wrapping_add((row * 16) as u16);
+            font::draw_string(&mut self.buf, WIN_W, 4, y, &format!("{:04X}:", row_addr), HEADER_COLOR);
+            for col in 0..16 {
+                let i = row * 16 + col;
+                let addr = row_addr.wrapping_add(col as u16);
+                let (text, color) = match self.editing {
+                    Some((edit_addr, Some(high))) if edit_addr == addr => (format!("{:X}_", high), HIGHLIGHT_COLOR),
+                    Some((edit_addr, None)) if edit_addr == addr => (format!("{:02X}", values[i]), HIGHLIGHT_COLOR),
+                    _ if self.changed_age[i] > 0 => (format!("{:02X}", values[i]), CHANGED_COLOR),
+                    _ => (format!("{:02X}", values[i]), TEXT_COLOR),
+                };
+                font::draw_string(&mut self.buf, WIN_W, 4 + (HEX_COL + col * 3) * cw, y, &text, color);
+                let ch = if (0x20..0x7F).contains(&values[i]) { values[i] as char } else { '.' };
+                font::draw_char(&mut self.buf, WIN_W, 4 + (ASCII_COL + col) * cw, y, ch as u8, TEXT_COLOR);
+            }
+        }
+
+        self.window.update_with_buffer(&self.buf, WIN_W, WIN_H).ok();
+    }
+
+    fn track_changes(&mut self, values: &[u8; VISIBLE]) {
+        if self.tracked_base != Some(self.base) {
+            self.tracked_base = Some(self.base);
+            self.changed_age = [0; VISIBLE];
+        } else {
+            for ((age, &value), &last) in self.changed_age.iter_mut().zip(values).zip(&self.last_values) {
+                *age = if value != last { CHANGE_FRAMES } else { age.saturating_sub(1) };
+            }
+        }
+        self.last_values = *values;
+    }
+
+    fn handle_input(&mut self, gb: &mut GameBoy) {
+        if self.window.get_mouse_down(MouseButton::Left) {
+            if let Some((x, y)) = self.window.get_mouse_pos(MouseMode::Discard) {
+                if let Some(addr) = cell_at(x as usize, y as usize, self.base) {
+                    self.editing = Some((addr, None));
+                }
+            }
+        }
+
+        if let Some((addr, high)) = self.editing {
+            if self.window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+                self.editing = None;
+                return;
+            }
+            for key in self.window.get_keys_pressed(KeyRepeat::No) {
+                let Some(digit) = hex_digit(key) else { continue };
+                match high {
+                    None => self.editing = Some((addr, Some(digit))),
+                    Some(high) => {
+                        gb.cpu.bus.write_byte_no_tick(addr, (high << 4) | digit);
+                        self.editing = Some((addr.wrapping_add(1), None));
+                    }
+                }
+                break;
+            }
+            return;
+        }
+
+        if self.window.is_key_pressed(Key::PageUp, KeyRepeat::Yes) {
+            self.base = self.base.wrapping_sub(0x100);
+        }
+        if self.window.is_key_pressed(Key::PageDown, KeyRepeat::Yes) {
+            self.base = self.base.wrapping_add(0x100);
+        }
+        if self.window.is_key_pressed(Key::Home, KeyRepeat::No) {
+            self.base = 0x0000;
+        }
+        if self.window.is_key_pressed(Key::End, KeyRepeat::No) {
+            // Last page, so $FFFF is on screen
+            self.base = (0x10000 - VISIBLE) as u16;
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+}
+
+/// Address of the hex cell under window pixel (x, y).
+fn cell_at(x: usize, y: usize, base: u16) -> Option<u16> {
+    if y < TOP || x < 4 {
+        return None;
+    }
+    let row = (y - TOP) / ROW_H;
+    let char_col = (x - 4) / font::CHAR_WIDTH;
+    if row >= ROWS || !(HEX_COL..HEX_COL + 16 * 3).contains(&char_col) {
+        return None;
+    }
+    let col = (char_col - HEX_COL) / 3;
+    Some(base.wrapping_add((row * 16 + col) as u16))
+}
+
+pub(super) fn hex_digit(key: Key) -> Option<u8> {
+    let digit = match key {
+        Key::Key0 | Key::NumPad0 => 0, Key::Key1 | Key::NumPad1 => 1,
+        Key::Key2 | Key::NumPad2 => 2, Key::Key3 | Key::NumPad3 => 3,
+        Key::Key4 | Key::NumPad4 => 4, Key::Key5 | Key::NumPad5 => 5,
+        Key::Key6 | Key::NumPad6 => 6, Key::Key7 | Key::NumPad7 => 7,
+        Key::Key8 | Key::NumPad8 => 8, Key::Key9 | Key::NumPad9 => 9,
+        Key::A => 0xA, Key::B => 0xB, Key::C => 0xC,
+        Key::D => 0xD, Key::E => 0xE, Key::F => 0xF,
+        _ => return None,
+    };
+    Some(digit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_at_maps_pixels_to_addresses() {
+        let cw = font::CHAR_WIDTH;
+        let x = |col: usize| 4 + (HEX_COL + col * 3) * cw + 1;
+        assert_eq!(cell_at(x(0), TOP, 0xC000), Some(0xC000));
+        assert_eq!(cell_at(x(15), TOP + ROW_H * 2 + 3, 0xC000), Some(0xC02F));
+        assert_eq!(cell_at(x(0), 2, 0xC000), None, "header row");
+        assert_eq!(cell_at(4, TOP, 0xC000), None, "address column");
+        assert_eq!(cell_at(4 + ASCII_COL * cw, TOP, 0xC000), None, "ASCII sidebar");
+    }
+
+    #[test]
+    fn test_hex_digit() {
+        assert_eq!(hex_digit(Key::Key7), Some(7));
+        assert_eq!(hex_digit(Key::F), Some(0xF));
+        assert_eq!(hex_digit(Key::G), None);
+    }
+}
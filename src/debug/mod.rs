@@ -3,7 +3,13 @@ pub mod tiles;
 pub mod oam;
 pub mod registers;
 pub mod disasm;
+pub mod bankmap;
+pub mod disasm_viewer;
+pub mod memory;
+pub mod palette;
+pub mod audio;
 
+use crate::breakpoint::{ConditionalBreakpoint, Watchpoint};
 use crate::gameboy::GameBoy;
 use minifb::{Window, Key, KeyRepeat};
 
@@ -14,7 +20,6 @@ pub const HEADER_COLOR: u32  = 0x0000FF88;
 pub const HIGHLIGHT_COLOR: u32 = 0x00FFAA00;
 pub const BP_COLOR: u32      = 0x00FF4444;
 
-#[allow(dead_code)]
 pub enum DebugAction {
     Step,
     BreakpointHit,
@@ -24,6 +29,21 @@ pub struct DebugWindows {
     pub tile_viewer: Option<tiles::TileViewer>,
     pub oam_viewer: Option<oam::OamViewer>,
     pub register_viewer: Option<registers::RegisterViewer>,
+    pub bank_map_viewer: Option<bankmap::BankMapViewer>,
+    pub disasm_viewer: Option<disasm_viewer::DisasmViewer>,
+    pub memory_editor: Option<memory::MemoryEditor>,
+    pub map_viewer: Option<tiles::MapViewer>,
+    pub palette_editor: Option<palette::PaletteEditor>,
+    pub audio_panel: Option<audio::AudioDebugPanel>,
+    /// Kept here rather than in the register viewer so they stay armed
+    /// after it is closed
+    pub watchpoints: Vec<Watchpoint>,
+}
+
+impl Default for DebugWindows {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DebugWindows {
@@ -32,11 +52,21 @@ impl DebugWindows {
             tile_viewer: None,
             oam_viewer: None,
             register_viewer: None,
+            bank_map_viewer: None,
+            disasm_viewer: None,
+            memory_editor: None,
+            map_viewer: None,
+            palette_editor: None,
+            audio_panel: None,
+            watchpoints: Vec::new(),
         }
     }
 
-    /// Handle F1/F2/F3 toggle keys from the main window.
+    /// Handle F1/F2/F3/F4/F7 (and Shift+F3, Shift+F5, Shift+F6, Ctrl+A) toggle keys from the main window.
+    /// Plain F5 and F6 are left to the main window for save states.
     pub fn handle_toggles(&mut self, main_window: &Window) {
+        let shift_held = main_window.is_key_down(Key::LeftShift) || main_window.is_key_down(Key::RightShift);
+        let ctrl_held = main_window.is_key_down(Key::LeftCtrl) || main_window.is_key_down(Key::RightCtrl);
         if main_window.is_key_pressed(Key::F1, KeyRepeat::No) {
             if self.tile_viewer.is_some() {
                 self.tile_viewer = None;
@@ -51,17 +81,58 @@ impl DebugWindows {
                 self.oam_viewer = Some(oam::OamViewer::new());
             }
         }
-        if main_window.is_key_pressed(Key::F3, KeyRepeat::No) {
+        if shift_held && main_window.is_key_pressed(Key::F3, KeyRepeat::No) {
+            if self.bank_map_viewer.is_some() {
+                self.bank_map_viewer = None;
+            } else {
+                self.bank_map_viewer = Some(bankmap::BankMapViewer::new());
+            }
+        } else if main_window.is_key_pressed(Key::F3, KeyRepeat::No) {
             if self.register_viewer.is_some() {
                 self.register_viewer = None;
             } else {
                 self.register_viewer = Some(registers::RegisterViewer::new());
             }
         }
+        if main_window.is_key_pressed(Key::F4, KeyRepeat::No) {
+            if self.disasm_viewer.is_some() {
+                self.disasm_viewer = None;
+            } else {
+                self.disasm_viewer = Some(disasm_viewer::DisasmViewer::new());
+            }
+        }
+        if shift_held && main_window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            if self.memory_editor.is_some() {
+                self.memory_editor = None;
+            } else {
+                self.memory_editor = Some(memory::MemoryEditor::new());
+            }
+        }
+        if main_window.is_key_pressed(Key::F7, KeyRepeat::No) {
+            if self.palette_editor.is_some() {
+                self.palette_editor = None;
+            } else {
+                self.palette_editor = Some(palette::PaletteEditor::new());
+            }
+        }
+        if shift_held && main_window.is_key_pressed(Key::F6, KeyRepeat::No) {
+            if self.map_viewer.is_some() {
+                self.map_viewer = None;
+            } else {
+                self.map_viewer = Some(tiles::MapViewer::new());
+            }
+        }
+        if ctrl_held && main_window.is_key_pressed(Key::A, KeyRepeat::No) {
+            if self.audio_panel.is_some() {
+                self.audio_panel = None;
+            } else {
+                self.audio_panel = Some(audio::AudioDebugPanel::new());
+            }
+        }
     }
 
     /// Update all open debug windows. Returns an optional DebugAction.
-    pub fn update(&mut self, gb: &GameBoy, palette: &[u32; 4]) -> Option<DebugAction> {
+    pub fn update(&mut self, gb: &mut GameBoy, palette: &[u32; 4]) -> Option<DebugAction> {
         // Close windows that user has closed via X button
         if let Some(ref tv) = self.tile_viewer {
             if !tv.is_open() { self.tile_viewer = None; }
@@ -72,20 +143,43 @@ impl DebugWindows {
         if let Some(ref rv) = self.register_viewer {
             if !rv.is_open() { self.register_viewer = None; }
         }
+        if let Some(ref bv) = self.bank_map_viewer {
+            if !bv.is_open() { self.bank_map_viewer = None; }
+        }
+        if let Some(ref dv) = self.disasm_viewer {
+            if !dv.is_open() { self.disasm_viewer = None; }
+        }
+        if let Some(ref me) = self.memory_editor {
+            if !me.is_open() { self.memory_editor = None; }
+        }
+        if let Some(ref mv) = self.map_viewer {
+            if !mv.is_open() { self.map_viewer = None; }
+        }
+        if let Some(ref pe) = self.palette_editor {
+            if !pe.is_open() { self.palette_editor = None; }
+        }
+        if let Some(ref ap) = self.audio_panel {
+            if !ap.is_open() { self.audio_panel = None; }
+        }
 
         // Update tile viewer
         if let Some(ref mut tv) = self.tile_viewer {
             tv.update(
-                &gb.cpu.bus.vram,
+                &gb.cpu.bus.vram[0],
                 gb.cpu.bus.ppu.bgp,
                 palette,
             );
         }
 
+        // Update BG map viewer
+        if let Some(ref mut mv) = self.map_viewer {
+            mv.update(gb, palette);
+        }
+
         // Update OAM viewer
         if let Some(ref mut ov) = self.oam_viewer {
             ov.update(
-                &gb.cpu.bus.vram,
+                &gb.cpu.bus.vram[0],
                 &gb.cpu.bus.oam,
                 gb.cpu.bus.ppu.obp0,
                 gb.cpu.bus.ppu.obp1,
@@ -93,22 +187,66 @@ impl DebugWindows {
             );
         }
 
+        // Update bank map viewer
+        if let Some(ref mut bv) = self.bank_map_viewer {
+            bv.update(&gb.cpu.bus.cartridge);
+        }
+
+        // Update memory editor (the only viewer that writes)
+        if let Some(ref mut me) = self.memory_editor {
+            me.update(gb);
+        }
+
+        // Update audio debug panel
+        if let Some(ref mut ap) = self.audio_panel {
+            ap.update(&mut gb.cpu.bus.apu);
+        }
+
+        // Update disassembly viewer
+        if let Some(ref mut dv) = self.disasm_viewer {
+            dv.update(gb);
+        }
+
         // Update register viewer
         let mut action = None;
         if let Some(ref mut rv) = self.register_viewer {
-            action = rv.update(gb, palette);
+            action = rv.update(gb, palette, &mut self.watchpoints);
         }
+        gb.cpu.bus.watchpoints.clone_from(&self.watchpoints);
 
         action
     }
 
+    /// Update the palette window (if open). It is driven separately from
+    /// `update` because the palette list lives in the front-end.
+    pub fn update_palette_editor(
+        &mut self,
+        palettes: &[(&str, [u32; 4])],
+        active: usize,
+    ) -> Option<palette::PaletteEvent> {
+        self.palette_editor.as_mut()?.update(palettes, active)
+    }
+
     /// Returns breakpoints from the register viewer (if open).
     pub fn breakpoints(&self) -> Option<&std::collections::HashSet<u16>> {
         self.register_viewer.as_ref().map(|rv| &rv.breakpoints)
     }
 
-    #[allow(dead_code)]
+    /// Returns conditional breakpoints from the register viewer (if open).
+    pub fn conditional_breakpoints(&self) -> &[ConditionalBreakpoint] {
+        self.register_viewer.as_ref().map_or(&[], |rv| &rv.conditions)
+    }
+
+    /// Mutable access for `GameBoy::run_frame_with_breakpoints`, which keeps
+    /// each breakpoint's last result.
+    pub fn conditional_breakpoints_mut(&mut self) -> &mut [ConditionalBreakpoint] {
+        self.register_viewer.as_mut().map_or(&mut [], |rv| &mut rv.conditions)
+    }
+
     pub fn any_open(&self) -> bool {
         self.tile_viewer.is_some() || self.oam_viewer.is_some() || self.register_viewer.is_some()
+            || self.bank_map_viewer.is_some() || self.disasm_viewer.is_some()
+            || self.memory_editor.is_some() || self.map_viewer.is_some()
+            || self.palette_editor.is_some() || self.audio_panel.is_some()
     }
 }
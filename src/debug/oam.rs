@@ -11,6 +11,12 @@ pub struct OamViewer {
     buf: Vec<u32>,
 }
 
+impl Default for OamViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl OamViewer {
     pub fn new() -> Self {
         let window = Window::new(
@@ -0,0 +1,223 @@
+use minifb::{Window, WindowOptions, Key, KeyRepeat, MouseButton, MouseMode};
+use super::font;
+use super::memory::hex_digit;
+use super::{BG_COLOR, TEXT_COLOR, HEADER_COLOR, HIGHLIGHT_COLOR};
+use crate::filters::{self, CUSTOM_PALETTE};
+
+const MAX_ROWS: usize = 12;
+const ROW_H: usize = 20;
+const TOP: usize = 16;
+const SWATCH_X: usize = 16;
+const SWATCH_W: usize = 22;
+const SWATCH_H: usize = 14;
+const NAME_X: usize = SWATCH_X + 4 * SWATCH_W + 8;
+const WIN_W: usize = 320;
+// Palette rows, the custom row, and two lines of help
+const WIN_H: usize = TOP + (MAX_ROWS + 1) * ROW_H + 24;
+
+/// What the user picked in the palette window.
+pub enum PaletteEvent {
+    /// Switch to the palette at this index
+    Select(usize),
+    /// The custom palette was edited; switch to it and persist it
+    SetCustom([u32; 4]),
+}
+
+/// Swatches of every palette, plus an editable "Custom" row at the bottom.
+pub struct PaletteEditor {
+    pub window: Window,
+    buf: Vec<u32>,
+    /// Seeded on the first update from the saved custom palette, or the
+    /// active one so there is something to edit
+    custom: Option<[u32; 4]>,
+    /// Custom color being typed and the hex digits entered so far
+    editing: Option<(usize, String)>,
+    mouse_was_down: bool,
+}
+
+impl Default for PaletteEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PaletteEditor {
+    pub fn new() -> Self {
+        let window = Window::new(
+            "Palettes (click to select)",
+            WIN_W,
+            WIN_H,
+            WindowOptions::default(),
+        ).expect("Failed to create palette editor window");
+        PaletteEditor {
+            window,
+            buf: vec![BG_COLOR; WIN_W * WIN_H],
+            custom: None,
+            editing: None,
+            mouse_was_down: false,
+        }
+    }
+
+    pub fn update(&mut self, palettes: &[(&str, [u32; 4])], active: usize) -> Option<PaletteEvent> {
+        let custom = *self.custom.get_or_insert_with(|| {
+            palettes.iter()
+                .find(|(name, _)| *name == CUSTOM_PALETTE)
+                .or_else(|| palettes.get(active))
+                .map_or(filters::PALETTE_CLASSIC, |&(_, colors)| colors)
+        });
+        let rows = list_rows(palettes);
+        let event = self.handle_input(&rows, custom);
+
+        self.buf.fill(BG_COLOR);
+        font::draw_string(&mut self.buf, WIN_W, 4, 2, "PALETTES", HEADER_COLOR);
+        for (row, &idx) in rows.iter().enumerate() {
+            let (name, colors) = palettes[idx];
+            let y = TOP + row * ROW_H;
+            let color = if idx == active { HIGHLIGHT_COLOR } else { TEXT_COLOR };
+            if idx == active {
+                font::draw_char(&mut self.buf, WIN_W, 4, y + 3, b'>', HIGHLIGHT_COLOR);
+            }
+            self.draw_swatches(y, &colors);
+            font::draw_string(&mut self.buf, WIN_W, NAME_X, y + 3, name, color);
+        }
+
+        let y = TOP + rows.len() * ROW_H;
+        let custom_active = palettes.get(active).is_some_and(|(name, _)| *name == CUSTOM_PALETTE);
+        if custom_active {
+            font::draw_char(&mut self.buf, WIN_W, 4, y + 3, b'>', HIGHLIGHT_COLOR);
+        }
+        let custom = self.custom.unwrap_or(custom);
+        self.draw_swatches(y, &custom);
+        let label = match &self.editing {
+            Some((slot, digits)) => format!("#{}_  (color {})", digits, slot + 1),
+            None => CUSTOM_PALETTE.to_string(),
+        };
+        let label_color = if custom_active || self.editing.is_some() { HIGHLIGHT_COLOR } else { TEXT_COLOR };
+        font::draw_string(&mut self.buf, WIN_W, NAME_X, y + 3, &label, label_color);
+
+        font::draw_string(&mut self.buf, WIN_W, 4, WIN_H - 20, "Click a custom swatch, type RRGGBB,", TEXT_COLOR);
+        font::draw_string(&mut self.buf, WIN_W, 4, WIN_H - 10, "Enter to apply, Esc to cancel", TEXT_COLOR);
+
+        self.window.update_with_buffer(&self.buf, WIN_W, WIN_H).ok();
+        event
+    }
+
+    fn draw_swatches(&mut self, y: usize, colors: &[u32; 4]) {
+        for (i, &color) in colors.iter().enumerate() {
+            let x = SWATCH_X + i * SWATCH_W;
+            for py in y..y + SWATCH_H {
+                self.buf[py * WIN_W + x..py * WIN_W + x + SWATCH_W - 2].fill(color);
+            }
+        }
+    }
+
+    fn handle_input(&mut self, rows: &[usize], mut custom: [u32; 4]) -> Option<PaletteEvent> {
+        let mouse_down = self.window.get_mouse_down(MouseButton::Left);
+        let clicked = mouse_down && !self.mouse_was_down;
+        self.mouse_was_down = mouse_down;
+
+        if clicked {
+            if let Some((x, y)) = self.window.get_mouse_pos(MouseMode::Discard) {
+                match hit_test(x as usize, y as usize, rows.len()) {
+                    Some(Hit::Palette(row)) => {
+                        self.editing = None;
+                        return Some(PaletteEvent::Select(rows[row]));
+                    }
+                    Some(Hit::CustomSwatch(slot)) => self.editing = Some((slot, String::new())),
+                    Some(Hit::CustomRow) => return Some(PaletteEvent::SetCustom(custom)),
+                    None => {}
+                }
+            }
+        }
+
+        let (slot, digits) = self.editing.as_mut()?;
+        for key in self.window.get_keys_pressed(KeyRepeat::No) {
+            match key {
+                Key::Escape => {
+                    self.editing = None;
+                    return None;
+                }
+                Key::Backspace => { digits.pop(); }
+                Key::Enter => {
+                    if let Ok(color) = filters::parse_hex_color(&format!("#{}", digits)) {
+                        custom[*slot] = color;
+                        self.custom = Some(custom);
+                        self.editing = None;
+                        return Some(PaletteEvent::SetCustom(custom));
+                    }
+                }
+                _ => {
+                    if let Some(d) = hex_digit(key) {
+                        if digits.len() < 6 {
+                            digits.push(char::from_digit(d as u32, 16).unwrap().to_ascii_uppercase());
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+}
+
+/// Indices of the palettes listed above the custom row (all but the
+/// custom palette itself, capped to the window height).
+fn list_rows(palettes: &[(&str, [u32; 4])]) -> Vec<usize> {
+    palettes.iter()
+        .enumerate()
+        .filter(|(_, (name, _))| *name != CUSTOM_PALETTE)
+        .map(|(i, _)| i)
+        .take(MAX_ROWS)
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum Hit {
+    Palette(usize),
+    CustomSwatch(usize),
+    CustomRow,
+}
+
+/// What lies under window pixel (x, y) when `rows` palettes are listed.
+fn hit_test(x: usize, y: usize, rows: usize) -> Option<Hit> {
+    if y < TOP {
+        return None;
+    }
+    let row = (y - TOP) / ROW_H;
+    if row < rows {
+        return Some(Hit::Palette(row));
+    }
+    if row > rows {
+        return None;
+    }
+    let swatches = SWATCH_X..SWATCH_X + 4 * SWATCH_W;
+    if swatches.contains(&x) {
+        Some(Hit::CustomSwatch((x - SWATCH_X) / SWATCH_W))
+    } else {
+        Some(Hit::CustomRow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_rows_skips_custom() {
+        let palettes = [("Classic", [0; 4]), (CUSTOM_PALETTE, [1; 4]), ("Autumn", [2; 4])];
+        assert_eq!(list_rows(&palettes), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_hit_test() {
+        assert_eq!(hit_test(NAME_X, TOP + 5, 4), Some(Hit::Palette(0)));
+        assert_eq!(hit_test(NAME_X, TOP + 3 * ROW_H, 4), Some(Hit::Palette(3)));
+        assert_eq!(hit_test(SWATCH_X + 2 * SWATCH_W + 1, TOP + 4 * ROW_H + 5, 4), Some(Hit::CustomSwatch(2)));
+        assert_eq!(hit_test(NAME_X, TOP + 4 * ROW_H, 4), Some(Hit::CustomRow));
+        assert_eq!(hit_test(NAME_X, TOP + 5 * ROW_H, 4), None);
+        assert_eq!(hit_test(NAME_X, 2, 4), None);
+    }
+}
@@ -3,18 +3,32 @@ use minifb::{Window, WindowOptions, Key, KeyRepeat};
 use super::font;
 use super::disasm;
 use super::{BG_COLOR, TEXT_COLOR, HEADER_COLOR, HIGHLIGHT_COLOR, BP_COLOR, DebugAction};
+use crate::breakpoint::{ConditionalBreakpoint, Watchpoint};
 use crate::gameboy::GameBoy;
 
 const WIN_W: usize = 320;
 const WIN_H: usize = 440;
+const CALL_STACK_DEPTH: usize = 8;
+const COND_INPUT_MAX: usize = 32;
 
 pub struct RegisterViewer {
     pub window: Window,
     buf: Vec<u32>,
     pub breakpoints: HashSet<u16>,
+    pub conditions: Vec<ConditionalBreakpoint>,
     // Breakpoint input state
     input_mode: bool,
     input_buf: String,
+    // Condition input state
+    cond_input_mode: bool,
+    cond_input_buf: String,
+    cond_error: Option<String>,
+}
+
+impl Default for RegisterViewer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RegisterViewer {
@@ -29,12 +43,21 @@ impl RegisterViewer {
             window,
             buf: vec![BG_COLOR; WIN_W * WIN_H],
             breakpoints: HashSet::new(),
+            conditions: Vec::new(),
             input_mode: false,
             input_buf: String::new(),
+            cond_input_mode: false,
+            cond_input_buf: String::new(),
+            cond_error: None,
         }
     }
 
-    pub fn update(&mut self, gb: &GameBoy, _palette: &[u32; 4]) -> Option<DebugAction> {
+    pub fn update(
+        &mut self,
+        gb: &GameBoy,
+        _palette: &[u32; 4],
+        watchpoints: &mut Vec<Watchpoint>,
+    ) -> Option<DebugAction> {
         self.buf.fill(BG_COLOR);
 
         let mut y = 4;
@@ -127,11 +150,34 @@ impl RegisterViewer {
         font::draw_string(&mut self.buf, WIN_W, 4, y, &line, HIGHLIGHT_COLOR);
         y += 14;
 
+        // Call stack
+        font::draw_string(&mut self.buf, WIN_W, 4, y, "CALL STACK", HEADER_COLOR);
+        y += 12;
+
+        let frames = call_stack(gb.cpu.sp, CALL_STACK_DEPTH, |addr| {
+            gb.cpu.bus.read_byte_no_tick(addr)
+        });
+        if frames.is_empty() {
+            font::draw_string(&mut self.buf, WIN_W, 4, y, "(empty)", TEXT_COLOR);
+            y += 10;
+        } else {
+            for (ret, site) in &frames {
+                let line = match site {
+                    Some(call) => format!("-> ${:04X} ({})", ret, call),
+                    None => format!("   ${:04X} (data?)", ret),
+                };
+                let color = if site.is_some() { TEXT_COLOR } else { BP_COLOR };
+                font::draw_string(&mut self.buf, WIN_W, 4, y, &line, color);
+                y += 10;
+            }
+        }
+        y += 4;
+
         // Breakpoints
         font::draw_string(&mut self.buf, WIN_W, 4, y, "BREAKPOINTS", HEADER_COLOR);
         y += 12;
 
-        if self.breakpoints.is_empty() {
+        if self.breakpoints.is_empty() && self.conditions.is_empty() && watchpoints.is_empty() {
             font::draw_string(&mut self.buf, WIN_W, 4, y, "(none)", TEXT_COLOR);
             y += 10;
         } else {
@@ -143,25 +189,41 @@ impl RegisterViewer {
                 y += 10;
             }
         }
+        for cond in &self.conditions {
+            let line = format!("  if {}", cond);
+            font::draw_string(&mut self.buf, WIN_W, 4, y, &line, BP_COLOR);
+            y += 10;
+        }
+        for wp in watchpoints.iter() {
+            let line = format!("  watch {}", wp);
+            font::draw_string(&mut self.buf, WIN_W, 4, y, &line, BP_COLOR);
+            y += 10;
+        }
         y += 4;
 
         // Input mode display
         if self.input_mode {
             let line = format!("BP addr> {}_", self.input_buf);
             font::draw_string(&mut self.buf, WIN_W, 4, y, &line, HIGHLIGHT_COLOR);
+        } else if self.cond_input_mode {
+            let line = format!("Cond> {}_", self.cond_input_buf);
+            font::draw_string(&mut self.buf, WIN_W, 4, y, &line, HIGHLIGHT_COLOR);
+        } else if let Some(err) = &self.cond_error {
+            font::draw_string(&mut self.buf, WIN_W, 4, y, err, BP_COLOR);
         }
 
         // Help
-        let y = WIN_H - 20;
+        let y = WIN_H - 30;
         font::draw_string(&mut self.buf, WIN_W, 4, y, "B:add bp  D:del bp  I:step", TEXT_COLOR);
+        font::draw_string(&mut self.buf, WIN_W, 4, y + 10, "C:add cond/watch  X:del last", TEXT_COLOR);
 
         self.window.update_with_buffer(&self.buf, WIN_W, WIN_H).ok();
 
         // Handle keyboard input
-        self.handle_input()
+        self.handle_input(watchpoints)
     }
 
-    fn handle_input(&mut self) -> Option<DebugAction> {
+    fn handle_input(&mut self, watchpoints: &mut Vec<Watchpoint>) -> Option<DebugAction> {
         if self.input_mode {
             // Hex digit input
             for &(key, ch) in &[
@@ -196,7 +258,53 @@ impl RegisterViewer {
             return None;
         }
 
+        if self.cond_input_mode {
+            let shift = self.window.is_key_down(Key::LeftShift) || self.window.is_key_down(Key::RightShift);
+            for key in self.window.get_keys_pressed(KeyRepeat::Yes) {
+                match key {
+                    Key::Backspace => { self.cond_input_buf.pop(); }
+                    Key::Enter => {
+                        let text = self.cond_input_buf.trim_start();
+                        let result = if text.starts_with('R') || text.starts_with('W') {
+                            Watchpoint::parse(text).map(|wp| watchpoints.push(wp))
+                        } else {
+                            ConditionalBreakpoint::parse(text).map(|cond| self.conditions.push(cond))
+                        };
+                        self.cond_error = result.err();
+                        self.cond_input_buf.clear();
+                        self.cond_input_mode = false;
+                    }
+                    Key::Escape => {
+                        self.cond_input_buf.clear();
+                        self.cond_input_mode = false;
+                    }
+                    _ => {
+                        if let Some(ch) = condition_char(key, shift) {
+                            if self.cond_input_buf.len() < COND_INPUT_MAX {
+                                self.cond_input_buf.push(ch);
+                            }
+                        }
+                    }
+                }
+            }
+            return None;
+        }
+
         // Normal mode
+        if self.window.is_key_pressed(Key::C, KeyRepeat::No) {
+            self.cond_input_mode = true;
+            self.cond_input_buf.clear();
+            self.cond_error = None;
+            return None;
+        }
+
+        if self.window.is_key_pressed(Key::X, KeyRepeat::No) {
+            if self.conditions.pop().is_none() {
+                watchpoints.pop();
+            }
+            return None;
+        }
+
         if self.window.is_key_pressed(Key::B, KeyRepeat::No) {
             self.input_mode = true;
             self.input_buf.clear();
@@ -222,3 +330,98 @@ impl RegisterViewer {
         self.window.is_open()
     }
 }
+
+/// Walks up to `depth` words from SP while the stack stays in WRAM/HRAM.
+/// Each word is a candidate return address; the call site is the CALL three
+/// bytes before it (or an RST one byte before). Words with no matching call
+/// site are most likely PUSHed data and are returned with `None`.
+fn call_stack<F: Fn(u16) -> u8>(sp: u16, depth: usize, read_fn: F) -> Vec<(u16, Option<String>)> {
+    let mut frames = Vec::new();
+    let mut addr = sp;
+    while frames.len() < depth && in_stack_region(addr) && in_stack_region(addr.wrapping_add(1)) {
+        let ret = u16::from_le_bytes([read_fn(addr), read_fn(addr.wrapping_add(1))]);
+        let (call, _) = disasm::disassemble(ret.wrapping_sub(3), &read_fn);
+        let (rst, _) = disasm::disassemble(ret.wrapping_sub(1), &read_fn);
+        let site = if call.starts_with("CALL") {
+            Some(call)
+        } else if rst.starts_with("RST") {
+            Some(rst)
+        } else {
+            None
+        };
+        frames.push((ret, site));
+        addr = addr.wrapping_add(2);
+    }
+    frames
+}
+
+/// Map a key press to a character of the condition syntax
+/// (`PC==0150`, `[FF44]==90`, `PC==0150 && A==01`) or the watchpoint
+/// syntax (`W FF40-FF4B`).
+fn condition_char(key: Key, shift: bool) -> Option<char> {
+    let ch = match key {
+        Key::Key0 => '0', Key::Key1 => '1', Key::Key2 => '2', Key::Key3 => '3',
+        Key::Key4 if shift => '$',
+        Key::Key4 => '4', Key::Key5 => '5', Key::Key6 => '6',
+        Key::Key7 if shift => '&',
+        Key::Key7 => '7', Key::Key8 => '8', Key::Key9 => '9',
+        Key::A => 'A', Key::B => 'B', Key::C => 'C', Key::D => 'D', Key::E => 'E',
+        Key::F => 'F', Key::H => 'H', Key::L => 'L', Key::N => 'N', Key::P => 'P',
+        Key::R => 'R', Key::W => 'W', Key::X => 'X', Key::Z => 'Z',
+        Key::Minus => '-',
+        Key::LeftBracket => '[',
+        Key::RightBracket => ']',
+        Key::Equal => '=',
+        Key::Space => ' ',
+        _ => return None,
+    };
+    Some(ch)
+}
+
+fn in_stack_region(addr: u16) -> bool {
+    matches!(addr, 0xC000..=0xDFFF | 0xFF80..=0xFFFE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_stack_decodes_call_sites() {
+        let mut mem = vec![0u8; 0x10000];
+        // CALL $4000 at $0150, returning to $0153
+        mem[0x0150..0x0153].copy_from_slice(&[0xCD, 0x00, 0x40]);
+        // RST $38 at $4010, returning to $4011
+        mem[0x4010] = 0xFF;
+        // Stack: $4011, then pushed data $1234, then $0153
+        mem[0xFFF8..0xFFFE].copy_from_slice(&[0x11, 0x40, 0x34, 0x12, 0x53, 0x01]);
+
+        let frames = call_stack(0xFFF8, 8, |a| mem[a as usize]);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], (0x4011, Some("RST $38".to_string())));
+        assert_eq!(frames[1], (0x1234, None));
+        assert_eq!(frames[2], (0x0153, Some("CALL $4000".to_string())));
+    }
+
+    #[test]
+    fn test_condition_keys_spell_expressions() {
+        let keys = [
+            (Key::LeftBracket, false), (Key::F, false), (Key::F, false), (Key::Key4, false),
+            (Key::Key4, false), (Key::RightBracket, false), (Key::Equal, false),
+            (Key::Equal, false), (Key::Key4, true), (Key::Key9, false), (Key::Key0, false),
+        ];
+        let text: String = keys.iter().filter_map(|&(k, shift)| condition_char(k, shift)).collect();
+        assert_eq!(text, "[FF44]==$90");
+        assert!(ConditionalBreakpoint::parse(&text).is_ok());
+        assert_eq!(condition_char(Key::Key7, true), Some('&'));
+        assert_eq!(condition_char(Key::F1, false), None);
+    }
+
+    #[test]
+    fn test_call_stack_stops_outside_ram() {
+        let mem = vec![0u8; 0x10000];
+        assert!(call_stack(0xFFFE, 8, |a| mem[a as usize]).is_empty());
+        assert!(call_stack(0x8000, 8, |a| mem[a as usize]).is_empty());
+        assert_eq!(call_stack(0xC000, 8, |a| mem[a as usize]).len(), 8);
+    }
+}
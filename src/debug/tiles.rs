@@ -1,6 +1,7 @@
-use minifb::{Window, WindowOptions};
+use minifb::{Window, WindowOptions, Key, KeyRepeat};
 use super::font;
-use super::{BG_COLOR, HEADER_COLOR};
+use super::{BG_COLOR, HEADER_COLOR, BP_COLOR};
+use crate::gameboy::GameBoy;
 
 const TILE_W: usize = 16; // tiles per row in atlas
 const TILE_H: usize = 24; // tile rows in atlas (384 tiles)
@@ -15,6 +16,15 @@ const WIN_H: usize = 480;
 pub struct TileViewer {
     pub window: Window,
     buf: Vec<u32>,
+    /// VRAM and colors `buf` was last drawn from; only tiles that changed
+    /// since are redrawn
+    drawn: Option<(Box<[u8; 0x2000]>, [u32; 4])>,
+}
+
+impl Default for TileViewer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TileViewer {
@@ -28,34 +38,46 @@ impl TileViewer {
         TileViewer {
             window,
             buf: vec![BG_COLOR; WIN_W * WIN_H],
+            drawn: None,
         }
     }
 
     pub fn update(&mut self, vram: &[u8; 0x2000], bgp: u8, palette: &[u32; 4]) {
-        self.buf.fill(BG_COLOR);
-
         // Map BGP palette indices to display colors
         let pal = decode_palette(bgp, palette);
 
-        // --- Draw tile atlas (all 384 tiles) ---
-        font::draw_string(&mut self.buf, WIN_W, 4, 2, "TILE ATLAS", HEADER_COLOR);
+        // New colors redraw everything; otherwise only changed tiles
+        let previous = match &self.drawn {
+            Some((prev, drawn_pal)) if *drawn_pal == pal => Some(&**prev),
+            _ => None,
+        };
+        let dirty = dirty_tiles(vram, previous);
         let atlas_y = 14;
-        for tile_idx in 0..384usize {
+        let map_y = atlas_y + ATLAS_PX_H + 12;
+        if previous.is_none() {
+            self.buf.fill(BG_COLOR);
+            font::draw_string(&mut self.buf, WIN_W, 4, 2, "TILE ATLAS", HEADER_COLOR);
+            font::draw_string(&mut self.buf, WIN_W, 4, map_y - 10, "MAP 0 ($9800)", HEADER_COLOR);
+            font::draw_string(&mut self.buf, WIN_W, 264, map_y - 10, "MAP 1 ($9C00)", HEADER_COLOR);
+        }
+
+        // --- Draw tile atlas (all 384 tiles) ---
+        for tile_idx in (0..384usize).filter(|&i| dirty[i]) {
             let tile_data = decode_tile(vram, tile_idx * 16);
             let tx = (tile_idx % TILE_W) * 8;
             let ty = atlas_y + (tile_idx / TILE_W) * 8;
             draw_tile_pixels(&mut self.buf, WIN_W, tx + 4, ty, &tile_data, &pal);
         }
 
-        // --- Draw tile map 0 ($9800) ---
-        let map_y = atlas_y + ATLAS_PX_H + 12;
-        font::draw_string(&mut self.buf, WIN_W, 4, map_y - 10, "MAP 0 ($9800)", HEADER_COLOR);
-        draw_tilemap(&mut self.buf, WIN_W, 4, map_y, vram, 0x1800, bgp, palette);
-
-        // --- Draw tile map 1 ($9C00) ---
-        font::draw_string(&mut self.buf, WIN_W, 264, map_y - 10, "MAP 1 ($9C00)", HEADER_COLOR);
-        draw_tilemap(&mut self.buf, WIN_W, 264, map_y, vram, 0x1C00, bgp, palette);
+        // --- Draw tile maps 0 ($9800) and 1 ($9C00) ---
+        // An entry is redrawn when it points at another tile or its tile changed
+        let redraw = |addr: usize| {
+            previous.is_none_or(|prev| prev[addr] != vram[addr]) || dirty[vram[addr] as usize]
+        };
+        draw_tilemap(&mut self.buf, WIN_W, 4, map_y, vram, 0x1800, &pal, redraw);
+        draw_tilemap(&mut self.buf, WIN_W, 264, map_y, vram, 0x1C00, &pal, redraw);
 
+        self.drawn = Some((Box::new(*vram), pal));
         self.window.update_with_buffer(&self.buf, WIN_W, WIN_H).ok();
     }
 
@@ -64,6 +86,131 @@ impl TileViewer {
     }
 }
 
+const MAP_PX: usize = 256;
+const MAP_WIN_W: usize = MAP_PX + 8;
+const MAP_WIN_H: usize = MAP_PX + 28;
+const VIEWPORT_COLOR: u32 = 0x00FFFFFF;
+
+/// Full 256x256 background map as the PPU sees it: map and tile data are
+/// picked from LCDC bits 3 and 4, with the SCX/SCY viewport outlined.
+pub struct MapViewer {
+    pub window: Window,
+    buf: Vec<u32>,
+    show_sprites: bool,
+}
+
+impl Default for MapViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MapViewer {
+    pub fn new() -> Self {
+        let window = Window::new(
+            "BG Map",
+            MAP_WIN_W,
+            MAP_WIN_H,
+            WindowOptions::default(),
+        ).expect("Failed to create map viewer window");
+        MapViewer {
+            window,
+            buf: vec![BG_COLOR; MAP_WIN_W * MAP_WIN_H],
+            show_sprites: true,
+        }
+    }
+
+    pub fn update(&mut self, gb: &GameBoy, palette: &[u32; 4]) {
+        if self.window.is_key_pressed(Key::S, KeyRepeat::No) {
+            self.show_sprites = !self.show_sprites;
+        }
+
+        self.buf.fill(BG_COLOR);
+        let ppu = &gb.cpu.bus.ppu;
+        let vram = &gb.cpu.bus.vram[0];
+        let map_offset = if ppu.lcdc & 0x08 != 0 { 0x1C00 } else { 0x1800 };
+        let unsigned_tiles = ppu.lcdc & 0x10 != 0;
+
+        let header = format!(
+            "MAP ${:04X}  TILES ${:04X}",
+            0x8000 + map_offset,
+            if unsigned_tiles { 0x8000 } else { 0x8800 }
+        );
+        font::draw_string(&mut self.buf, MAP_WIN_W, 4, 2, &header, HEADER_COLOR);
+
+        let (ox, oy) = (4, 14);
+        let pal = decode_palette(ppu.bgp, palette);
+        for ty in 0..32 {
+            for tx in 0..32 {
+                let tile_idx = vram[map_offset + ty * 32 + tx];
+                let tile_data = decode_tile(vram, bg_tile_offset(tile_idx, unsigned_tiles));
+                draw_tile_pixels(&mut self.buf, MAP_WIN_W, ox + tx * 8, oy + ty * 8, &tile_data, &pal);
+            }
+        }
+
+        if self.show_sprites {
+            let height = if ppu.lcdc & 0x04 != 0 { 16 } else { 8 };
+            for sprite in gb.cpu.bus.oam.chunks_exact(4) {
+                let (y, x) = (sprite[0], sprite[1]);
+                if y == 0 || y >= 160 || x == 0 || x >= 168 {
+                    continue; // off screen
+                }
+                // Screen position -> map position through the scroll registers
+                let mx = (x as usize + ppu.scx as usize + MAP_PX - 8) % MAP_PX;
+                let my = (y as usize + ppu.scy as usize + MAP_PX - 16) % MAP_PX;
+                draw_wrapped_rect(&mut self.buf, MAP_WIN_W, ox, oy, mx, my, 8, height, BP_COLOR);
+            }
+        }
+
+        draw_wrapped_rect(
+            &mut self.buf, MAP_WIN_W, ox, oy,
+            ppu.scx as usize, ppu.scy as usize, 160, 144, VIEWPORT_COLOR,
+        );
+
+        let help = format!("S:sprites ({})", if self.show_sprites { "on" } else { "off" });
+        font::draw_string(&mut self.buf, MAP_WIN_W, 4, oy + MAP_PX + 3, &help, HEADER_COLOR);
+
+        self.window.update_with_buffer(&self.buf, MAP_WIN_W, MAP_WIN_H).ok();
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+}
+
+/// VRAM offset of a BG tile: LCDC bit 4 set uses unsigned indices from
+/// $8000, clear uses signed indices around $9000.
+fn bg_tile_offset(tile_idx: u8, unsigned_tiles: bool) -> usize {
+    if unsigned_tiles {
+        tile_idx as usize * 16
+    } else {
+        (0x1000 + (tile_idx as i8 as isize) * 16) as usize
+    }
+}
+
+/// Outline a `w`x`h` rectangle at (`x`, `y`) on the 256x256 map drawn at
+/// (`ox`, `oy`), wrapping around the edges like the PPU does.
+#[allow(clippy::too_many_arguments)]
+fn draw_wrapped_rect(
+    buf: &mut [u32], buf_w: usize, ox: usize, oy: usize,
+    x: usize, y: usize, w: usize, h: usize, color: u32,
+) {
+    let mut plot = |px: usize, py: usize| {
+        let idx = (oy + py % MAP_PX) * buf_w + ox + px % MAP_PX;
+        if idx < buf.len() {
+            buf[idx] = color;
+        }
+    };
+    for i in 0..w {
+        plot(x + i, y);
+        plot(x + i, y + h - 1);
+    }
+    for i in 0..h {
+        plot(x, y + i);
+        plot(x + w - 1, y + i);
+    }
+}
+
 fn decode_palette(bgp: u8, display_pal: &[u32; 4]) -> [u32; 4] {
     [
         display_pal[(bgp & 0x03) as usize],
@@ -73,6 +220,19 @@ fn decode_palette(bgp: u8, display_pal: &[u32; 4]) -> [u32; 4] {
     ]
 }
 
+/// Tiles whose 16 bytes differ between `vram` and `previous`; all of them
+/// when there is nothing to compare against.
+fn dirty_tiles(vram: &[u8; 0x2000], previous: Option<&[u8; 0x2000]>) -> [bool; 384] {
+    let mut dirty = [true; 384];
+    if let Some(prev) = previous {
+        for (tile, d) in dirty.iter_mut().enumerate() {
+            let bytes = tile * 16..tile * 16 + 16;
+            *d = vram[bytes.clone()] != prev[bytes];
+        }
+    }
+    dirty
+}
+
 /// Decode 16 bytes of tile data into 64 pixel color indices (0-3).
 fn decode_tile(vram: &[u8], addr: usize) -> [u8; 64] {
     let mut pixels = [0u8; 64];
@@ -101,16 +261,21 @@ fn draw_tile_pixels(buf: &mut [u32], buf_w: usize, x: usize, y: usize, pixels: &
     }
 }
 
+/// Draw the 32x32 map at `map_offset`, skipping entries (by VRAM address)
+/// that `redraw` rejects.
 fn draw_tilemap(
     buf: &mut [u32], buf_w: usize, x: usize, y: usize,
-    vram: &[u8], map_offset: usize, bgp: u8, palette: &[u32; 4],
+    vram: &[u8], map_offset: usize, pal: &[u32; 4], redraw: impl Fn(usize) -> bool,
 ) {
-    let pal = decode_palette(bgp, palette);
     // LCDC bit 4 determines addressing mode; for debug we show both modes
     // We use unsigned addressing (like LCDC bit 4 = 1) for simplicity
     for ty in 0..32 {
         for tx in 0..32 {
-            let tile_idx = vram[map_offset + ty * 32 + tx] as usize;
+            let addr = map_offset + ty * 32 + tx;
+            if !redraw(addr) {
+                continue;
+            }
+            let tile_idx = vram[addr] as usize;
             let tile_data = decode_tile(vram, tile_idx * 16);
             // Draw at half scale (skip every other pixel)
             for row in 0..8 {
@@ -125,3 +290,42 @@ fn draw_tilemap(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bg_tile_offset_addressing_modes() {
+        assert_eq!(bg_tile_offset(0x00, true), 0x0000);
+        assert_eq!(bg_tile_offset(0x80, true), 0x0800);
+        assert_eq!(bg_tile_offset(0x00, false), 0x1000);
+        assert_eq!(bg_tile_offset(0x7F, false), 0x17F0);
+        assert_eq!(bg_tile_offset(0x80, false), 0x0800);
+        assert_eq!(bg_tile_offset(0xFF, false), 0x0FF0);
+    }
+
+    #[test]
+    fn test_dirty_tiles() {
+        let mut vram = [0u8; 0x2000];
+        assert!(dirty_tiles(&vram, None).iter().all(|&d| d));
+
+        let previous = vram;
+        vram[5 * 16 + 3] = 0xFF;
+        vram[383 * 16 + 15] = 0x01;
+        let dirty = dirty_tiles(&vram, Some(&previous));
+        let changed: Vec<usize> = (0..384).filter(|&i| dirty[i]).collect();
+        assert_eq!(changed, vec![5, 383]);
+    }
+
+    #[test]
+    fn test_viewport_rect_wraps() {
+        let mut buf = vec![0u32; MAP_PX * MAP_PX];
+        draw_wrapped_rect(&mut buf, MAP_PX, 0, 0, 200, 150, 160, 144, 1);
+        // Top-left corner and the wrapped bottom-right corner
+        assert_eq!(buf[150 * MAP_PX + 200], 1);
+        assert_eq!(buf[(150 + 143 - 256) * MAP_PX + (200 + 159 - 256)], 1);
+        // Inside the rectangle stays untouched
+        assert_eq!(buf[160 * MAP_PX + 210], 0);
+    }
+}
@@ -1,15 +1,107 @@
+use crate::config::{ColorCorrection, Config, CrtFilterConfig};
+#[cfg(feature = "native")]
+use crate::debug::font;
+#[cfg(feature = "native")]
+use std::fs::File;
+#[cfg(feature = "native")]
+use std::io::{BufWriter, Write};
+#[cfg(feature = "native")]
+use std::path::{Path, PathBuf};
+
 pub const PALETTE_CLASSIC: [u32; 4] = [0x00E0F8D0, 0x0088C070, 0x00346856, 0x00081820];
 pub const PALETTE_DMG_GREEN: [u32; 4] = [0x009BBC0F, 0x008BAC0F, 0x00306230, 0x000F380F];
 pub const PALETTE_GRAYSCALE: [u32; 4] = [0x00FFFFFF, 0x00AAAAAA, 0x00555555, 0x00000000];
-pub const PALETTE_POCKET: [u32; 4] = [0x00C4CFA1, 0x008B956D, 0x004D533C, 0x001F1F1F];
+/// Game Boy Pocket: lighter, neutral gray LCD without the DMG's green tint
+pub const PALETTE_POCKET: [u32; 4] = [0x00C8C8C8, 0x00888888, 0x00484848, 0x00080808];
+/// Game Boy Light with the backlight on: slightly bluish whites
+pub const PALETTE_GBL: [u32; 4] = [0x00D8ECF4, 0x008CA8B4, 0x00465C68, 0x000C1820];
 
-pub const PALETTES: [(&str, [u32; 4]); 4] = [
+pub const PALETTES: [(&str, [u32; 4]); 5] = [
     ("Classic", PALETTE_CLASSIC),
     ("DMG Green", PALETTE_DMG_GREEN),
     ("Grayscale", PALETTE_GRAYSCALE),
     ("Pocket", PALETTE_POCKET),
+    ("GBL", PALETTE_GBL),
+];
+
+/// Name of the palette edited in the F7 palette window.
+pub const CUSTOM_PALETTE: &str = "Custom";
+
+/// Built-in palettes followed by any valid `[[palette]]` entries from the
+/// config, then the custom palette if one has been saved.
+pub fn load_palettes_from_config(config: &Config) -> Vec<(&str, [u32; 4])> {
+    let mut palettes = PALETTES.to_vec();
+    for entry in &config.palettes {
+        match parse_palette_colors(&entry.colors) {
+            Ok(colors) => palettes.push((entry.name.as_str(), colors)),
+            Err(e) => eprintln!("Skipping palette '{}': {}", entry.name, e),
+        }
+    }
+    if !config.display.custom_palette.is_empty() {
+        match parse_palette_colors(&config.display.custom_palette) {
+            Ok(colors) => palettes.push((CUSTOM_PALETTE, colors)),
+            Err(e) => eprintln!("Skipping custom palette: {}", e),
+        }
+    }
+    palettes
+}
+
+fn parse_palette_colors(colors: &[String]) -> Result<[u32; 4], String> {
+    if colors.len() != 4 {
+        return Err(format!("expected 4 colors, got {}", colors.len()));
+    }
+    let mut result = [0u32; 4];
+    for (slot, s) in result.iter_mut().zip(colors) {
+        *slot = parse_hex_color(s)?;
+    }
+    Ok(result)
+}
+
+/// Parse a "#RRGGBB" string into 0x00RRGGBB.
+pub fn parse_hex_color(s: &str) -> Result<u32, String> {
+    let hex = s.strip_prefix('#').ok_or_else(|| format!("color '{}' must start with '#'", s))?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("color '{}' must be #RRGGBB", s));
+    }
+    u32::from_str_radix(hex, 16).map_err(|e| format!("color '{}': {}", s, e))
+}
+
+/// Format 0x00RRGGBB as "#RRGGBB".
+pub fn format_hex_color(color: u32) -> String {
+    format!("#{:06X}", color & 0x00FF_FFFF)
+}
+
+/// Approximate DMG-01 panel response from dark to light, at luminance
+/// 0, 85, 170 and 255. Pixels never reach true black or white.
+const DMG_LCD_CURVE: [[u8; 3]; 4] = [
+    [0x2C, 0x3A, 0x1E],
+    [0x55, 0x6B, 0x2F],
+    [0x87, 0x9C, 0x46],
+    [0xB4, 0xC2, 0x8A],
 ];
 
+/// Map one 0x00RRGGBB color through the LCD response for `mode`.
+pub fn apply_color_correction(pixel: u32, mode: ColorCorrection) -> u32 {
+    let (r, g, b) = ((pixel >> 16) & 0xFF, (pixel >> 8) & 0xFF, pixel & 0xFF);
+    let (r, g, b) = match mode {
+        ColorCorrection::None => return pixel,
+        ColorCorrection::Dmg => {
+            let luma = (r * 299 + g * 587 + b * 114) / 1000;
+            let segment = (luma / 85).min(2) as usize;
+            let t = luma - segment as u32 * 85;
+            let (lo, hi) = (DMG_LCD_CURVE[segment], DMG_LCD_CURVE[segment + 1]);
+            let lerp = |c: usize| (lo[c] as u32 * (85 - t) + hi[c] as u32 * t) / 85;
+            (lerp(0), lerp(1), lerp(2))
+        }
+        ColorCorrection::Cgb => {
+            // Channel mixing on 5-bit values, as the CGB's own colors are
+            let (r, g, b) = (r >> 3, g >> 3, b >> 3);
+            ((r * 13 + g * 2 + b) >> 1, (g * 3 + b) << 1, (r * 3 + g * 2 + b * 11) >> 1)
+        }
+    };
+    (r << 16) | (g << 8) | b
+}
+
 pub fn upscale_nearest(src: &[u32], dst: &mut [u32], src_w: usize, src_h: usize) {
     let dst_w = src_w * 2;
     for y in 0..src_h {
@@ -25,6 +117,74 @@ pub fn upscale_nearest(src: &[u32], dst: &mut [u32], src_w: usize, src_h: usize)
     }
 }
 
+/// Bilinear resize of `src` (src_w x src_h) into `dst` (dst_w x dst_h),
+/// sampling at pixel centers. Equal sizes copy the image unchanged.
+pub fn upscale_bilinear(src: &[u32], dst: &mut [u32], src_w: usize, src_h: usize, dst_w: usize, dst_h: usize) {
+    // Source coordinate of each destination column/row in 24.8 fixed point
+    let sample = |d: usize, src_len: usize, dst_len: usize| -> (usize, usize, u32) {
+        let pos = ((2 * d + 1) * src_len * 256 / (2 * dst_len)).saturating_sub(128);
+        let i = (pos >> 8).min(src_len - 1);
+        (i, (i + 1).min(src_len - 1), (pos & 0xFF) as u32)
+    };
+    let columns: Vec<_> = (0..dst_w).map(|dx| sample(dx, src_w, dst_w)).collect();
+    // Horizontal pass of one source row, cached since many output rows share it
+    let stretch_row = |y: usize, out: &mut Vec<u32>| {
+        let row = &src[y * src_w..][..src_w];
+        out.clear();
+        out.extend(columns.iter().map(|&(x0, x1, wx)| blend(row[x0], row[x1], wx)));
+    };
+    let (mut top, mut bottom) = (Vec::with_capacity(dst_w), Vec::with_capacity(dst_w));
+    let mut cached = None;
+    for (dy, out) in dst.chunks_exact_mut(dst_w).take(dst_h).enumerate() {
+        let (y0, y1, wy) = sample(dy, src_h, dst_h);
+        if cached != Some(y0) {
+            stretch_row(y0, &mut top);
+            stretch_row(y1, &mut bottom);
+            cached = Some(y0);
+        }
+        if wy == 0 {
+            out.copy_from_slice(&top);
+        } else {
+            for ((o, &a), &b) in out.iter_mut().zip(&top).zip(&bottom) {
+                *o = blend(a, b, wy);
+            }
+        }
+    }
+}
+
+/// Mix `a` and `b` per channel, `weight`/256 of the way towards `b`.
+fn blend(a: u32, b: u32, weight: u32) -> u32 {
+    if weight == 0 {
+        return a;
+    }
+    // Red and blue share one multiply, green gets the other
+    let inv = 256 - weight;
+    let rb = ((a & 0xFF00FF) * inv + (b & 0xFF00FF) * weight) >> 8;
+    let g = ((a & 0x00FF00) * inv + (b & 0x00FF00) * weight) >> 8;
+    (rb & 0xFF00FF) | (g & 0x00FF00)
+}
+
+/// Largest whole multiple of the 160x144 screen that fits in
+/// `dst_w` x `dst_h` (at least 1x), centered: (x, y, width, height).
+pub fn integer_viewport(dst_w: usize, dst_h: usize) -> (usize, usize, usize, usize) {
+    let scale = (dst_w / 160).min(dst_h / 144).max(1);
+    let (w, h) = (160 * scale, 144 * scale);
+    (dst_w.saturating_sub(w) / 2, dst_h.saturating_sub(h) / 2, w, h)
+}
+
+/// Nearest-neighbour scale `src` into `viewport` of `dst` (`dst_w` wide)
+/// and fill everything around it with `border`.
+pub fn letterbox(src: &[u32], src_w: usize, src_h: usize, dst: &mut [u32], dst_w: usize, viewport: (usize, usize, usize, usize), border: u32) {
+    let (vx, vy, vw, vh) = viewport;
+    dst.fill(border);
+    for (dy, row) in dst.chunks_exact_mut(dst_w).skip(vy).take(vh).enumerate() {
+        let src_row = &src[dy * src_h / vh * src_w..][..src_w];
+        for (dx, out) in row.iter_mut().skip(vx).take(vw).enumerate() {
+            *out = src_row[dx * src_w / vw];
+        }
+    }
+}
+
 pub fn apply_scanlines(buf: &mut [u32], width: usize, height: usize) {
     for y in (1..height).step_by(2) {
         let row_start = y * width;
@@ -37,3 +197,522 @@ pub fn apply_scanlines(buf: &mut [u32], width: usize, height: usize) {
         }
     }
 }
+
+/// Draw `text` at (x, y) over a 50% blended `bg` box, for on-screen messages.
+#[cfg(feature = "native")]
+pub fn render_text_overlay(buffer: &mut [u32], width: usize, text: &str, x: usize, y: usize, fg: u32, bg: u32) {
+    let (text_w, text_h) = font::measure_string(text);
+    let half_bg = (bg >> 1) & 0x7F7F7F;
+    for py in y..y + text_h {
+        for px in x..(x + text_w).min(width) {
+            if let Some(pixel) = buffer.get_mut(py * width + px) {
+                *pixel = ((*pixel >> 1) & 0x7F7F7F) + half_bg;
+            }
+        }
+    }
+    font::draw_string(buffer, width, x, y, text, fg);
+}
+
+/// CRT look for the upscaled frame: screen curvature and phosphor bloom.
+/// Keeps its lookup table and scratch buffers between frames.
+pub struct CrtFilter {
+    width: usize,
+    height: usize,
+    /// Source index for each output pixel of the barrel distortion, `None`
+    /// outside the curved screen; rebuilt when the factor changes
+    distortion_map: Vec<Option<u32>>,
+    distortion_factor: f32,
+    scratch: Vec<u32>,
+    glow: Vec<[u16; 3]>,
+}
+
+/// Channels below this do not bloom
+const GLOW_THRESHOLD: u32 = 0x80;
+
+impl CrtFilter {
+    pub fn new(width: usize, height: usize) -> Self {
+        CrtFilter {
+            width,
+            height,
+            distortion_map: Vec::new(),
+            distortion_factor: 0.0,
+            scratch: vec![0; width * height],
+            glow: vec![[0; 3]; width * height],
+        }
+    }
+
+    pub fn apply(&mut self, buf: &mut [u32], config: &CrtFilterConfig) {
+        self.phosphor_glow(buf, config.glow_radius);
+        self.barrel_distortion(buf, config.curvature);
+    }
+
+    /// Bend the image outwards from the center like a curved tube; `factor`
+    /// is how far the corners are pulled in (0 = flat). Pixels that leave
+    /// the screen go black.
+    pub fn barrel_distortion(&mut self, buf: &mut [u32], factor: f32) {
+        if factor <= 0.0 {
+            return;
+        }
+        if self.distortion_map.is_empty() || self.distortion_factor != factor {
+            self.build_distortion_map(factor);
+        }
+        for (out, src) in self.scratch.iter_mut().zip(&self.distortion_map) {
+            *out = src.map_or(0, |i| buf[i as usize]);
+        }
+        buf.copy_from_slice(&self.scratch);
+    }
+
+    fn build_distortion_map(&mut self, factor: f32) {
+        let (w, h) = (self.width as f32, self.height as f32);
+        self.distortion_factor = factor;
+        self.distortion_map.clear();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let u = (x as f32 + 0.5) / w * 2.0 - 1.0;
+                let v = (y as f32 + 0.5) / h * 2.0 - 1.0;
+                let bend = 1.0 + factor * (u * u + v * v);
+                let (su, sv) = (u * bend, v * bend);
+                let src = (su.abs() <= 1.0 && sv.abs() <= 1.0).then(|| {
+                    let sx = (((su + 1.0) / 2.0 * w) as usize).min(self.width - 1);
+                    let sy = (((sv + 1.0) / 2.0 * h) as usize).min(self.height - 1);
+                    (sy * self.width + sx) as u32
+                });
+                self.distortion_map.push(src);
+            }
+        }
+    }
+
+    /// Bloom: box-blur the bright part of each channel over `radius` pixels
+    /// and add half of it back on top.
+    pub fn phosphor_glow(&mut self, buf: &mut [u32], radius: u8) {
+        if radius == 0 {
+            return;
+        }
+        let (w, h, r) = (self.width, self.height, radius as usize);
+        for (g, &c) in self.glow.iter_mut().zip(buf.iter()) {
+            *g = [16, 8, 0].map(|shift| ((c >> shift) & 0xFF).saturating_sub(GLOW_THRESHOLD) as u16);
+        }
+        // Separable box blur with running sums, rows then columns
+        let mut line = Vec::with_capacity(w.max(h));
+        for y in 0..h {
+            line.clear();
+            line.extend((0..w).map(|x| self.glow[y * w + x]));
+            box_blur(&line, r, |x, v| self.glow[y * w + x] = v);
+        }
+        for x in 0..w {
+            line.clear();
+            line.extend((0..h).map(|y| self.glow[y * w + x]));
+            box_blur(&line, r, |y, v| self.glow[y * w + x] = v);
+        }
+        for (c, g) in buf.iter_mut().zip(&self.glow) {
+            let add = |shift: u32, extra: u16| (((*c >> shift) & 0xFF) + extra as u32 / 2).min(0xFF) << shift;
+            *c = add(16, g[0]) | add(8, g[1]) | add(0, g[2]);
+        }
+    }
+}
+
+/// Average each entry of `line` with its `r` neighbours on each side
+/// (clamped at the ends), passing the results to `out`.
+fn box_blur(line: &[[u16; 3]], r: usize, mut out: impl FnMut(usize, [u16; 3])) {
+    let len = line.len();
+    let at = |i: isize| line[i.clamp(0, len as isize - 1) as usize];
+    let mut sum = [0u32; 3];
+    for i in -(r as isize)..=r as isize {
+        for (s, v) in sum.iter_mut().zip(at(i)) {
+            *s += v as u32;
+        }
+    }
+    let count = (2 * r + 1) as u32;
+    for i in 0..len {
+        out(i, sum.map(|s| (s / count) as u16));
+        let (add, sub) = (at((i + r + 1) as isize), at(i as isize - r as isize));
+        for c in 0..3 {
+            sum[c] = sum[c] + add[c] as u32 - sub[c] as u32;
+        }
+    }
+}
+
+/// Zero crossings of the sinc kernel on each side of its center
+const RESAMPLER_ZERO_CROSSINGS: f64 = 8.0;
+/// Cutoff as a fraction of the output Nyquist, leaving room for the transition band
+const RESAMPLER_CUTOFF: f64 = 0.9;
+/// Sub-sample positions tabulated per input sample; others are interpolated
+const RESAMPLER_PHASES: usize = 64;
+
+/// Windowed-sinc (Blackman) low-pass resampler for one audio channel.
+/// Feed it every input sample; it band-limits to the output rate before
+/// decimating, so tones above the output Nyquist don't alias back down.
+pub struct Resampler {
+    /// Input history, stored twice so the newest `taps` are always contiguous
+    history: Vec<f32>,
+    pos: usize,
+    taps: usize,
+    /// Output clock in input-sample units; an output is due when it passes 1
+    phase: f64,
+    ratio: f64,
+    /// `RESAMPLER_PHASES + 1` rows of `taps` coefficients, oldest input first
+    kernel: Vec<f32>,
+}
+
+impl Default for Resampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resampler {
+    pub fn new() -> Self {
+        Resampler { history: Vec::new(), pos: 0, taps: 0, phase: 0.0, ratio: 0.0, kernel: Vec::new() }
+    }
+
+    /// Push one input sample. `ratio` is output rate / input rate, at most 1
+    /// (larger values are clamped). Returns an output sample when one is due.
+    pub fn process(&mut self, input: f32, ratio: f64) -> Option<f32> {
+        let ratio = ratio.min(1.0);
+        if ratio <= 0.0 {
+            return None;
+        }
+        if ratio != self.ratio {
+            self.build_kernel(ratio);
+        }
+
+        self.history[self.pos] = input;
+        self.history[self.pos + self.taps] = input;
+        self.pos = (self.pos + 1) % self.taps;
+
+        self.phase += ratio;
+        if self.phase < 1.0 {
+            return None;
+        }
+        self.phase -= 1.0;
+
+        // The output instant lies `delay` input samples before the newest one
+        let delay = (self.phase / ratio).min(1.0) * RESAMPLER_PHASES as f64;
+        let row = (delay as usize).min(RESAMPLER_PHASES - 1);
+        let frac = (delay - row as f64) as f32;
+        let a = &self.kernel[row * self.taps..(row + 1) * self.taps];
+        let b = &self.kernel[(row + 1) * self.taps..(row + 2) * self.taps];
+        let window = &self.history[self.pos..self.pos + self.taps];
+        let out = window.iter().zip(a.iter().zip(b))
+            .map(|(&x, (&ka, &kb))| x * (ka + (kb - ka) * frac))
+            .sum();
+        Some(out)
+    }
+
+    /// Tabulate the kernel for `ratio`. The history is kept when the tap
+    /// count doesn't change, so small rate adjustments don't click.
+    fn build_kernel(&mut self, ratio: f64) {
+        let cutoff = ratio * RESAMPLER_CUTOFF;
+        let half_width = RESAMPLER_ZERO_CROSSINGS / cutoff;
+        let taps = 2 * half_width.ceil() as usize;
+        if taps != self.taps {
+            self.taps = taps;
+            self.history = vec![0.0; 2 * taps];
+            self.pos = 0;
+        }
+        self.ratio = ratio;
+
+        self.kernel = Vec::with_capacity((RESAMPLER_PHASES + 1) * taps);
+        for row in 0..=RESAMPLER_PHASES {
+            let delay = row as f64 / RESAMPLER_PHASES as f64;
+            let start = self.kernel.len();
+            for i in 0..taps {
+                // Tap `i` holds the input (taps - 1 - i) samples before the newest
+                let t = i as f64 + 1.0 + delay - (taps / 2) as f64;
+                self.kernel.push((sinc(cutoff * t) * blackman(t / half_width)) as f32);
+            }
+            // Unity gain at DC for every phase
+            let sum: f32 = self.kernel[start..].iter().sum();
+            self.kernel[start..].iter_mut().for_each(|k| *k /= sum);
+        }
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        let px = std::f64::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// Blackman window over -1..=1, zero outside
+fn blackman(x: f64) -> f64 {
+    if x.abs() >= 1.0 {
+        return 0.0;
+    }
+    let px = std::f64::consts::PI * x;
+    0.42 + 0.5 * px.cos() + 0.08 * (2.0 * px).cos()
+}
+
+/// Longest recording `VideoRecorder` accepts, in frames (300 s at ~59.7 fps).
+#[cfg(feature = "native")]
+pub const VIDEO_MAX_FRAMES: u32 = 300 * 60;
+
+/// Records native 160x144 frames as raw RGB24 (ffmpeg `-f rawvideo -pix_fmt rgb24`).
+/// Frames are buffered and flushed to disk as they fill, and on drop.
+#[cfg(feature = "native")]
+pub struct VideoRecorder {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    frames: u32,
+}
+
+#[cfg(feature = "native")]
+impl VideoRecorder {
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        Ok(VideoRecorder { writer: BufWriter::new(file), path: path.to_path_buf(), frames: 0 })
+    }
+
+    /// Append one frame of 0x00RRGGBB pixels. Returns false once the
+    /// length cap is reached; the frame is not written then.
+    pub fn push_frame(&mut self, pixels: &[u32]) -> Result<bool, String> {
+        if self.frames >= VIDEO_MAX_FRAMES {
+            return Ok(false);
+        }
+        let mut rgb = Vec::with_capacity(pixels.len() * 3);
+        for &color in pixels {
+            rgb.extend_from_slice(&[(color >> 16) as u8, (color >> 8) as u8, color as u8]);
+        }
+        self.writer.write_all(&rgb).map_err(|e| format!("Failed to write video frame: {}", e))?;
+        self.frames += 1;
+        Ok(true)
+    }
+
+    /// Flush to disk and return the ffmpeg command that converts the file.
+    pub fn finish(mut self) -> Result<String, String> {
+        self.writer.flush().map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+        Ok(format!(
+            "ffmpeg -f rawvideo -pix_fmt rgb24 -s 160x144 -r 59.73 -i {} out.mp4 ({} frames)",
+            self.path.display(), self.frames
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_color_correction() {
+        for color in PALETTE_GRAYSCALE {
+            assert_eq!(apply_color_correction(color, ColorCorrection::None), color);
+        }
+
+        let dmg: Vec<u32> = PALETTE_GRAYSCALE.iter()
+            .map(|&c| apply_color_correction(c, ColorCorrection::Dmg))
+            .collect();
+        assert_eq!(dmg[0], 0x00B4C28A, "white maps to the lightest LCD shade");
+        assert_eq!(dmg[3], 0x002C3A1E, "black maps to the darkest LCD shade");
+        for shade in &dmg {
+            let (r, g, b) = ((shade >> 16) & 0xFF, (shade >> 8) & 0xFF, shade & 0xFF);
+            assert!(g > r && g > b, "green tint: {:06X}", shade);
+        }
+        assert!(dmg.windows(2).all(|w| w[0] > w[1]), "order is kept");
+
+        assert_eq!(apply_color_correction(0x00FFFFFF, ColorCorrection::Cgb), 0x00F8F8F8);
+        assert_eq!(apply_color_correction(0x00000000, ColorCorrection::Cgb), 0);
+        let red = apply_color_correction(0x00FF0000, ColorCorrection::Cgb);
+        assert!(red & 0x0000FF > 0, "red bleeds into blue: {:06X}", red);
+    }
+
+    #[test]
+    fn test_upscale_bilinear() {
+        // Same size is an exact copy, so the 2x window keeps hard edges
+        let src = [0x00FFFFFF, 0x00000000, 0x00000000, 0x00FFFFFF];
+        let mut same = [0u32; 4];
+        upscale_bilinear(&src, &mut same, 2, 2, 2, 2);
+        assert_eq!(same, src);
+
+        // Enlarging blends across the edge between the two columns
+        let mut wide = [0u32; 6];
+        upscale_bilinear(&src[..2], &mut wide, 2, 1, 6, 1);
+        assert_eq!(wide[0], 0x00FFFFFF);
+        assert_eq!(wide[5], 0x00000000);
+        assert!(wide[2] != 0x00FFFFFF && wide[2] != 0, "edge pixel is smoothed: {:06X}", wide[2]);
+        assert!(wide.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn test_crt_barrel_distortion() {
+        let (w, h) = (32, 24);
+        let src: Vec<u32> = (0..(w * h) as u32).map(|i| 0x00100000 | i).collect();
+        let mut crt = CrtFilter::new(w, h);
+
+        let mut flat = src.clone();
+        crt.barrel_distortion(&mut flat, 0.0);
+        assert_eq!(flat, src);
+
+        let mut curved = src.clone();
+        crt.barrel_distortion(&mut curved, 0.2);
+        assert_eq!(curved[0], 0, "corners fall off the curved screen");
+        let center = (h / 2) * w + w / 2;
+        assert_eq!(curved[center], src[center]);
+    }
+
+    #[test]
+    fn test_crt_phosphor_glow_spreads_bright_pixels() {
+        let (w, h) = (9, 9);
+        let mut buf = vec![0x00101010; w * h];
+        buf[4 * w + 4] = 0x00FFFFFF;
+        let mut crt = CrtFilter::new(w, h);
+        crt.phosphor_glow(&mut buf, 1);
+        assert_eq!(buf[4 * w + 4], 0x00FFFFFF);
+        assert!(buf[4 * w + 5] > 0x00101010, "neighbour glows: {:06X}", buf[4 * w + 5]);
+        assert_eq!(buf[0], 0x00101010, "far pixels are untouched");
+
+        // Nothing above the threshold, nothing to bloom
+        let mut dim = vec![0x00404040; w * h];
+        crt.phosphor_glow(&mut dim, 2);
+        assert!(dim.iter().all(|&c| c == 0x00404040));
+    }
+
+    #[test]
+    fn test_letterbox_integer_scale() {
+        // 1920x1080 fits 7x (1120x1008), pillarboxed and letterboxed
+        assert_eq!(integer_viewport(1920, 1080), (400, 36, 1120, 1008));
+        assert_eq!(integer_viewport(320, 288), (0, 0, 320, 288));
+        assert_eq!(integer_viewport(100, 100), (0, 0, 160, 144), "never below 1x");
+
+        let src = [1, 2, 3, 4];
+        let mut dst = [9u32; 6 * 4];
+        letterbox(&src, 2, 2, &mut dst, 6, (1, 0, 4, 4), 0);
+        assert_eq!(dst[..6], [0, 1, 1, 2, 2, 0]);
+        assert_eq!(dst[18..], [0, 3, 3, 4, 4, 0]);
+    }
+
+    #[test]
+    fn test_pocket_and_gbl_palettes() {
+        for color in PALETTE_POCKET {
+            let (r, g, b) = ((color >> 16) & 0xFF, (color >> 8) & 0xFF, color & 0xFF);
+            assert!(r == g && g == b, "Pocket is neutral gray: {:06X}", color);
+        }
+        let white = PALETTE_GBL[0];
+        assert!(white & 0xFF > (white >> 16) & 0xFF, "GBL white leans blue");
+
+        for (i, (name, _)) in PALETTES.iter().enumerate() {
+            let mut config = Config::default();
+            config.display.palette = name.to_string();
+            assert_eq!(config.palette_index(), i, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#FF0000"), Ok(0x00FF0000));
+        assert_eq!(parse_hex_color("#8b4513"), Ok(0x008B4513));
+        assert!(parse_hex_color("FF0000").is_err());
+        assert!(parse_hex_color("#FF00").is_err());
+        assert!(parse_hex_color("#GG0000").is_err());
+    }
+
+    #[test]
+    fn test_custom_palette_round_trips_through_config() {
+        let colors = [0x00123456, 0x00ABCDEF, 0x00000000, 0x00FFFFFF];
+        let mut config = Config::default();
+        config.display.custom_palette = colors.iter().map(|&c| format_hex_color(c)).collect();
+        assert_eq!(config.display.custom_palette[1], "#ABCDEF");
+
+        let text = toml::to_string(&config).unwrap();
+        let config: Config = toml::from_str(&text).unwrap();
+        let palettes = load_palettes_from_config(&config);
+        assert_eq!(palettes.last(), Some(&(CUSTOM_PALETTE, colors)));
+        assert_eq!(load_palettes_from_config(&Config::default()).len(), PALETTES.len());
+    }
+
+    #[test]
+    fn test_load_palettes_appends_config_entries() {
+        let config: Config = toml::from_str(
+            r##"
+            [controls]
+            up = "Up"
+            down = "Down"
+            left = "Left"
+            right = "Right"
+            a = "Z"
+            b = "X"
+            select = "Backspace"
+            start = "Enter"
+
+            [display]
+            scale = "4x"
+            palette = "Autumn"
+            scanlines = false
+
+            [speed]
+            fast_forward_multiplier = 0
+
+            [[palette]]
+            name = "Autumn"
+            colors = ["#F4A460", "#8B4513", "#2E8B57", "#006400"]
+
+            [[palette]]
+            name = "Broken"
+            colors = ["#FFFFFF"]
+            "##,
+        ).unwrap();
+
+        let palettes = load_palettes_from_config(&config);
+        assert_eq!(palettes.len(), PALETTES.len() + 1);
+        assert_eq!(palettes[PALETTES.len()], ("Autumn", [0x00F4A460, 0x008B4513, 0x002E8B57, 0x00006400]));
+    }
+
+    /// RMS of `freq` Hz through a 262144 -> 48000 Hz resampler, after it settles.
+    fn resampled_rms(freq: f64) -> f32 {
+        let (rate_in, rate_out) = (262_144.0, 48_000.0);
+        let mut resampler = Resampler::new();
+        let out: Vec<f32> = (0..rate_in as usize / 10)
+            .filter_map(|i| {
+                let x = (2.0 * std::f64::consts::PI * freq * i as f64 / rate_in).sin() as f32;
+                resampler.process(x, rate_out / rate_in)
+            })
+            .collect();
+        assert!((4799..=4800).contains(&out.len()), "{} outputs", out.len());
+        let settled = &out[100..];
+        (settled.iter().map(|x| x * x).sum::<f32>() / settled.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_resampler_passes_audible_band_and_rejects_aliases() {
+        let mut resampler = Resampler::new();
+        let last = (0..1000).filter_map(|_| resampler.process(0.5, 0.25)).last().unwrap();
+        assert!((last - 0.5).abs() < 1e-4, "unity gain at DC: {}", last);
+
+        assert!((resampled_rms(1000.0) - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01);
+        // 30 kHz would fold down to 18 kHz with plain decimation
+        assert!(resampled_rms(30_000.0) < 0.01);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_video_recorder_writes_rgb_and_caps_length() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("video.rgb");
+        let mut recorder = VideoRecorder::create(&path).unwrap();
+        let frame = vec![0x00123456u32; 160 * 144];
+        assert!(recorder.push_frame(&frame).unwrap());
+        recorder.frames = VIDEO_MAX_FRAMES - 1;
+        assert!(recorder.push_frame(&frame).unwrap());
+        assert!(!recorder.push_frame(&frame).unwrap(), "cap reached");
+        assert!(recorder.finish().unwrap().contains("rgb24"));
+
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len(), 2 * 160 * 144 * 3);
+        assert_eq!(&data[0..3], &[0x12, 0x34, 0x56]);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_render_text_overlay() {
+        let width = 32;
+        let mut buffer = vec![0x00FFFFFF; width * 8];
+        render_text_overlay(&mut buffer, width, "AB", 0, 0, 0x00000000, 0x00000000);
+        let drawn = (0..8).any(|y| (0..16).any(|x| buffer[y * width + x] != 0x00FFFFFF));
+        assert!(drawn);
+        // Background is blended at 50%, pixels outside the text are untouched
+        assert_eq!(buffer[7 * width + 15] & 0xFF, 0x7F);
+        assert_eq!(buffer[16], 0x00FFFFFF);
+    }
+}
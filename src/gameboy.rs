@@ -1,74 +1,274 @@
 use std::collections::HashSet;
-use crate::cpu::CPU;
+#[cfg(feature = "native")]
+use std::fs;
+#[cfg(feature = "native")]
+use std::time::SystemTime;
+use std::time::{Duration, Instant};
+use crate::cpu::{CPU, HardwareModel};
+use crate::cpu::registers::FlagsRegister;
+use crate::breakpoint::ConditionalBreakpoint;
 use crate::cartridge::Cartridge;
+use crate::rewind::Rewind;
 use crate::savestate;
 
 pub const CYCLES_PER_FRAME: u32 = 70224;
 
+/// Suggested `run_until_pc` cap: about 2.4 s of emulated time (~142 frames).
+pub const RUN_UNTIL_PC_MAX_CYCLES: u64 = 10_000_000;
+
+/// Frames between watchdog PC samples.
+const WATCHDOG_CHECK_INTERVAL: u32 = 100;
+
+/// Detects hung execution: fires `on_hang` when the PC sampled every
+/// `WATCHDOG_CHECK_INTERVAL` frames has not moved for `timeout`.
+pub struct WatchdogTimer {
+    last_pc_change: Instant,
+    last_pc: u16,
+    timeout: Duration,
+    frames_since_check: u32,
+    on_hang: Box<dyn Fn(&GameBoy)>,
+}
+
+/// CPU registers and I/O values left behind by a boot ROM.
+pub struct PostBootState {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
+    /// (address, value) pairs written to I/O after the registers are set
+    pub io: Vec<(u16, u8)>,
+}
+
+// I/O state after the DMG/CGB boot ROM. Channel trigger registers are left
+// alone so the boot chime does not replay.
+const POST_BOOT_IO: [(u16, u8); 15] = [
+    (0xFF26, 0xF1), // NR52 first: APU must be on for the other sound writes
+    (0xFF05, 0x00), // TIMA
+    (0xFF06, 0x00), // TMA
+    (0xFF07, 0xF8), // TAC
+    (0xFF0F, 0xE1), // IF
+    (0xFF10, 0x80), // NR10
+    (0xFF11, 0xBF), // NR11
+    (0xFF12, 0xF3), // NR12
+    (0xFF24, 0x77), // NR50
+    (0xFF25, 0xF3), // NR51
+    (0xFF40, 0x91), // LCDC
+    (0xFF47, 0xFC), // BGP
+    (0xFF48, 0xFF), // OBP0
+    (0xFF49, 0xFF), // OBP1
+    (0xFFFF, 0x00), // IE
+];
+
+impl PostBootState {
+    pub fn dmg() -> Self {
+        PostBootState {
+            a: 0x01, f: 0xB0, b: 0x00, c: 0x13, d: 0x00, e: 0xD8, h: 0x01, l: 0x4D,
+            sp: 0xFFFE,
+            pc: 0x0100,
+            io: POST_BOOT_IO.to_vec(),
+        }
+    }
+
+    pub fn cgb() -> Self {
+        PostBootState {
+            a: 0x11, f: 0x80, b: 0x00, c: 0x00, d: 0xFF, e: 0x56, h: 0x00, l: 0x0D,
+            ..PostBootState::dmg()
+        }
+    }
+
+    /// CGB boot ROM handing over to a DMG-only cartridge.
+    pub fn cgb_in_dmg_mode() -> Self {
+        PostBootState {
+            a: 0x11, f: 0x80, b: 0x00, c: 0x00, d: 0x00, e: 0x08, h: 0x00, l: 0x7C,
+            ..PostBootState::dmg()
+        }
+    }
+
+    pub fn sgb() -> Self {
+        PostBootState {
+            a: 0x01, f: 0x00, b: 0x00, c: 0x14, d: 0x00, e: 0x00, h: 0xC0, l: 0x60,
+            ..PostBootState::dmg()
+        }
+    }
+
+    pub fn for_model(model: HardwareModel) -> Self {
+        match model {
+            HardwareModel::Dmg => PostBootState::dmg(),
+            HardwareModel::Cgb => PostBootState::cgb(),
+            HardwareModel::CgbInDmgMode => PostBootState::cgb_in_dmg_mode(),
+        }
+    }
+}
+
 pub struct GameBoy {
     pub cpu: CPU,
+    watchdog: Option<WatchdogTimer>,
+    /// When set, `run_frame` snapshots the state before each frame
+    rewind: Option<Rewind>,
 }
 
 impl GameBoy {
     pub fn new(cartridge: Cartridge) -> Self {
-        let cpu = CPU::new(cartridge);
-        GameBoy { cpu }
+        // No boot ROM support: start from the post-boot state of the model
+        // the cartridge header asks for
+        let model = HardwareModel::from_cgb_flag(cartridge.info().cgb_flag);
+        let mut gb = GameBoy { cpu: CPU::new_with_model(cartridge, model), watchdog: None, rewind: None };
+        gb.skip_bios(PostBootState::for_model(model));
+        gb
     }
 
-    pub fn run_frame(&mut self) {
-        let mut cycles_this_frame: u32 = 0;
-        while cycles_this_frame < CYCLES_PER_FRAME {
-            self.cpu.bus.cycles_ticked = 0;
-            let cycles = self.cpu.step();
-
-            // Tick timer for remaining cycles not already ticked during bus accesses
-            let remaining = cycles.saturating_sub(self.cpu.bus.cycles_ticked);
-            if remaining > 0 {
-                self.cpu.bus.timer.tick(remaining, &mut self.cpu.bus.apu);
-                if self.cpu.bus.timer.interrupt {
-                    self.cpu.bus.if_register |= 0x04;
-                    self.cpu.bus.timer.interrupt = false;
-                }
-            }
+    /// Build from an in-memory ROM image, with no file I/O (no battery
+    /// saves or save-state slots, since there is no ROM path).
+    pub fn from_rom_bytes(rom: &[u8]) -> Result<Self, String> {
+        Ok(GameBoy::new(Cartridge::from_bytes(rom)?))
+    }
 
-            // Tick PPU
-            let vram_copy = self.cpu.bus.vram;
-            let oam_copy = self.cpu.bus.oam;
-            self.cpu.bus.ppu.tick(cycles, &vram_copy, &oam_copy);
-            if self.cpu.bus.ppu.vblank_interrupt {
-                self.cpu.bus.if_register |= 0x01; // VBlank interrupt
-            }
-            if self.cpu.bus.ppu.stat_interrupt {
-                self.cpu.bus.if_register |= 0x02; // LCD STAT interrupt
+    /// Apply `state` as if a boot ROM had just handed over to the cartridge.
+    pub fn skip_bios(&mut self, state: PostBootState) {
+        let regs = &mut self.cpu.registers;
+        regs.a = state.a;
+        regs.f = FlagsRegister::from(state.f);
+        regs.b = state.b;
+        regs.c = state.c;
+        regs.d = state.d;
+        regs.e = state.e;
+        regs.h = state.h;
+        regs.l = state.l;
+        self.cpu.sp = state.sp;
+        self.cpu.pc = state.pc;
+        for (addr, value) in state.io {
+            self.cpu.bus.write_byte_no_tick(addr, value);
+        }
+    }
+
+    /// Swap in a new cartridge and return to the post-boot state.
+    /// The watchdog, audio sample rate, IR port setting and printer are kept;
+    /// rewind history from the old cartridge is discarded.
+    pub fn reset_with_cartridge(&mut self, cartridge: Cartridge) {
+        let sample_rate = self.cpu.bus.apu.sample_rate;
+        let ir_config = self.cpu.bus.ir_config;
+        let printer = self.cpu.bus.printer.take();
+        let model = HardwareModel::from_cgb_flag(cartridge.info().cgb_flag);
+        self.cpu = CPU::new_with_model(cartridge, model);
+        self.cpu.bus.apu.reset();
+        self.skip_bios(PostBootState::for_model(model));
+        self.cpu.bus.apu.set_sample_rate(sample_rate);
+        self.cpu.bus.ir_config = ir_config;
+        self.cpu.bus.printer = printer;
+        if let Some(rewind) = &mut self.rewind {
+            rewind.clear();
+        }
+    }
+
+    /// Replace ROM bytes in place (see `Cartridge::patch_rom`). Unlike cheats,
+    /// this changes the ROM data itself until `revert_rom_patch` is called.
+    pub fn inject_rom_patch(&mut self, addr: u16, bytes: &[u8]) -> Result<(), String> {
+        self.cpu.bus.cartridge.patch_rom(addr, bytes)
+    }
+
+    pub fn revert_rom_patch(&mut self, addr: u16, len: usize) {
+        self.cpu.bus.cartridge.revert_rom_patch(addr, len);
+    }
+
+    /// Install a watchdog that calls `on_hang` if the PC stays put for `timeout`.
+    pub fn set_watchdog(&mut self, timeout: Duration, on_hang: impl Fn(&GameBoy) + 'static) {
+        self.watchdog = Some(WatchdogTimer {
+            last_pc_change: Instant::now(),
+            last_pc: self.cpu.pc,
+            timeout,
+            frames_since_check: 0,
+            on_hang: Box::new(on_hang),
+        });
+    }
+
+    /// Keep up to `max_bytes` of per-frame snapshots for `rewind_frame`.
+    /// `None` turns rewind off and frees the history.
+    pub fn set_rewind(&mut self, max_bytes: Option<usize>) {
+        self.rewind = max_bytes.map(Rewind::new);
+    }
+
+    /// Restore the state from before the last frame. Returns false when
+    /// rewind is off or the history is empty.
+    pub fn rewind_frame(&mut self) -> bool {
+        let Some(snapshot) = self.rewind.as_mut().and_then(|r| r.pop()) else {
+            return false;
+        };
+        match savestate::load(self, &snapshot) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Rewind error: {}", e);
+                false
             }
+        }
+    }
 
-            // Joypad interrupt
-            if self.cpu.bus.joypad.interrupt {
-                self.cpu.bus.if_register |= 0x10; // Joypad interrupt
-                self.cpu.bus.joypad.interrupt = false;
+    fn check_watchdog(&mut self) {
+        let Some(mut wd) = self.watchdog.take() else { return };
+        wd.frames_since_check += 1;
+        if wd.frames_since_check >= WATCHDOG_CHECK_INTERVAL {
+            wd.frames_since_check = 0;
+            if self.cpu.pc != wd.last_pc {
+                wd.last_pc = self.cpu.pc;
+                wd.last_pc_change = Instant::now();
+            } else if wd.last_pc_change.elapsed() >= wd.timeout {
+                (wd.on_hang)(self);
+                // Re-arm so the callback fires once per timeout, not every check
+                wd.last_pc_change = Instant::now();
             }
+        }
+        self.watchdog = Some(wd);
+    }
 
-            cycles_this_frame += cycles as u32;
+    pub fn run_frame(&mut self) {
+        if self.rewind.is_some() {
+            let snapshot = savestate::save(self);
+            if let Some(rewind) = &mut self.rewind {
+                rewind.push(snapshot);
+            }
+        }
+        let mut cycles_this_frame: u32 = 0;
+        while cycles_this_frame < CYCLES_PER_FRAME {
+            let cycles = self.step();
+            cycles_this_frame += self.normal_speed_cycles(cycles);
         }
+        self.check_watchdog();
+    }
+
+    /// `cycles` CPU T-cycles in normal-speed (PPU) T-cycles: half as many in
+    /// CGB double speed.
+    fn normal_speed_cycles(&self, cycles: u8) -> u32 {
+        if self.cpu.bus.double_speed { cycles as u32 / 2 } else { cycles as u32 }
     }
 
     /// Execute a single CPU instruction + tick timer/PPU/joypad.
-    pub fn run_step(&mut self) -> u8 {
+    /// Returns the T-cycles the instruction took.
+    pub fn step(&mut self) -> u8 {
         self.cpu.bus.cycles_ticked = 0;
         let cycles = self.cpu.step();
 
         let remaining = cycles.saturating_sub(self.cpu.bus.cycles_ticked);
         if remaining > 0 {
             self.cpu.bus.timer.tick(remaining, &mut self.cpu.bus.apu);
+            self.cpu.bus.tick_oam_dma(remaining);
             if self.cpu.bus.timer.interrupt {
                 self.cpu.bus.if_register |= 0x04;
                 self.cpu.bus.timer.interrupt = false;
             }
         }
 
-        let vram_copy = self.cpu.bus.vram;
-        let oam_copy = self.cpu.bus.oam;
-        self.cpu.bus.ppu.tick(cycles, &vram_copy, &oam_copy);
+        // The PPU runs at normal speed whatever the CPU speed
+        let ppu_cycles = self.normal_speed_cycles(cycles) as u8;
+        let bus = &mut self.cpu.bus;
+        bus.ppu.tick(ppu_cycles, bus.vram.as_flattened(), &bus.oam);
+        if self.cpu.bus.ppu.hblank_started {
+            self.cpu.bus.tick_hdma();
+        }
         if self.cpu.bus.ppu.vblank_interrupt {
             self.cpu.bus.if_register |= 0x01;
         }
@@ -84,26 +284,81 @@ impl GameBoy {
         cycles
     }
 
-    /// Run a frame, checking PC against breakpoints after each step.
+    /// Run a frame, checking PC against breakpoints and evaluating each
+    /// conditional breakpoint after every step; a conditional breakpoint
+    /// only fires when its condition becomes true (see
+    /// `ConditionalBreakpoint::check`). Also stops after a step that touched
+    /// a watchpoint (see `MemoryBus::watchpoint_hit`).
     /// Returns true if a breakpoint was hit (frame not fully completed).
-    pub fn run_frame_with_breakpoints(&mut self, breakpoints: &HashSet<u16>) -> bool {
+    pub fn run_frame_with_breakpoints(
+        &mut self,
+        breakpoints: &HashSet<u16>,
+        conditions: &mut [ConditionalBreakpoint],
+    ) -> bool {
+        self.cpu.bus.watchpoint_hit = None;
         let mut cycles_this_frame: u32 = 0;
         while cycles_this_frame < CYCLES_PER_FRAME {
-            let cycles = self.run_step();
-            cycles_this_frame += cycles as u32;
+            let cycles = self.step();
+            cycles_this_frame += self.normal_speed_cycles(cycles);
 
-            if breakpoints.contains(&self.cpu.pc) {
+            // Check every condition so each one's last result stays current
+            let mut condition_hit = false;
+            for bp in conditions.iter_mut() {
+                condition_hit |= bp.check(&self.cpu);
+            }
+            if self.cpu.bus.watchpoint_hit.is_some()
+                || breakpoints.contains(&self.cpu.pc)
+                || condition_hit
+            {
                 return true;
             }
         }
         false
     }
 
+    /// Step until PC reaches `target` or `max_cycles` T-cycles have run
+    /// (see `RUN_UNTIL_PC_MAX_CYCLES`). Returns the T-cycles consumed.
+    pub fn run_until_pc(&mut self, target: u16, max_cycles: u64) -> u64 {
+        let mut cycles: u64 = 0;
+        while self.cpu.pc != target && cycles < max_cycles {
+            cycles += self.step() as u64;
+        }
+        cycles
+    }
+
+    /// Drain the bytes sent over the serial port since the last call.
+    pub fn take_serial_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.cpu.bus.serial_output)
+    }
+
     pub fn framebuffer(&self) -> &[u8; 160 * 144] {
         &self.cpu.bus.ppu.framebuffer
     }
 
+    /// The current frame as 0x00RRGGBB pixels. DMG shades are coloured with
+    /// `palette` (lightest first); in CGB mode the RGB555 output is used
+    /// instead and `palette` is ignored.
+    pub fn frame_rgb(&self, palette: &[u32; 4]) -> Vec<u32> {
+        let ppu = &self.cpu.bus.ppu;
+        if ppu.cgb_mode {
+            ppu.cgb_framebuffer.iter().map(|&c| crate::ppu::rgb555_to_rgb888(c)).collect()
+        } else {
+            self.framebuffer().iter().map(|&pixel| palette[(pixel & 0x03) as usize]).collect()
+        }
+    }
+
+    /// Encode `frame_rgb(palette)` as a 160x144 PNG.
+    pub fn capture_screen(&self, palette: &[u32; 4]) -> Vec<u8> {
+        let mut rgb = Vec::with_capacity(160 * 144 * 3);
+        for color in self.frame_rgb(palette) {
+            rgb.extend_from_slice(&[(color >> 16) as u8, (color >> 8) as u8, color as u8]);
+        }
+        crate::png::encode_rgb(160, 144, &rgb)
+    }
+
+    #[cfg(feature = "native")]
     pub fn save_state_to_slot(&self, slot: u8) -> Result<(), String> {
+        check_slot(slot)?;
         let rom_path = self.cpu.bus.cartridge.rom_path()
             .ok_or_else(|| "No ROM path available".to_string())?;
         let path = savestate::save_state_path(rom_path, slot);
@@ -112,7 +367,9 @@ impl GameBoy {
         Ok(())
     }
 
+    #[cfg(feature = "native")]
     pub fn load_state_from_slot(&mut self, slot: u8) -> Result<(), String> {
+        check_slot(slot)?;
         let rom_path = self.cpu.bus.cartridge.rom_path()
             .ok_or_else(|| "No ROM path available".to_string())?
             .to_string();
@@ -121,4 +378,316 @@ impl GameBoy {
         eprintln!("State loaded from {}", path.display());
         Ok(())
     }
+
+    /// Populated save-state slots with their modification times.
+    #[cfg(feature = "native")]
+    pub fn list_save_slots(&self) -> Vec<(u8, SystemTime)> {
+        let Some(rom_path) = self.cpu.bus.cartridge.rom_path() else {
+            return Vec::new();
+        };
+        (0..savestate::SAVE_SLOTS)
+            .filter_map(|slot| {
+                let path = savestate::save_state_path(rom_path, slot);
+                let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+                Some((slot, modified))
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "native")]
+    pub fn delete_save_slot(&self, slot: u8) -> Result<(), String> {
+        check_slot(slot)?;
+        let rom_path = self.cpu.bus.cartridge.rom_path()
+            .ok_or_else(|| "No ROM path available".to_string())?;
+        let path = savestate::save_state_path(rom_path, slot);
+        fs::remove_file(&path)
+            .map_err(|e| format!("Failed to delete save state {}: {}", path.display(), e))
+    }
+}
+
+#[cfg(feature = "native")]
+fn check_slot(slot: u8) -> Result<(), String> {
+    if slot >= savestate::SAVE_SLOTS {
+        return Err(format!("Invalid save slot {} (must be 0-{})", slot, savestate::SAVE_SLOTS - 1));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::breakpoint::Watchpoint;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_watchdog_fires_on_infinite_loop() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        // JP $C000 at $C000
+        gb.cpu.bus.wram[0][0..3].copy_from_slice(&[0xC3, 0x00, 0xC0]);
+        gb.cpu.pc = 0xC000;
+
+        let hung = Rc::new(Cell::new(false));
+        let hung_clone = hung.clone();
+        gb.set_watchdog(Duration::from_millis(10), move |_| hung_clone.set(true));
+
+        // Run for at least 100 ms, and long enough to reach a watchdog check
+        let start = Instant::now();
+        let mut frames = 0;
+        while start.elapsed() < Duration::from_millis(100) || frames < WATCHDOG_CHECK_INTERVAL {
+            gb.run_frame();
+            frames += 1;
+        }
+
+        assert_eq!(gb.cpu.pc, 0xC000);
+        assert!(hung.get(), "on_hang should fire when PC never changes");
+    }
+
+    #[test]
+    fn test_skip_bios_post_boot_states() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        assert_eq!(gb.cpu.registers.a, 0x01);
+        assert_eq!(gb.cpu.registers.get_af(), 0x01B0);
+        assert_eq!(gb.cpu.bus.ppu.lcdc, 0x91);
+        assert!(gb.cpu.bus.apu.power);
+
+        gb.skip_bios(PostBootState::cgb());
+        assert_eq!(gb.cpu.registers.a, 0x11);
+        assert_eq!(gb.cpu.registers.get_de(), 0xFF56);
+
+        gb.skip_bios(PostBootState::sgb());
+        assert_eq!(gb.cpu.registers.get_hl(), 0xC060);
+        assert_eq!(gb.cpu.pc, 0x0100);
+    }
+
+    #[test]
+    fn test_new_selects_model_from_header() {
+        let mut rom = vec![0u8; 0x8000];
+        let gb = GameBoy::new(Cartridge::from_bytes(&rom).unwrap());
+        assert_eq!(gb.cpu.bus.model, HardwareModel::Dmg);
+        assert_eq!(gb.cpu.registers.a, 0x01);
+        assert!(!gb.cpu.bus.ppu.cgb_mode);
+
+        for flag in [0x80, 0xC0] {
+            rom[0x0143] = flag;
+            let gb = GameBoy::new(Cartridge::from_bytes(&rom).unwrap());
+            assert_eq!(gb.cpu.bus.model, HardwareModel::Cgb);
+            assert_eq!(gb.cpu.registers.a, 0x11);
+            assert_eq!(gb.cpu.registers.b, 0x00);
+            assert!(gb.cpu.bus.ppu.cgb_mode);
+        }
+
+        let state = PostBootState::for_model(HardwareModel::CgbInDmgMode);
+        assert_eq!((state.a, state.l), (0x11, 0x7C));
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_save_slots_list_and_delete() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let rom_path = dir.join("slots.gb");
+        fs::write(&rom_path, vec![0u8; 0x8000]).unwrap();
+        let gb = GameBoy::new(Cartridge::from_file(&rom_path).unwrap());
+
+        assert!(gb.list_save_slots().is_empty());
+        gb.save_state_to_slot(3).unwrap();
+        gb.save_state_to_slot(7).unwrap();
+        let slots: Vec<u8> = gb.list_save_slots().iter().map(|&(s, _)| s).collect();
+        assert_eq!(slots, vec![3, 7]);
+
+        gb.delete_save_slot(3).unwrap();
+        assert_eq!(gb.list_save_slots().len(), 1);
+        assert!(gb.delete_save_slot(3).is_err());
+        assert!(gb.save_state_to_slot(10).is_err());
+    }
+
+    #[test]
+    fn test_rewind_frame_restores_previous_frame() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        assert!(!gb.rewind_frame(), "rewind is off by default");
+
+        gb.set_rewind(Some(1 << 20));
+        gb.cpu.bus.hram[0] = 1;
+        gb.run_frame();
+        gb.cpu.bus.hram[0] = 2;
+        gb.run_frame();
+
+        assert!(gb.rewind_frame());
+        assert_eq!(gb.cpu.bus.hram[0], 2);
+        assert!(gb.rewind_frame());
+        assert_eq!(gb.cpu.bus.hram[0], 1);
+        assert!(!gb.rewind_frame());
+    }
+
+    #[test]
+    fn test_capture_screen_applies_palette() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        gb.cpu.bus.ppu.framebuffer[0] = 3;
+        let png = gb.capture_screen(&[0xFFFFFF, 0xAAAAAA, 0x555555, 0x123456]);
+        let (width, height, rgb) = crate::png::decode_rgb(&png);
+        assert_eq!((width, height), (160, 144));
+        // Pixel 0 in palette colour 3, pixel 1 in colour 0
+        assert_eq!(&rgb[0..6], &[0x12, 0x34, 0x56, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_capture_screen_uses_cgb_colors() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        gb.cpu.bus.ppu.cgb_mode = true;
+        gb.cpu.bus.ppu.framebuffer[0] = 3;
+        gb.cpu.bus.ppu.cgb_framebuffer[0] = 0x7C1F; // red + blue
+        let png = gb.capture_screen(&[0xFFFFFF, 0xAAAAAA, 0x555555, 0x123456]);
+        let (_, _, rgb) = crate::png::decode_rgb(&png);
+        assert_eq!(&rgb[0..6], &[0xFF, 0x00, 0xFF, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_frame_rgb_renders_cgb_palette_color() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        let palette = [0xFFFFFF, 0xAAAAAA, 0x555555, 0x000000];
+        assert_eq!(gb.frame_rgb(&palette)[0], 0xFFFFFF);
+
+        // BG palette 0 color 0 = pure green, rendered through the PPU
+        gb.cpu.bus.ppu.cgb_mode = true;
+        gb.cpu.bus.ppu.bg_palette_ram[0..2].copy_from_slice(&0x03E0u16.to_le_bytes());
+        gb.cpu.bus.ppu.lcdc = 0x91;
+        gb.run_frame();
+        let frame = gb.frame_rgb(&palette);
+        assert_eq!(frame.len(), 160 * 144);
+        assert_eq!(frame[0], 0x00FF00);
+    }
+
+    #[test]
+    fn test_from_rom_bytes() {
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x0143] = 0x80;
+        let gb = GameBoy::from_rom_bytes(&rom).unwrap();
+        assert_eq!(gb.cpu.bus.model, HardwareModel::Cgb);
+        assert!(gb.cpu.bus.cartridge.rom_path().is_none());
+        assert!(GameBoy::from_rom_bytes(&rom[..0x100]).is_err());
+    }
+
+    /// CPU T-cycles from one LY change to the next, running NOPs.
+    fn cpu_cycles_per_line(gb: &mut GameBoy) -> u32 {
+        let wait_for_next_line = |gb: &mut GameBoy| {
+            let ly = gb.cpu.bus.ppu.ly;
+            let mut cycles = 0;
+            while gb.cpu.bus.ppu.ly == ly {
+                gb.cpu.pc = 0xC000;
+                cycles += gb.step() as u32;
+            }
+            cycles
+        };
+        wait_for_next_line(gb);
+        wait_for_next_line(gb)
+    }
+
+    #[test]
+    fn test_double_speed_runs_cpu_twice_per_ppu_cycle() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        gb.cpu.bus.wram[0][0] = 0x00; // NOP
+        assert_eq!(cpu_cycles_per_line(&mut gb), 456);
+
+        gb.cpu.bus.double_speed = true;
+        gb.cpu.bus.timer.double_speed = true;
+        assert_eq!(cpu_cycles_per_line(&mut gb), 912);
+    }
+
+    #[test]
+    fn test_step_nop_takes_four_cycles() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        gb.cpu.bus.wram[0][0] = 0x00; // NOP
+        gb.cpu.pc = 0xC000;
+        assert_eq!(gb.step(), 4);
+        assert_eq!(gb.cpu.pc, 0xC001);
+    }
+
+    #[test]
+    fn test_run_until_pc() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        // NOP; NOP; JP $C000 at $C000
+        gb.cpu.bus.wram[0][0..5].copy_from_slice(&[0x00, 0x00, 0xC3, 0x00, 0xC0]);
+        gb.cpu.pc = 0xC000;
+        assert_eq!(gb.run_until_pc(0xC002, RUN_UNTIL_PC_MAX_CYCLES), 8);
+        assert_eq!(gb.cpu.pc, 0xC002);
+        assert_eq!(gb.run_until_pc(0xC002, RUN_UNTIL_PC_MAX_CYCLES), 0, "already at target");
+
+        // Never reached: gives up at the cycle cap
+        let cycles = gb.run_until_pc(0x1234, RUN_UNTIL_PC_MAX_CYCLES);
+        assert!(cycles >= RUN_UNTIL_PC_MAX_CYCLES);
+        assert!(cycles < RUN_UNTIL_PC_MAX_CYCLES + 24);
+        let cycles = gb.run_until_pc(0x1234, 1000);
+        assert!((1000..1024).contains(&cycles));
+    }
+
+    #[test]
+    fn test_conditional_breakpoint_stops_frame() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        // INC A; JP $C000 at $C000
+        gb.cpu.bus.wram[0][0..4].copy_from_slice(&[0x3C, 0xC3, 0x00, 0xC0]);
+        gb.cpu.pc = 0xC000;
+        gb.cpu.registers.a = 0;
+
+        let mut conditions = [ConditionalBreakpoint::parse("A==05").unwrap()];
+        assert!(gb.run_frame_with_breakpoints(&HashSet::new(), &mut conditions));
+        assert_eq!(gb.cpu.registers.a, 5);
+        assert_eq!(gb.cpu.pc, 0xC001);
+    }
+
+    #[test]
+    fn test_conditional_breakpoint_fires_on_edge() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        // JP $C000 at $C000: PC never changes
+        gb.cpu.bus.wram[0][0..3].copy_from_slice(&[0xC3, 0x00, 0xC0]);
+        gb.cpu.pc = 0xC000;
+        gb.cpu.registers.b = 0x42;
+
+        let mut conditions = [ConditionalBreakpoint::parse("B==42").unwrap()];
+        assert!(gb.run_frame_with_breakpoints(&HashSet::new(), &mut conditions));
+        // Still true after resuming: the frame runs to completion
+        assert!(!gb.run_frame_with_breakpoints(&HashSet::new(), &mut conditions));
+
+        gb.cpu.registers.b = 0;
+        assert!(!gb.run_frame_with_breakpoints(&HashSet::new(), &mut conditions));
+        gb.cpu.registers.b = 0x42;
+        assert!(gb.run_frame_with_breakpoints(&HashSet::new(), &mut conditions));
+    }
+
+    #[test]
+    fn test_watchpoint_stops_frame() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        // LD ($C100),A; JP $C000 at $C000
+        gb.cpu.bus.wram[0][0..6].copy_from_slice(&[0xEA, 0x00, 0xC1, 0xC3, 0x00, 0xC0]);
+        gb.cpu.pc = 0xC000;
+        gb.cpu.bus.watchpoints.push(Watchpoint::parse("W C100").unwrap());
+
+        assert!(gb.run_frame_with_breakpoints(&HashSet::new(), &mut []));
+        assert_eq!(gb.cpu.pc, 0xC003);
+        assert_eq!(gb.cpu.bus.watchpoint_hit.map(|wp| wp.start), Some(0xC100));
+    }
+
+    #[test]
+    fn test_rom_patch_nops_out_call() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        gb.inject_rom_patch(0x0150, &[0xCD, 0x00, 0x02]).unwrap(); // CALL $0200
+        gb.run_until_pc(0x0150, RUN_UNTIL_PC_MAX_CYCLES);
+        gb.step();
+        assert_eq!(gb.cpu.pc, 0x0200);
+        assert_eq!(gb.cpu.sp, 0xFFFC);
+
+        let mut gb = GameBoy::new(Cartridge::default());
+        gb.inject_rom_patch(0x0150, &[0xCD, 0x00, 0x02]).unwrap();
+        gb.inject_rom_patch(0x0150, &[0x00, 0x00, 0x00]).unwrap();
+        gb.run_until_pc(0x0150, RUN_UNTIL_PC_MAX_CYCLES);
+        for _ in 0..3 {
+            gb.step();
+        }
+        assert_eq!(gb.cpu.pc, 0x0153);
+        assert_eq!(gb.cpu.sp, 0xFFFE, "CALL should not have been taken");
+
+        gb.revert_rom_patch(0x0150, 3);
+        assert_eq!(gb.cpu.bus.read_byte_no_tick(0x0150), 0x00);
+        assert!(gb.inject_rom_patch(0x7FFF, &[0, 0]).is_err());
+    }
 }
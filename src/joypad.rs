@@ -3,6 +3,8 @@ pub struct Joypad {
     pub buttons: u8,   // Start, Select, B, A (active low: 0 = pressed)
     pub dpad: u8,      // Down, Up, Left, Right (active low: 0 = pressed)
     pub interrupt: bool,
+    /// P1 input lines (bits 0-3) as last seen; a 1 -> 0 edge requests the interrupt
+    prev_p1: u8,
 }
 
 impl Joypad {
@@ -19,6 +21,15 @@ impl Joypad {
 
     pub fn write(&mut self, byte: u8) {
         self.select = byte & 0x30;
+        self.update_interrupt();
+    }
+
+    fn update_interrupt(&mut self) {
+        let p1 = self.read() & 0x0F;
+        if self.prev_p1 & !p1 != 0 {
+            self.interrupt = true;
+        }
+        self.prev_p1 = p1;
     }
 
     pub fn key_down(&mut self, key: JoypadKey) {
@@ -32,7 +43,7 @@ impl Joypad {
             JoypadKey::Select => self.buttons &= !0x04,
             JoypadKey::Start  => self.buttons &= !0x08,
         }
-        self.interrupt = true;
+        self.update_interrupt();
     }
 
     pub fn key_up(&mut self, key: JoypadKey) {
@@ -46,10 +57,11 @@ impl Joypad {
             JoypadKey::Select => self.buttons |= 0x04,
             JoypadKey::Start  => self.buttons |= 0x08,
         }
+        self.update_interrupt();
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum JoypadKey {
     Right, Left, Up, Down,
     A, B, Select, Start,
@@ -70,6 +82,7 @@ impl Joypad {
         self.buttons = read_u8(data, cursor);
         self.dpad = read_u8(data, cursor);
         self.interrupt = read_bool(data, cursor);
+        self.prev_p1 = self.read() & 0x0F;
     }
 }
 
@@ -80,6 +93,34 @@ impl Default for Joypad {
             buttons: 0x0F,
             dpad: 0x0F,
             interrupt: false,
+            prev_p1: 0x0F,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interrupt_only_on_press_edge_of_selected_line() {
+        let mut joypad = Joypad::default();
+        joypad.write(0x20); // select d-pad
+
+        joypad.key_down(JoypadKey::A);
+        assert!(!joypad.interrupt, "buttons are not selected");
+
+        joypad.key_down(JoypadKey::Up);
+        assert!(joypad.interrupt);
+        joypad.interrupt = false;
+
+        // Held key polled again every frame: no new edge
+        joypad.key_down(JoypadKey::Up);
+        joypad.key_up(JoypadKey::Up);
+        assert!(!joypad.interrupt);
+
+        // Selecting the buttons row exposes the held A as a falling edge
+        joypad.write(0x10);
+        assert!(joypad.interrupt);
+    }
+}
@@ -0,0 +1,96 @@
+//! ROM picker shown when the emulator is started without a ROM argument.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+use crate::cartridge::Cartridge;
+use crate::debug::font::{self, CHAR_WIDTH};
+use crate::debug::{BG_COLOR, HEADER_COLOR, HIGHLIGHT_COLOR, TEXT_COLOR};
+
+const WIN_W: usize = 320;
+const WIN_H: usize = 288;
+const TOP: usize = 28;
+const ROW_H: usize = 20;
+const FILE_COLOR: u32 = 0x00808090;
+
+/// One line of the list: header title plus the file name below it.
+struct Entry {
+    path: PathBuf,
+    title: String,
+    file_name: String,
+}
+
+impl Entry {
+    fn new(path: &Path) -> Self {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let title = match Cartridge::read_title(path) {
+            Some(title) if !title.trim().is_empty() => title,
+            Some(_) => "(untitled)".to_string(),
+            None => "(missing)".to_string(),
+        };
+        Entry { path: path.to_path_buf(), title, file_name }
+    }
+}
+
+/// Cut `text` to `max` characters, marking the cut with "..".
+fn fit(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max.saturating_sub(2)).collect();
+    format!("{}..", kept)
+}
+
+/// Open a window listing `recent` and block until the user picks one
+/// (Enter) or closes it (Esc). Returns the chosen path.
+pub fn pick_rom(recent: &[PathBuf]) -> Option<PathBuf> {
+    let entries: Vec<Entry> = recent.iter().map(|p| Entry::new(p)).collect();
+    if entries.is_empty() {
+        return None;
+    }
+    let mut window = Window::new("Game Boy - Recent ROMs", WIN_W, WIN_H, WindowOptions::default())
+        .map_err(|e| eprintln!("Failed to create launcher window: {}", e))
+        .ok()?;
+    let mut buf = vec![BG_COLOR; WIN_W * WIN_H];
+    let mut selected = 0usize;
+    let max_chars = (WIN_W - 24) / CHAR_WIDTH;
+
+    while window.is_open() {
+        for key in window.get_keys_pressed(KeyRepeat::Yes) {
+            match key {
+                Key::Up => selected = selected.saturating_sub(1),
+                Key::Down => selected = (selected + 1).min(entries.len() - 1),
+                Key::Enter => return Some(entries[selected].path.clone()),
+                Key::Escape => return None,
+                _ => {}
+            }
+        }
+
+        buf.fill(BG_COLOR);
+        font::draw_string(&mut buf, WIN_W, 8, 8, "RECENT ROMS", HEADER_COLOR);
+        for (i, entry) in entries.iter().enumerate() {
+            let y = TOP + i * ROW_H;
+            let color = if i == selected { HIGHLIGHT_COLOR } else { TEXT_COLOR };
+            if i == selected {
+                font::draw_char(&mut buf, WIN_W, 8, y, b'>', HIGHLIGHT_COLOR);
+            }
+            font::draw_string(&mut buf, WIN_W, 24, y, &fit(&entry.title, max_chars), color);
+            font::draw_string(&mut buf, WIN_W, 24, y + 9, &fit(&entry.file_name, max_chars), FILE_COLOR);
+        }
+        font::draw_string(&mut buf, WIN_W, 8, WIN_H - 12, "Up/Down select, Enter open, Esc quit", TEXT_COLOR);
+        window.update_with_buffer(&buf, WIN_W, WIN_H).ok();
+        std::thread::sleep(Duration::from_millis(16));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_truncates_long_names() {
+        assert_eq!(fit("TETRIS", 10), "TETRIS");
+        assert_eq!(fit("a_very_long_file_name.gb", 10), "a_very_l..");
+    }
+}
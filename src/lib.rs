@@ -0,0 +1,37 @@
+//! Game Boy emulator core. The `gb_emulator` binary in `main.rs` is a
+//! minifb/cpal front-end over this library.
+//!
+//! The front-end pieces (windows, audio output, file writers) are behind the
+//! default `native` feature. Browser builds use
+//! `--no-default-features --features wasm`.
+
+pub mod cpu;
+pub mod cartridge;
+pub mod timer;
+pub mod ppu;
+pub mod joypad;
+pub mod gameboy;
+pub mod savestate;
+pub mod apu;
+pub mod filters;
+pub mod config;
+#[cfg(feature = "native")]
+pub mod debug;
+pub mod benchmark;
+pub mod memdump;
+#[cfg(feature = "native")]
+pub mod audio;
+pub mod rewind;
+pub mod wav;
+pub mod png;
+pub mod breakpoint;
+pub mod printer;
+#[cfg(feature = "native")]
+pub mod launcher;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use cartridge::Cartridge;
+pub use config::Config;
+pub use gameboy::GameBoy;
+pub use joypad::JoypadKey;
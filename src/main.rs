@@ -1,22 +1,13 @@
-mod cpu;
-mod cartridge;
-mod timer;
-mod ppu;
-mod joypad;
-mod gameboy;
-mod savestate;
-mod apu;
-mod filters;
-mod config;
-mod debug;
-
-use cartridge::Cartridge;
-use gameboy::GameBoy;
-use joypad::JoypadKey;
+use gb_emulator::{benchmark, cartridge, config, debug, filters, launcher, memdump, printer, savestate, wav};
+use gb_emulator::audio::{AudioState, LatencyTracker};
+use gb_emulator::{Cartridge, GameBoy, JoypadKey};
+use gb_emulator::cpu::memory::IrConfig;
 
 use minifb::{Key, Window, WindowOptions, Scale};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 
 #[derive(PartialEq, Clone, Copy)]
@@ -26,7 +17,8 @@ enum SpeedMode {
     Paused,
 }
 
-use filters::PALETTES;
+/// How long a save/load/slot message stays in the title bar.
+const SLOT_STATUS_DURATION: Duration = Duration::from_secs(2);
 
 const SCALE_STEPS: [(Scale, &str); 3] = [
     (Scale::X1, "2x"),
@@ -34,11 +26,37 @@ const SCALE_STEPS: [(Scale, &str); 3] = [
     (Scale::X4, "8x"),
 ];
 
-fn create_window(scale: Scale) -> Window {
+fn scale_factor(scale: Scale) -> usize {
+    match scale {
+        Scale::X2 => 2,
+        Scale::X4 => 4,
+        Scale::X8 => 8,
+        _ => 1,
+    }
+}
+
+/// Pixel size the frame is presented at, plus the minifb scale on top of
+/// it. Bilinear and fixed-size windows are scaled in software, so minifb
+/// shows them 1:1.
+fn window_layout(scale: Scale, display: &config::Display) -> (usize, usize, Scale) {
+    if let Some(fixed) = &display.fixed_window {
+        return (fixed.width, fixed.height, Scale::X1);
+    }
+    match display.scale_filter {
+        config::ScaleFilter::Nearest => (320, 288, scale),
+        config::ScaleFilter::Bilinear => {
+            let factor = scale_factor(scale);
+            (320 * factor, 288 * factor, Scale::X1)
+        }
+    }
+}
+
+fn create_window(scale: Scale, display: &config::Display) -> Window {
+    let (width, height, scale) = window_layout(scale, display);
     Window::new(
         "GB Emulator",
-        320,
-        288,
+        width,
+        height,
         WindowOptions {
             scale,
             ..WindowOptions::default()
@@ -49,28 +67,95 @@ fn create_window(scale: Scale) -> Window {
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     let headless = args.iter().any(|a| a == "--headless");
-    let rom_args: Vec<&String> = args.iter().skip(1).filter(|a| *a != "--headless").collect();
+    let debug_mbc = args.iter().any(|a| a == "--debug-mbc");
+    let info_only = args.iter().any(|a| a == "--info");
+    let dump_memory = args.iter().any(|a| a == "--dump-memory-each-frame");
+    let ir_loopback = args.iter().any(|a| a == "--ir-loopback");
+    let printer = args.iter().any(|a| a == "--printer");
+    let strict_header = args.iter().any(|a| a == "--strict-header");
+    let serial_stdout = args.iter().any(|a| a == "--serial-stdout");
+    let benchmark_frames = flag_value(&args, "--benchmark").map(|v| {
+        v.parse::<u32>().unwrap_or_else(|_| {
+            eprintln!("Invalid --benchmark frame count: {}", v);
+            std::process::exit(1);
+        })
+    });
+    let min_fps = flag_value(&args, "--min-fps").map(|v| {
+        v.parse::<f64>().unwrap_or_else(|_| {
+            eprintln!("Invalid --min-fps value: {}", v);
+            std::process::exit(1);
+        })
+    });
+    let max_frames = flag_value(&args, "--max-frames").map_or(DEFAULT_MAX_FRAMES, |v| {
+        v.parse::<u32>().unwrap_or_else(|_| {
+            eprintln!("Invalid --max-frames count: {}", v);
+            std::process::exit(1);
+        })
+    });
+    let record_audio = flag_value(&args, "--record-audio").map(std::path::PathBuf::from);
+    let screenshot = flag_value(&args, "--screenshot").map(std::path::PathBuf::from);
+    let rom_args = positional_args(&args);
 
-    if rom_args.is_empty() {
-        eprintln!("Usage: {} [--headless] <rom.gb>", args[0]);
-        std::process::exit(1);
-    }
+    // With no ROM argument, the windowed front end offers the recent ROMs list
+    let windowed = !headless && !info_only && benchmark_frames.is_none();
+    let rom_path = match rom_args.first() {
+        Some(arg) => PathBuf::from(arg),
+        None => match windowed.then(|| launcher::pick_rom(&config::RecentRoms::load().paths)).flatten() {
+            Some(path) => path,
+            None => {
+                eprintln!("Usage: {} [--headless] [--info] [--debug-mbc] [--benchmark N [--min-fps N]] [--max-frames N] [--dump-memory-each-frame] [--ir-loopback] [--printer] [--strict-header] [--serial-stdout] [--record-audio FILE.wav] [--screenshot FILE.png] <rom.gb>", args[0]);
+                std::process::exit(1);
+            }
+        },
+    };
 
-    let cartridge = Cartridge::from_file(rom_args[0]).unwrap_or_else(|e| {
+    let mut cartridge = Cartridge::from_file_checked(&rom_path, strict_header).unwrap_or_else(|e| {
         eprintln!("Error loading ROM: {}", e);
         std::process::exit(1);
     });
+    cartridge.debug_mbc = debug_mbc;
+
+    if info_only {
+        print_cartridge_info(&cartridge.info(), &cartridge.flags);
+        return;
+    }
 
     println!("Title: {}", cartridge.title);
     println!("Type: 0x{:02X}", cartridge.cartridge_type);
 
     let mut gb = GameBoy::new(cartridge);
+    if ir_loopback {
+        gb.cpu.bus.ir_config = IrConfig::Loopback;
+    }
+    if printer {
+        gb.cpu.bus.printer = Some(printer::Printer::new());
+    }
+
+    if let Some(frames) = benchmark_frames {
+        let fps = run_benchmark(&mut gb, frames);
+        if let Some(min) = min_fps.filter(|&min| fps < min) {
+            eprintln!("Benchmark below {:.0} FPS", min);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     if headless {
-        run_headless(&mut gb);
+        run_headless(&mut gb, max_frames, dump_memory, record_audio.as_deref());
+        if let Some(path) = &screenshot {
+            save_screenshot(&gb, path, &filters::PALETTES[0].1);
+        }
     } else {
+        let mut recent = config::RecentRoms::load();
+        recent.add(std::fs::canonicalize(&rom_path).unwrap_or(rom_path));
+        if let Err(e) = recent.save() {
+            eprintln!("{}", e);
+        }
         let config = config::Config::load();
-        run_windowed(&mut gb, &config);
+        if config.debug_mbc {
+            gb.cpu.bus.cartridge.debug_mbc = true;
+        }
+        run_windowed(&mut gb, &config, record_audio.as_deref(), serial_stdout);
     }
 
     if let Err(e) = gb.cpu.bus.cartridge.save() {
@@ -78,17 +163,118 @@ fn main() {
     }
 }
 
-fn run_headless(gb: &mut GameBoy) {
-    // No audio output in headless mode
+// Flags that consume the following argument as their value
+const VALUE_FLAGS: [&str; 5] = ["--benchmark", "--min-fps", "--max-frames", "--record-audio", "--screenshot"];
+
+// Sample rate of headless --record-audio output
+const RECORD_SAMPLE_RATE: u32 = 44100;
+
+// ~60 seconds of emulated time
+const DEFAULT_MAX_FRAMES: u32 = 3600;
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|s| s.as_str())
+}
+
+fn positional_args(args: &[String]) -> Vec<&String> {
+    let mut result = Vec::new();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            iter.next();
+        } else if !arg.starts_with("--") {
+            result.push(arg);
+        }
+    }
+    result
+}
+
+fn print_cartridge_info(info: &cartridge::CartridgeInfo, flags: &cartridge::CartridgeFlags) {
+    println!("Title:        {}", info.title);
+    println!("Manufacturer: {}", info.manufacturer_code);
+    println!("CGB flag:     0x{:02X}", info.cgb_flag);
+    println!("SGB flag:     0x{:02X}", info.sgb_flag);
+    println!("Type:         {} (0x{:02X})", info.cartridge_type_name, info.cartridge_type);
+    println!("ROM size:     {} KiB", info.rom_size / 1024);
+    println!("RAM size:     {} KiB", info.ram_size / 1024);
+    println!("Destination:  {}", if info.destination_code == 0 { "Japan" } else { "Overseas" });
+    println!("Version:      {}", info.version);
+    println!("Header sum:   {}", if info.header_checksum_valid { "OK" } else { "BAD" });
+    println!("Global sum:   0x{:04X} ({})", info.global_checksum, if flags.has_valid_global_checksum { "OK" } else { "BAD" });
+    println!("Logo:         {}", if flags.has_valid_logo { "OK" } else { "BAD" });
+}
+
+const HISTOGRAM_BUCKETS: usize = 10;
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Run `frames` frames flat out and print timing statistics. Returns the average FPS.
+fn run_benchmark(gb: &mut GameBoy, frames: u32) -> f64 {
     gb.cpu.bus.apu.set_sample_rate(0);
 
-    // Run for up to ~60 seconds of emulated time (~3600 frames)
+    let mut bench = benchmark::Benchmark::new();
+    let wall_start = Instant::now();
+    for _ in 0..frames {
+        let start = Instant::now();
+        gb.run_frame();
+        bench.add(start.elapsed().as_nanos() as u64);
+        gb.cpu.bus.apu.sample_buffer.clear();
+    }
+    let wall = wall_start.elapsed().as_secs_f64();
+    let fps = if wall > 0.0 { frames as f64 / wall } else { 0.0 };
+
+    let report = bench.report();
+    println!("Frames:  {}", report.frames);
+    println!("Wall:    {:.3} s", wall);
+    println!("FPS:     {:.1}", fps);
+    println!("Mean:    {} us", report.mean / 1000);
+    println!("Min:     {} us", report.min / 1000);
+    println!("Max:     {} us", report.max / 1000);
+    println!("p50:     {} us", report.p50 / 1000);
+    println!("p95:     {} us", report.p95 / 1000);
+    println!("p99:     {} us", report.p99 / 1000);
+    println!("Total:   {:.3} s", report.total as f64 / 1e9);
+
+    let histogram = bench.histogram(HISTOGRAM_BUCKETS);
+    let tallest = histogram.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    for bucket in &histogram {
+        let bar = "#".repeat(bucket.count * HISTOGRAM_WIDTH / tallest);
+        println!("{:>7}-{:<7} us {:>6} {}", bucket.start / 1000, bucket.end / 1000, bucket.count, bar);
+    }
+    fps
+}
+
+/// Run without a window for at most `max_frames` frames. Returns the number of frames run.
+fn run_headless(gb: &mut GameBoy, max_frames: u32, mut dump_memory: bool, record_audio: Option<&Path>) -> u32 {
+    // No audio output in headless mode unless it is being recorded
+    let mut recording = Vec::new();
+    gb.cpu.bus.apu.set_sample_rate(if record_audio.is_some() { RECORD_SAMPLE_RATE } else { 0 });
+
+    let mut dumper = memdump::MemoryDumper::new(&gb.cpu.bus);
+    let stdout = std::io::stdout();
+
+    // Run for up to `max_frames` frames
     // Stop early if Blargg memory-mapped result is available
-    for _ in 0..3600 {
+    let mut frame_count = 0;
+    while frame_count < max_frames {
         gb.run_frame();
+        frame_count += 1;
+        save_printed_sheets(gb, frame_count as u64);
         // Clear sample buffer periodically (no audio output)
+        if record_audio.is_some() {
+            recording.append(&mut gb.cpu.bus.apu.sample_buffer);
+        }
         gb.cpu.bus.apu.sample_buffer.clear();
 
+        if dump_memory {
+            if let Err(e) = dumper.write_changes(&gb.cpu.bus, &mut stdout.lock()) {
+                eprintln!("Memory dump error: {}", e);
+                dump_memory = false;
+            }
+        }
+
         // Check for Blargg memory-mapped result signature at $A001-$A003
         let sig = [
             gb.cpu.bus.cartridge.read_byte(0xA001),
@@ -113,6 +299,13 @@ fn run_headless(gb: &mut GameBoy) {
         }
     }
 
+    // Serial output (Blargg ROMs print their results here too)
+    let serial = gb.take_serial_output();
+    if !serial.is_empty() {
+        eprintln!("Serial output:");
+        eprintln!("{}", String::from_utf8_lossy(&serial).trim_end());
+    }
+
     // Dump VRAM tile map as ASCII (for screen-only test ROMs like halt_bug)
     // Blargg uses tiles where tile index maps to ASCII code
     let tilemap_base = 0x1800usize; // $9800 in VRAM
@@ -120,7 +313,7 @@ fn run_headless(gb: &mut GameBoy) {
     for row in 0..18 {
         let mut line = String::new();
         for col in 0..20 {
-            let tile = gb.cpu.bus.vram[tilemap_base + row * 32 + col];
+            let tile = gb.cpu.bus.vram[0][tilemap_base + row * 32 + col];
             if tile >= 0x20 && tile < 0x7F {
                 line.push(tile as char);
                 has_text = true;
@@ -136,27 +329,87 @@ fn run_headless(gb: &mut GameBoy) {
     }
 
     eprintln!();
+
+    if let Some(path) = record_audio {
+        save_recording(path, &recording, RECORD_SAMPLE_RATE);
+    }
+    frame_count
+}
+
+/// ROM title reduced to characters that are safe in a file name.
+fn screenshot_stem(title: &str) -> String {
+    let stem: String = title.trim().chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if stem.is_empty() { "screenshot".to_string() } else { stem }
+}
+
+fn save_screenshot(gb: &GameBoy, path: &Path, palette: &[u32; 4]) {
+    match std::fs::write(path, gb.capture_screen(palette)) {
+        Ok(()) => eprintln!("Screenshot saved to {}", path.display()),
+        Err(e) => eprintln!("Failed to write {}: {}", path.display(), e),
+    }
+}
+
+/// Write sheets from the Game Boy Printer (--printer) as
+/// `<title>_print_<frame>.png`.
+fn save_printed_sheets(gb: &mut GameBoy, frame: u64) {
+    let Some(printer) = &mut gb.cpu.bus.printer else { return };
+    let stem = screenshot_stem(&gb.cpu.bus.cartridge.title);
+    for (i, sheet) in printer.take_printed().iter().enumerate() {
+        let name = if i == 0 {
+            format!("{}_print_{}.png", stem, frame)
+        } else {
+            format!("{}_print_{}_{}.png", stem, frame, i)
+        };
+        match std::fs::write(&name, sheet.to_png()) {
+            Ok(()) => eprintln!("Printed {}x{} sheet to {}", sheet.width, sheet.height, name),
+            Err(e) => eprintln!("Failed to write {}: {}", name, e),
+        }
+    }
+}
+
+fn save_recording(path: &Path, samples: &[f32], sample_rate: u32) {
+    match wav::write_wav(path, samples, sample_rate) {
+        Ok(()) => eprintln!("Recorded {:.1}s of audio to {}",
+            samples.len() as f64 / 2.0 / sample_rate.max(1) as f64, path.display()),
+        Err(e) => eprintln!("Audio recording error: {}", e),
+    }
 }
 
-fn run_windowed(gb: &mut GameBoy, config: &config::Config) {
+fn run_windowed(gb: &mut GameBoy, config: &config::Config, record_audio: Option<&Path>, serial_stdout: bool) {
     // Set up audio output via cpal
-    let audio_buffer: Arc<Mutex<VecDeque<f32>>> = Arc::new(Mutex::new(VecDeque::new()));
-    let _stream = setup_audio(gb, &audio_buffer);
+    gb.cpu.bus.apu.high_pass_enabled = config.audio.high_pass_filter;
+    let mut audio = AudioState::new(gb.cpu.bus.apu.sample_rate);
+    let _stream = setup_audio(gb, &mut audio, &config.audio.device);
+    let mut latency = LatencyTracker::new(audio.sample_rate, config.audio.min_latency_frames, config.audio.max_latency_frames);
 
     let mut scale_idx: usize = config.scale_index();
-    let mut window = create_window(SCALE_STEPS[scale_idx].0);
+    let mut window = create_window(SCALE_STEPS[scale_idx].0, &config.display);
+    let border_color = config.display.fixed_window.as_ref()
+        .and_then(|fixed| filters::parse_hex_color(&fixed.border_color).ok())
+        .unwrap_or(0);
 
     let frame_duration = Duration::from_nanos(16_742_706); // ~59.7 Hz
     let ff_multiplier = config.speed.fast_forward_multiplier;
     let mut native_buf = vec![0u32; 160 * 144];
     let mut buffer = vec![0u32; 320 * 288];
+    let mut output_buffer = Vec::new();
+    let mut crt = config.display.crt_filter.as_ref().map(|_| filters::CrtFilter::new(320, 288));
+    let mut prev_framebuffer = [0u8; 160 * 144];
+    let mut rendered_palette: Option<[u32; 4]> = None;
 
     // Palette and scanline state (from config)
-    let mut palette_idx: usize = config.palette_index();
+    let mut palettes = filters::load_palettes_from_config(config);
+    let mut palette_idx: usize = palettes.iter()
+        .position(|(name, _)| *name == config.display.palette)
+        .unwrap_or_else(|| config.palette_index());
     let mut scanlines = config.display.scanlines;
+    let color_correction = config.display.color_correction;
 
-    // Build joypad key map from config
+    // Build joypad key and gamepad button maps from config
     let joypad_map = config.joypad_key_map();
+    let mut gamepad = GamepadInput::new(config.gamepad_button_map());
 
     // FPS tracking
     let mut frame_count: u32 = 0;
@@ -172,13 +425,34 @@ fn run_windowed(gb: &mut GameBoy, config: &config::Config) {
     // Debug windows
     let mut debug = debug::DebugWindows::new();
 
+    // Frames emulated so far, used in screenshot names
+    let mut total_frames: u64 = 0;
+
+    // F12 video recording
+    let mut video: Option<filters::VideoRecorder> = None;
+
+    // Samples for --record-audio, written when the window closes
+    let mut recording: Vec<f32> = Vec::new();
+
+    // Rewind history (hold R). Backspace is the default Select key, so
+    // rewind gets its own binding.
+    if config.rewind.enabled {
+        gb.set_rewind(Some(config.rewind.max_megabytes * 1024 * 1024));
+    }
+
+    // Save-state slot and its on-screen status message
+    let mut current_slot: u8 = 0;
+    let mut slot_status: Option<(String, Instant)> = None;
+    let mut title_dirty = false;
+
     while window.is_open() && !window.is_key_down(Key::Escape) {
         let frame_start = Instant::now();
 
         // Handle input
-        update_joypad(&window, gb, &joypad_map);
+        gamepad.poll();
+        update_joypad(&window, gb, &joypad_map, &gamepad);
 
-        // Debug window toggles (F1/F2/F3)
+        // Debug window toggles (F1/F2/F3/F4, Shift+F3, Shift+F5, Ctrl+A)
         debug.handle_toggles(&window);
 
         // Speed controls
@@ -200,22 +474,77 @@ fn run_windowed(gb: &mut GameBoy, config: &config::Config) {
             speed_mode = if ff_locked || tab_held { SpeedMode::FastForward } else { SpeedMode::Normal };
         }
 
-        // Save states
-        if window.is_key_pressed(Key::F5, minifb::KeyRepeat::No) {
-            if let Err(e) = gb.save_state_to_slot(0) {
-                eprintln!("Save state error: {}", e);
+        // Save states (F6 cycles the slot)
+        if !shift_held && window.is_key_pressed(Key::F6, minifb::KeyRepeat::No) {
+            current_slot = (current_slot + 1) % savestate::SAVE_SLOTS;
+            slot_status = Some((format!("slot {}", current_slot), Instant::now()));
+            title_dirty = true;
+        }
+        if !shift_held && window.is_key_pressed(Key::F5, minifb::KeyRepeat::No) {
+            match gb.save_state_to_slot(current_slot) {
+                Ok(()) => slot_status = Some((format!("saved slot {}", current_slot), Instant::now())),
+                Err(e) => eprintln!("Save state error: {}", e),
             }
         }
         if window.is_key_pressed(Key::F8, minifb::KeyRepeat::No) {
-            if let Err(e) = gb.load_state_from_slot(0) {
-                eprintln!("Load state error: {}", e);
+            match gb.load_state_from_slot(current_slot) {
+                Ok(()) => slot_status = Some((format!("loaded slot {}", current_slot), Instant::now())),
+                Err(e) => eprintln!("Load state error: {}", e),
+            }
+        }
+        if slot_status.as_ref().is_some_and(|(_, at)| at.elapsed() >= SLOT_STATUS_DURATION) {
+            slot_status = None;
+        }
+
+        // Screenshot at native resolution with the current palette
+        if window.is_key_pressed(Key::F9, minifb::KeyRepeat::No) {
+            let name = format!("{}_{}.png", screenshot_stem(&gb.cpu.bus.cartridge.title), total_frames);
+            let palette = palettes[palette_idx].1.map(|c| filters::apply_color_correction(c, color_correction));
+            save_screenshot(gb, Path::new(&name), &palette);
+        }
+
+        // Video recording toggle
+        if window.is_key_pressed(Key::F12, minifb::KeyRepeat::No) {
+            if let Some(recorder) = video.take() {
+                match recorder.finish() {
+                    Ok(msg) => eprintln!("Recording stopped: {}", msg),
+                    Err(e) => eprintln!("Video recording error: {}", e),
+                }
+            } else {
+                let name = format!("{}_{}.rgb", screenshot_stem(&gb.cpu.bus.cartridge.title), total_frames);
+                match filters::VideoRecorder::create(Path::new(&name)) {
+                    Ok(recorder) => {
+                        eprintln!("Recording video to {}", name);
+                        video = Some(recorder);
+                    }
+                    Err(e) => eprintln!("Video recording error: {}", e),
+                }
+            }
+            title_dirty = true;
+        }
+
+        // Debug layer toggles: Alt+B background, Alt+S sprites, Alt+W window
+        let alt_held = window.is_key_down(Key::LeftAlt) || window.is_key_down(Key::RightAlt);
+        if alt_held {
+            let ppu = &mut gb.cpu.bus.ppu;
+            if window.is_key_pressed(Key::B, minifb::KeyRepeat::No) {
+                ppu.bg_enabled_override = !ppu.bg_enabled_override;
+                title_dirty = true;
+            }
+            if window.is_key_pressed(Key::S, minifb::KeyRepeat::No) {
+                ppu.sprites_enabled_override = !ppu.sprites_enabled_override;
+                title_dirty = true;
+            }
+            if window.is_key_pressed(Key::W, minifb::KeyRepeat::No) {
+                ppu.window_enabled_override = !ppu.window_enabled_override;
+                title_dirty = true;
             }
         }
 
         // Palette / scanline controls
         if window.is_key_pressed(Key::P, minifb::KeyRepeat::No) {
-            palette_idx = (palette_idx + 1) % PALETTES.len();
-            eprintln!("Palette: {}", PALETTES[palette_idx].0);
+            palette_idx = (palette_idx + 1) % palettes.len();
+            eprintln!("Palette: {}", palettes[palette_idx].0);
         }
         if window.is_key_pressed(Key::F10, minifb::KeyRepeat::No) {
             scanlines = !scanlines;
@@ -224,14 +553,27 @@ fn run_windowed(gb: &mut GameBoy, config: &config::Config) {
 
         // Window scaling
         if window.is_key_pressed(Key::F11, minifb::KeyRepeat::No) {
-            scale_idx = (scale_idx + 1) % SCALE_STEPS.len();
-            window = create_window(SCALE_STEPS[scale_idx].0);
-            eprintln!("Scale: {}", SCALE_STEPS[scale_idx].1);
-            continue;
+            if config.display.fixed_window.is_some() {
+                eprintln!("Scale is set by display.fixed_window");
+            } else {
+                scale_idx = (scale_idx + 1) % SCALE_STEPS.len();
+                window = create_window(SCALE_STEPS[scale_idx].0, &config.display);
+                eprintln!("Scale: {}", SCALE_STEPS[scale_idx].1);
+                continue;
+            }
+        }
+
+        // Rewinding replaces this frame's emulation with the previous snapshot
+        let rewound = window.is_key_down(Key::R) && gb.rewind_frame();
+        if rewound {
+            if let Ok(mut buf) = audio.buffer.lock() {
+                buf.clear();
+            }
+            latency.reset_queue();
         }
 
         // Determine whether to run a frame
-        let run_frame = match speed_mode {
+        let run_frame = !rewound && match speed_mode {
             SpeedMode::Normal | SpeedMode::FastForward => true,
             SpeedMode::Paused => {
                 // Frame step: N advances one frame while paused
@@ -242,41 +584,91 @@ fn run_windowed(gb: &mut GameBoy, config: &config::Config) {
         if run_frame {
             // Check if we have breakpoints to watch
             let has_breakpoints = debug.breakpoints()
-                .map_or(false, |bps| !bps.is_empty());
+                .map_or(false, |bps| !bps.is_empty())
+                || !debug.conditional_breakpoints().is_empty()
+                || !debug.watchpoints.is_empty();
 
             if has_breakpoints {
-                let bps = debug.breakpoints().unwrap().clone();
-                let hit = gb.run_frame_with_breakpoints(&bps);
+                let bps = debug.breakpoints().cloned().unwrap_or_default();
+                let hit = gb.run_frame_with_breakpoints(&bps, debug.conditional_breakpoints_mut());
                 if hit {
                     speed_mode = SpeedMode::Paused;
-                    eprintln!("Breakpoint hit at ${:04X}", gb.cpu.pc);
+                    match gb.cpu.bus.watchpoint_hit.take() {
+                        Some(wp) => eprintln!("Watchpoint {} hit at ${:04X}", wp, gb.cpu.pc),
+                        None => eprintln!("Breakpoint hit at ${:04X}", gb.cpu.pc),
+                    }
                 }
             } else {
                 gb.run_frame();
             }
+            total_frames += 1;
 
             if speed_mode == SpeedMode::FastForward {
                 // Mute audio during fast-forward: discard samples
                 gb.cpu.bus.apu.sample_buffer.clear();
-                if let Ok(mut buf) = audio_buffer.lock() {
+                if let Ok(mut buf) = audio.buffer.lock() {
                     buf.clear();
                 }
+                latency.reset_queue();
             } else {
-                drain_audio_samples(gb, &audio_buffer);
+                if record_audio.is_some() {
+                    recording.extend_from_slice(&gb.cpu.bus.apu.sample_buffer);
+                }
+                drain_audio_samples(gb, &audio, &mut latency);
+            }
+            // Link-port bytes (test ROM results), echoed only with --serial-stdout
+            let serial = gb.take_serial_output();
+            if serial_stdout && !serial.is_empty() {
+                let mut stdout = std::io::stdout();
+                stdout.write_all(&serial).ok();
+                stdout.flush().ok();
             }
+            save_printed_sheets(gb, total_frames);
         } else if !was_paused {
             // Just entered pause — clear audio buffer to silence output
-            if let Ok(mut buf) = audio_buffer.lock() {
+            if let Ok(mut buf) = audio.buffer.lock() {
                 buf.clear();
             }
+            latency.reset_queue();
         }
         was_paused = speed_mode == SpeedMode::Paused;
 
         // Convert framebuffer to u32 colors with current palette
-        let fb = gb.framebuffer();
-        let palette = &PALETTES[palette_idx].1;
-        for (i, &pixel) in fb.iter().enumerate() {
-            native_buf[i] = palette[(pixel & 0x03) as usize];
+        // Only changed DMG pixels are recolored unless the palette colors changed
+        let palette = palettes[palette_idx].1.map(|c| filters::apply_color_correction(c, color_correction));
+        let cgb_mode = gb.cpu.bus.ppu.cgb_mode;
+        if !cgb_mode && rendered_palette == Some(palette) {
+            for (i, pixel) in gb.cpu.bus.ppu.framebuffer_diff(&prev_framebuffer) {
+                native_buf[i] = palette[(pixel & 0x03) as usize];
+            }
+        } else {
+            native_buf.copy_from_slice(&gb.frame_rgb(&palette));
+            rendered_palette = if cgb_mode { None } else { Some(palette) };
+        }
+        prev_framebuffer = *gb.framebuffer();
+
+        if run_frame {
+            if let Some(recorder) = &mut video {
+                match recorder.push_frame(&native_buf) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        eprintln!("Recording reached the {} frame limit", filters::VIDEO_MAX_FRAMES);
+                        if let Some(Err(e)) = video.take().map(|r| r.finish()) {
+                            eprintln!("Video recording error: {}", e);
+                        }
+                        title_dirty = true;
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        video = None;
+                        title_dirty = true;
+                    }
+                }
+            }
+            // Blink the REC indicator about twice a second
+            if video.is_some() && total_frames.is_multiple_of(15) {
+                title_dirty = true;
+            }
         }
 
         // Upscale 2x and optionally apply scanlines
@@ -284,14 +676,35 @@ fn run_windowed(gb: &mut GameBoy, config: &config::Config) {
         if scanlines {
             filters::apply_scanlines(&mut buffer, 320, 288);
         }
+        if let (Some(crt), Some(crt_config)) = (&mut crt, &config.display.crt_filter) {
+            crt.apply(&mut buffer, crt_config);
+        }
+        if config.display.show_fps {
+            filters::render_text_overlay(&mut buffer, 320, &format!("{:.0} FPS", fps_display), 2, 2, 0x00FFFFFF, 0);
+        }
+        if let Some((msg, _)) = &slot_status {
+            filters::render_text_overlay(&mut buffer, 320, msg, 2, 288 - debug::font::CHAR_HEIGHT - 2, 0x00FFFFFF, 0);
+        }
 
-        window.update_with_buffer(&buffer, 320, 288).unwrap();
+        let (out_w, out_h, _) = window_layout(SCALE_STEPS[scale_idx].0, &config.display);
+        if config.display.fixed_window.is_some() {
+            output_buffer.resize(out_w * out_h, 0);
+            let viewport = filters::integer_viewport(out_w, out_h);
+            filters::letterbox(&buffer, 320, 288, &mut output_buffer, out_w, viewport, border_color);
+            window.update_with_buffer(&output_buffer, out_w, out_h).unwrap();
+        } else if (out_w, out_h) == (320, 288) {
+            window.update_with_buffer(&buffer, 320, 288).unwrap();
+        } else {
+            output_buffer.resize(out_w * out_h, 0);
+            filters::upscale_bilinear(&buffer, &mut output_buffer, 320, 288, out_w, out_h);
+            window.update_with_buffer(&output_buffer, out_w, out_h).unwrap();
+        }
 
         // Update debug windows
-        let debug_action = debug.update(gb, palette);
+        let debug_action = debug.update(gb, &palette);
         match debug_action {
             Some(debug::DebugAction::Step) => {
-                gb.run_step();
+                gb.step();
                 speed_mode = SpeedMode::Paused;
             }
             Some(debug::DebugAction::BreakpointHit) => {
@@ -300,6 +713,31 @@ fn run_windowed(gb: &mut GameBoy, config: &config::Config) {
             None => {}
         }
 
+        // Palette window (F7): switch palettes or edit and save the custom one
+        match debug.update_palette_editor(&palettes, palette_idx) {
+            Some(debug::palette::PaletteEvent::Select(idx)) => {
+                palette_idx = idx;
+                eprintln!("Palette: {}", palettes[palette_idx].0);
+            }
+            Some(debug::palette::PaletteEvent::SetCustom(colors)) => {
+                palette_idx = match palettes.iter().position(|(name, _)| *name == filters::CUSTOM_PALETTE) {
+                    Some(idx) => {
+                        palettes[idx].1 = colors;
+                        idx
+                    }
+                    None => {
+                        palettes.push((filters::CUSTOM_PALETTE, colors));
+                        palettes.len() - 1
+                    }
+                };
+                let hex: Vec<String> = colors.iter().map(|&c| filters::format_hex_color(c)).collect();
+                if let Err(e) = config::Config::save_custom_palette(&hex) {
+                    eprintln!("Could not save custom palette: {}", e);
+                }
+            }
+            None => {}
+        }
+
         // FPS counter
         frame_count += 1;
         let fps_elapsed = fps_timer.elapsed();
@@ -307,12 +745,13 @@ fn run_windowed(gb: &mut GameBoy, config: &config::Config) {
             fps_display = frame_count as f64 / fps_elapsed.as_secs_f64();
             frame_count = 0;
             fps_timer = Instant::now();
-            let mode_str = match speed_mode {
-                SpeedMode::Normal => "",
-                SpeedMode::FastForward => " [FAST]",
-                SpeedMode::Paused => " [PAUSED]",
-            };
-            window.set_title(&format!("GB Emulator — {:.1} FPS{}", fps_display, mode_str));
+            title_dirty = true;
+        }
+        if title_dirty {
+            let rec = video.is_some() && (total_frames / 15).is_multiple_of(2);
+            let title = window_title(fps_display, speed_mode, current_slot, &audio, rec);
+            window.set_title(&format!("{}{}", title, layers_status(&gb.cpu.bus.ppu)));
+            title_dirty = false;
         }
 
         // Frame timing
@@ -350,13 +789,68 @@ fn run_windowed(gb: &mut GameBoy, config: &config::Config) {
             }
         }
     }
+
+    if let Some(path) = record_audio {
+        // The APU runs at the output device's rate; the file is always 44.1 kHz
+        let samples = wav::resample_stereo(&recording, gb.cpu.bus.apu.sample_rate, RECORD_SAMPLE_RATE);
+        save_recording(path, &samples, RECORD_SAMPLE_RATE);
+    }
 }
 
-fn setup_audio(gb: &mut GameBoy, audio_buffer: &Arc<Mutex<VecDeque<f32>>>) -> Option<cpal::Stream> {
-    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+fn window_title(fps: f64, speed_mode: SpeedMode, slot: u8, audio: &AudioState, rec: bool) -> String {
+    let mode_str = match speed_mode {
+        SpeedMode::Normal => "",
+        SpeedMode::FastForward => " [FAST]",
+        SpeedMode::Paused => " [PAUSED]",
+    };
+    let latency = audio.audio_latency_frames();
+    let lag_str = if latency > 2.0 {
+        format!(" [audio lag: {:.1}fr]", latency)
+    } else {
+        String::new()
+    };
+    let rec_str = if rec { " \u{1F534} REC" } else { "" };
+    format!("GB Emulator — {:.1} FPS [slot {}]{}{}{}", fps, slot, mode_str, lag_str, rec_str)
+}
+
+/// " [BG off WIN off]"-style note for layers hidden with Alt+B/S/W.
+fn layers_status(ppu: &gb_emulator::ppu::Ppu) -> String {
+    let hidden: Vec<&str> = [
+        (ppu.bg_enabled_override, "BG"),
+        (ppu.sprites_enabled_override, "OBJ"),
+        (ppu.window_enabled_override, "WIN"),
+    ]
+    .iter()
+    .filter(|(shown, _)| !shown)
+    .map(|&(_, name)| name)
+    .collect();
+    if hidden.is_empty() {
+        String::new()
+    } else {
+        format!(" [{} off]", hidden.join("/"))
+    }
+}
+
+/// Output device named `name`, or the default one when it is empty or missing.
+fn find_output_device(host: &cpal::Host, name: &str) -> Option<cpal::Device> {
+    use cpal::traits::{DeviceTrait, HostTrait};
+
+    if !name.is_empty() {
+        let named = host.output_devices().ok()
+            .and_then(|mut devices| devices.find(|d| d.name().is_ok_and(|n| n == name)));
+        if named.is_some() {
+            return named;
+        }
+        eprintln!("Audio device '{}' not found; using the default", name);
+    }
+    host.default_output_device()
+}
+
+fn setup_audio(gb: &mut GameBoy, audio: &mut AudioState, device_name: &str) -> Option<cpal::Stream> {
+    use cpal::traits::{DeviceTrait, StreamTrait};
 
     let host = cpal::default_host();
-    let device = match host.default_output_device() {
+    let device = match find_output_device(&host, device_name) {
         Some(d) => d,
         None => {
             eprintln!("No audio output device found");
@@ -374,10 +868,12 @@ fn setup_audio(gb: &mut GameBoy, audio_buffer: &Arc<Mutex<VecDeque<f32>>>) -> Op
 
     let sample_rate = config.sample_rate().0;
     gb.cpu.bus.apu.set_sample_rate(sample_rate);
+    audio.sample_rate = sample_rate;
 
-    let buffer_clone = audio_buffer.clone();
+    let buffer_clone = audio.buffer.clone();
     let last_sample: Arc<Mutex<f32>> = Arc::new(Mutex::new(0.0));
     let last_sample_clone = last_sample.clone();
+    let underruns = audio.underruns.clone();
     let stream = device.build_output_stream(
         &config.into(),
         move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
@@ -386,6 +882,10 @@ fn setup_audio(gb: &mut GameBoy, audio_buffer: &Arc<Mutex<VecDeque<f32>>>) -> Op
             for i in 0..drain_count {
                 data[i] = buffer.pop_front().unwrap();
             }
+            // Ran dry part-way: a real underrun, unlike the empty queue while paused
+            if drain_count > 0 && drain_count < data.len() {
+                underruns.fetch_add(1, Ordering::Relaxed);
+            }
             // On underrun, hold last sample to avoid pops
             let hold = if drain_count > 0 {
                 let v = data[drain_count - 1];
@@ -417,12 +917,12 @@ fn setup_audio(gb: &mut GameBoy, audio_buffer: &Arc<Mutex<VecDeque<f32>>>) -> Op
     }
 }
 
-fn drain_audio_samples(gb: &mut GameBoy, audio_buffer: &Arc<Mutex<VecDeque<f32>>>) {
-    if let Ok(mut buffer) = audio_buffer.lock() {
+fn drain_audio_samples(gb: &mut GameBoy, audio: &AudioState, latency: &mut LatencyTracker) {
+    if let Ok(mut buffer) = audio.buffer.lock() {
+        let produced = gb.cpu.bus.apu.sample_buffer.len();
+        // Cap at the tuned target to prevent latency buildup
+        let max_samples = latency.record_frame(buffer.len(), produced, audio.underrun_count());
         buffer.extend(gb.cpu.bus.apu.sample_buffer.drain(..));
-        // Cap at ~4 frames of audio to prevent latency buildup
-        let sample_rate = gb.cpu.bus.apu.sample_rate as usize;
-        let max_samples = (sample_rate * 2 * 4) / 60; // stereo, 4 frames
         if buffer.len() > max_samples {
             let excess = buffer.len() - max_samples;
             drop(buffer.drain(..excess));
@@ -430,12 +930,102 @@ fn drain_audio_samples(gb: &mut GameBoy, audio_buffer: &Arc<Mutex<VecDeque<f32>>
     }
 }
 
-fn update_joypad(window: &Window, gb: &mut GameBoy, key_map: &[(Key, JoypadKey)]) {
+fn update_joypad(window: &Window, gb: &mut GameBoy, key_map: &[(Key, JoypadKey)], gamepad: &GamepadInput) {
     for &(key, joypad_key) in key_map {
-        if window.is_key_down(key) {
+        if window.is_key_down(key) || gamepad.is_down(joypad_key) {
             gb.cpu.bus.joypad.key_down(joypad_key);
         } else {
             gb.cpu.bus.joypad.key_up(joypad_key);
         }
     }
 }
+
+/// Game Boy buttons held on connected gamepads, tracked from gilrs events.
+struct GamepadInput {
+    gilrs: Option<gilrs::Gilrs>,
+    button_map: Vec<(gilrs::Button, JoypadKey)>,
+    held: Vec<(gilrs::GamepadId, JoypadKey)>,
+}
+
+impl GamepadInput {
+    fn new(button_map: Vec<(gilrs::Button, JoypadKey)>) -> Self {
+        let gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                eprintln!("Gamepad support unavailable: {}", e);
+                None
+            }
+        };
+        GamepadInput { gilrs, button_map, held: Vec::new() }
+    }
+
+    /// Drain pending gilrs events. A disconnected pad releases everything it held.
+    fn poll(&mut self) {
+        let Some(gilrs) = self.gilrs.as_mut() else { return };
+        while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+            match event {
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    for &(_, key) in self.button_map.iter().filter(|(b, _)| *b == button) {
+                        if !self.held.contains(&(id, key)) {
+                            self.held.push((id, key));
+                        }
+                    }
+                }
+                gilrs::EventType::ButtonReleased(button, _) => {
+                    for &(_, key) in self.button_map.iter().filter(|(b, _)| *b == button) {
+                        self.held.retain(|&held| held != (id, key));
+                    }
+                }
+                gilrs::EventType::Connected => {
+                    eprintln!("Gamepad connected: {}", gilrs.gamepad(id).name());
+                }
+                gilrs::EventType::Disconnected => {
+                    eprintln!("Gamepad disconnected: {}", gilrs.gamepad(id).name());
+                    self.held.retain(|&(pad, _)| pad != id);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn is_down(&self, key: JoypadKey) -> bool {
+        self.held.iter().any(|&(_, held)| held == key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_headless_stops_at_max_frames() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        assert_eq!(run_headless(&mut gb, 2, false, None), 2);
+    }
+
+    #[test]
+    fn test_run_headless_records_audio() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("record.wav");
+        let mut gb = GameBoy::new(Cartridge::default());
+        run_headless(&mut gb, 60, false, Some(&path));
+        let wav = std::fs::read(&path).unwrap();
+        // 60 frames is just under one second of 44.1 kHz stereo 16-bit samples
+        let data_len = wav.len() - 44;
+        assert!((data_len as i64 - 44100 * 4).abs() < 44100 * 4 / 50, "data length {}", data_len);
+    }
+
+    #[test]
+    fn test_screenshot_stem() {
+        assert_eq!(screenshot_stem("POKEMON RED"), "POKEMON_RED");
+        assert_eq!(screenshot_stem(""), "screenshot");
+    }
+
+    #[test]
+    fn test_positional_args_skip_flag_values() {
+        let args: Vec<String> = ["gb", "--max-frames", "120", "rom.gb", "--headless", "--record-audio", "out.wav"]
+            .iter().map(|s| s.to_string()).collect();
+        assert_eq!(flag_value(&args, "--max-frames"), Some("120"));
+        assert_eq!(positional_args(&args), vec!["rom.gb"]);
+    }
+}
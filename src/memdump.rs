@@ -0,0 +1,69 @@
+use std::io::{self, Write};
+use crate::cpu::memory::MemoryBus;
+
+/// Tracks WRAM/HRAM between frames and writes the bytes that changed.
+pub struct MemoryDumper {
+    prev_wram: [u8; 0x2000],
+    prev_hram: [u8; 0x7F],
+}
+
+impl MemoryDumper {
+    pub fn new(bus: &MemoryBus) -> Self {
+        let mut prev_wram = [0u8; 0x2000];
+        prev_wram.copy_from_slice(&bus.read_range(0xC000, 0x2000));
+        MemoryDumper {
+            prev_wram,
+            prev_hram: bus.hram,
+        }
+    }
+
+    /// Write one `"$ADDR: 0xVV"` line per byte changed since the last call.
+    pub fn write_changes<W: Write>(&mut self, bus: &MemoryBus, out: &mut W) -> io::Result<()> {
+        // Mapped $C000-$DFFF view, so CGB bank switches show up as changes
+        let wram = bus.read_range(0xC000, 0x2000);
+        for (i, (&new, old)) in wram.iter().zip(self.prev_wram.iter_mut()).enumerate() {
+            if new != *old {
+                writeln!(out, "${:04X}: 0x{:02X}", 0xC000 + i, new)?;
+                *old = new;
+            }
+        }
+        for (i, (&new, old)) in bus.hram.iter().zip(self.prev_hram.iter_mut()).enumerate() {
+            if new != *old {
+                writeln!(out, "${:04X}: 0x{:02X}", 0xFF80 + i, new)?;
+                *old = new;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::Cartridge;
+    use crate::gameboy::GameBoy;
+
+    #[test]
+    fn test_dump_reports_changed_wram_byte() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        // JR -2 at $D000 keeps the CPU away from $C000
+        gb.cpu.bus.wram[1][0..2].copy_from_slice(&[0x18, 0xFE]);
+        gb.cpu.pc = 0xD000;
+
+        let mut dumper = MemoryDumper::new(&gb.cpu.bus);
+
+        let mut frame1 = Vec::new();
+        gb.run_frame();
+        dumper.write_changes(&gb.cpu.bus, &mut frame1).unwrap();
+
+        gb.cpu.bus.wram[0][0] = 0xAB;
+        let mut frame2 = Vec::new();
+        gb.run_frame();
+        dumper.write_changes(&gb.cpu.bus, &mut frame2).unwrap();
+
+        let frame1 = String::from_utf8(frame1).unwrap();
+        let frame2 = String::from_utf8(frame2).unwrap();
+        assert!(!frame1.contains("$C000"));
+        assert!(frame2.lines().any(|l| l == "$C000: 0xAB"), "frame 2 output: {}", frame2);
+    }
+}
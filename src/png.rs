@@ -0,0 +1,43 @@
+/// Encode 24-bit RGB pixels (`width * height * 3` bytes, row-major) as a PNG.
+pub fn encode_rgb(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    assert_eq!(rgb.len(), width as usize * 3 * height as usize);
+
+    let mut png = Vec::new();
+    let mut encoder = ::png::Encoder::new(&mut png, width, height);
+    encoder.set_color(::png::ColorType::Rgb);
+    encoder.set_depth(::png::BitDepth::Eight);
+    // Writing into a Vec cannot fail once the size matches the header
+    let mut writer = encoder.write_header().expect("PNG header");
+    writer.write_image_data(rgb).expect("PNG image data");
+    writer.finish().expect("PNG end");
+    png
+}
+
+/// Decode a PNG written by `encode_rgb` back to (width, height, RGB bytes).
+#[cfg(test)]
+pub(crate) fn decode_rgb(data: &[u8]) -> (u32, u32, Vec<u8>) {
+    let mut reader = ::png::Decoder::new(data).read_info().unwrap();
+    let mut rgb = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut rgb).unwrap();
+    assert_eq!(info.color_type, ::png::ColorType::Rgb);
+    rgb.truncate(info.buffer_size());
+    (info.width, info.height, rgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_rgb_round_trip() {
+        let png = encode_rgb(2, 1, &[255, 0, 0, 0, 0, 255]);
+        assert_eq!(&png[0..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(decode_rgb(&png), (2, 1, vec![255, 0, 0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_encode_rgb_compresses_flat_frames() {
+        let png = encode_rgb(160, 144, &vec![0xAA; 160 * 144 * 3]);
+        assert!(png.len() < 2048, "{} bytes", png.len());
+    }
+}
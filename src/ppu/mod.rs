@@ -9,8 +9,8 @@ enum PpuMode {
 #[derive(Clone, Copy)]
 struct FifoPixel {
     color: u8,       // 2-bit color number (0-3)
-    palette: u8,     // palette register value
-    bg_priority: bool, // OAM BG-over-OBJ flag
+    palette: u8,     // DMG OBJ: palette register value; CGB: palette number
+    bg_priority: bool, // BG-over-OBJ: OAM flag on sprites, CGB attribute bit 7 on BG
     is_sprite: bool,
 }
 
@@ -68,6 +68,7 @@ struct Fetcher {
     state: FetcherState,
     tick: u8,           // counts 0/1 within each state (2 T-cycles per state)
     tile_index: u8,     // tile ID read from tilemap
+    tile_attr: u8,      // CGB attribute byte from VRAM bank 1
     tile_data_low: u8,
     tile_data_high: u8,
     tile_x: u8,         // current tile column in tilemap
@@ -80,6 +81,7 @@ impl Fetcher {
             state: FetcherState::ReadTileId,
             tick: 0,
             tile_index: 0,
+            tile_attr: 0,
             tile_data_low: 0,
             tile_data_high: 0,
             tile_x: 0,
@@ -91,6 +93,7 @@ impl Fetcher {
         self.state = FetcherState::ReadTileId;
         self.tick = 0;
         self.tile_index = 0;
+        self.tile_attr = 0;
         self.tile_data_low = 0;
         self.tile_data_high = 0;
     }
@@ -128,6 +131,28 @@ pub struct Ppu {
     pub obp1: u8,
     pub vblank_interrupt: bool,
     pub stat_interrupt: bool,
+    /// Set by `tick` when mode 0 begins (drives CGB HBlank DMA)
+    pub hblank_started: bool,
+    /// OR of all enabled STAT sources; the interrupt fires on its rising edge only
+    pub stat_irq_line: bool,
+    /// CGB rendering rules (LCDC.0 is BG/window master priority, not BG enable)
+    pub cgb_mode: bool,
+    /// CGB BG palette memory: 8 palettes x 4 colors, RGB555 little-endian
+    pub bg_palette_ram: [u8; 64],
+    /// BCPS ($FF68): bits 0-5 = palette RAM index, bit 7 = auto-increment
+    pub bcps: u8,
+    /// CGB OBJ palette memory, same layout as `bg_palette_ram`
+    pub obj_palette_ram: [u8; 64],
+    /// OCPS ($FF6A), same layout as BCPS
+    pub ocps: u8,
+    /// CGB output as RGB555, filled alongside `framebuffer` in CGB mode
+    pub cgb_framebuffer: Vec<u16>,
+    /// Debug layer toggles (true = drawn). Clearing one hides that layer as
+    /// if its LCDC bit were 0, without changing what the game reads back.
+    /// Hiding the BG also hides the window, as LCDC.0 does on DMG.
+    pub bg_enabled_override: bool,
+    pub sprites_enabled_override: bool,
+    pub window_enabled_override: bool,
 
     // Pixel FIFO fields
     bg_fifo: PixelFifo,
@@ -139,27 +164,88 @@ pub struct Ppu {
     scx_discard: u8,
     window_line_counter: u8,
     window_active: bool,
+    // Window was drawn at some point on this line; only then does the
+    // window line counter advance, so toggling LCDC.5 mid-frame freezes it.
+    // Stays set if LCDC.5 is cleared after the window started mid-line.
+    window_triggered_this_line: bool,
     wy_triggered: bool,
     sprite_fetching: bool,
     sprite_fetch_step: u8,
     sprite_fetch_idx: u8,  // index into scanline_sprites
     sprite_tile_data_low: u8,
     sprite_tile_data_high: u8,
-    drawing_cycles: u32,
+    drawing_cycles: u32, // T-cycles spent in the current (or last) mode 3
+    mode3_length: u32,   // minimum mode 3 length for this line, see `mode3_length`
     oam_scan_index: u8, // OAM entry being scanned (0-39)
     oam_scan_tick: u8,   // 0 or 1 within each 2-T-cycle OAM check
 }
 
 impl Ppu {
     pub fn read_stat(&self) -> u8 {
-        let mode_bits = match self.mode {
+        let lyc_flag = if self.ly == self.lyc { 0x04 } else { 0 };
+        (self.stat & 0xF8) | lyc_flag | self.stat_mode()
+    }
+
+    /// Current PPU mode as STAT bits 1:0 (0=HBlank, 1=VBlank, 2=OAM, 3=Drawing).
+    pub fn stat_mode(&self) -> u8 {
+        match self.mode {
             PpuMode::HBlank => 0,
             PpuMode::VBlank => 1,
             PpuMode::OamScan => 2,
             PpuMode::Drawing => 3,
-        };
-        let lyc_flag = if self.ly == self.lyc { 0x04 } else { 0 };
-        (self.stat & 0xF8) | lyc_flag | mode_bits
+        }
+    }
+
+    pub fn in_vblank(&self) -> bool {
+        self.mode == PpuMode::VBlank
+    }
+
+    pub fn scanline(&self) -> u8 {
+        self.ly
+    }
+
+    /// (index, new value) for every pixel that differs from `previous`.
+    pub fn framebuffer_diff(&self, previous: &[u8; 160 * 144]) -> Vec<(usize, u8)> {
+        self.framebuffer.iter()
+            .zip(previous.iter())
+            .enumerate()
+            .filter(|(_, (cur, prev))| cur != prev)
+            .map(|(i, (&cur, _))| (i, cur))
+            .collect()
+    }
+
+    /// RGB555 color `color` (0-3) of CGB BG palette `palette` (0-7).
+    pub fn bg_palette_color(&self, palette: u8, color: u8) -> u16 {
+        palette_ram_color(&self.bg_palette_ram, palette, color)
+    }
+
+    /// RGB555 color `color` (0-3) of CGB OBJ palette `palette` (0-7).
+    pub fn obj_palette_color(&self, palette: u8, color: u8) -> u16 {
+        palette_ram_color(&self.obj_palette_ram, palette, color)
+    }
+
+    pub fn read_bcps(&self) -> u8 {
+        self.bcps | 0x40 // bit 6 unused
+    }
+
+    pub fn read_bcpd(&self) -> u8 {
+        self.bg_palette_ram[(self.bcps & 0x3F) as usize]
+    }
+
+    pub fn write_bcpd(&mut self, byte: u8) {
+        write_palette_data(&mut self.bg_palette_ram, &mut self.bcps, byte);
+    }
+
+    pub fn read_ocps(&self) -> u8 {
+        self.ocps | 0x40
+    }
+
+    pub fn read_ocpd(&self) -> u8 {
+        self.obj_palette_ram[(self.ocps & 0x3F) as usize]
+    }
+
+    pub fn write_ocpd(&mut self, byte: u8) {
+        write_palette_data(&mut self.obj_palette_ram, &mut self.ocps, byte);
     }
 
     pub fn write_stat(&mut self, byte: u8) {
@@ -169,16 +255,21 @@ impl Ppu {
     pub fn tick(&mut self, t_cycles: u8, vram: &[u8], oam: &[u8]) {
         self.vblank_interrupt = false;
         self.stat_interrupt = false;
+        self.hblank_started = false;
 
         if self.lcdc & 0x80 == 0 {
+            self.stat_irq_line = false;
             return;
         }
+        // Picks up STAT/LYC register writes made since the last tick
+        self.update_stat_irq();
 
         let mut remaining = t_cycles as u32;
         while remaining > 0 {
             match self.mode {
                 PpuMode::Drawing => {
                     self.mode_clock += 1;
+                    self.drawing_cycles += 1;
                     self.tick_drawing(vram, oam);
                     remaining -= 1;
                 }
@@ -200,18 +291,16 @@ impl Ppu {
                     if self.mode_clock >= 456 {
                         self.mode_clock -= 456;
                         self.ly += 1;
-                        if self.window_active {
+                        if self.window_triggered_this_line {
                             self.window_line_counter += 1;
                         }
                         if self.ly == 144 {
                             self.mode = PpuMode::VBlank;
                             self.vblank_interrupt = true;
-                            self.check_stat_interrupt(1);
                         } else {
                             self.mode = PpuMode::OamScan;
-                            self.check_stat_interrupt(2);
                         }
-                        self.check_lyc();
+                        self.update_stat_irq();
                     }
                 }
                 PpuMode::VBlank => {
@@ -227,18 +316,24 @@ impl Ppu {
                             self.mode = PpuMode::OamScan;
                             self.window_line_counter = 0;
                             self.wy_triggered = false;
-                            self.check_stat_interrupt(2);
                         }
-                        self.check_lyc();
+                        self.update_stat_irq();
                     }
                 }
             }
         }
     }
 
+    /// Whether a sprite at OAM Y `oam_y` covers the current line. OAM Y is
+    /// the screen row plus 16, so sprites partly above the screen count.
+    fn sprite_on_line(&self, oam_y: u8) -> bool {
+        let sprite_height: u16 = if self.lcdc & 0x04 != 0 { 16 } else { 8 };
+        let row = self.ly as u16 + 16;
+        (oam_y as u16..oam_y as u16 + sprite_height).contains(&row)
+    }
+
     fn do_full_oam_scan(&mut self, oam: &[u8]) {
         self.sprite_count = 0;
-        let sprite_height: u8 = if self.lcdc & 0x04 != 0 { 16 } else { 8 };
         for i in 0..40u8 {
             if self.sprite_count >= 10 { break; }
             let base = i as usize * 4;
@@ -246,8 +341,7 @@ impl Ppu {
             let sx = oam[base + 1];
             let tile = oam[base + 2];
             let flags = oam[base + 3];
-            let screen_y = sy.wrapping_sub(16);
-            if self.ly >= screen_y && self.ly < screen_y.wrapping_add(sprite_height) {
+            if self.sprite_on_line(sy) {
                 self.scanline_sprites[self.sprite_count as usize] = SpriteEntry {
                     oam_index: i, x: sx, y: sy, tile, flags,
                 };
@@ -256,8 +350,59 @@ impl Ppu {
         }
     }
 
+    /// Mode 3 length in T-cycles the current line will take with `oam`, by
+    /// the same model the PPU uses (see `mode3_length`).
+    pub fn count_mode3_penalty(&self, oam: &[u8]) -> u32 {
+        let sprite_x = oam.chunks_exact(4)
+            .filter(|entry| self.sprite_on_line(entry[0]))
+            .take(10)
+            .map(|entry| entry[1]);
+        self.mode3_length_for(sprite_x)
+    }
+
+    /// Length of the current, or most recent, mode 3 in T-cycles. HBlank
+    /// starts once the FIFO has pushed 160 pixels and `mode3_length` cycles
+    /// have passed, whichever is later.
+    pub fn mode3_cycles(&self) -> u32 {
+        self.drawing_cycles
+    }
+
+    /// Mode 3 length for the line just scanned: 172 T-cycles, plus the SCX & 7
+    /// discarded pixels, plus 6-11 per fetched sprite. A sprite also waits for
+    /// the BG fetch of the tile under its left edge (up to 5 cycles), unless
+    /// an earlier sprite already waited on that tile.
+    fn mode3_length(&self) -> u32 {
+        let sprites = &self.scanline_sprites[..self.sprite_count as usize];
+        self.mode3_length_for(sprites.iter().map(|sprite| sprite.x))
+    }
+
+    /// `mode3_length` for sprites at OAM X positions `sprite_x`, in OAM order.
+    fn mode3_length_for(&self, sprite_x: impl Iterator<Item = u8>) -> u32 {
+        let fine_scroll = (self.scx & 7) as u32;
+        let mut length = 172 + fine_scroll;
+        if self.lcdc & 0x02 == 0 {
+            return length;
+        }
+        let mut tiles_waited: u32 = 0;
+        for x in sprite_x {
+            if x >= 168 {
+                continue; // never reaches the screen, never fetched
+            }
+            let pos = x as u32 + fine_scroll;
+            let tile = 1u32 << (pos / 8);
+            if tiles_waited & tile == 0 {
+                length += 5u32.saturating_sub(pos % 8);
+                tiles_waited |= tile;
+            }
+            length += 6;
+        }
+        length
+    }
+
     fn start_drawing(&mut self) {
         self.mode = PpuMode::Drawing;
+        self.drawing_cycles = 0;
+        self.mode3_length = self.mode3_length();
         self.bg_fifo.clear();
         self.obj_fifo.clear();
         self.pixel_x = 0;
@@ -266,6 +411,7 @@ impl Ppu {
         self.fetcher.tile_x = self.scx / 8;
         self.fetcher.fetching_window = false;
         self.window_active = false;
+        self.window_triggered_this_line = false;
         self.sprite_fetching = false;
 
         // Check if window Y condition met on this frame
@@ -278,11 +424,26 @@ impl Ppu {
 
     #[inline(always)]
     fn tick_drawing(&mut self, vram: &[u8], oam: &[u8]) {
+        // All 160 pixels are out; HBlank waits for the computed length
+        if self.pixel_x >= 160 {
+            if self.mode3_cycles() >= self.mode3_length {
+                self.mode = PpuMode::HBlank;
+                self.hblank_started = true;
+                self.update_stat_irq();
+            }
+            return;
+        }
+
         if self.sprite_fetching {
             self.tick_sprite_fetch(vram);
             return;
         }
 
+        // LCDC.5 cleared mid-line: the fetcher falls back to the BG map
+        if self.window_active && self.lcdc & 0x20 == 0 {
+            self.deactivate_window();
+        }
+
         // Tick BG/window fetcher first so a Push fills the FIFO before sprite check
         self.tick_fetcher(vram);
 
@@ -327,16 +488,22 @@ impl Ppu {
                 let tile_col = (self.fetcher.tile_x & 31) as u16;
                 let map_addr = tile_map_base + tile_row * 32 + tile_col;
                 self.fetcher.tile_index = vram[map_addr as usize];
+                // VRAM bank 1 follows bank 0 in the slice when present
+                self.fetcher.tile_attr = if self.cgb_mode {
+                    vram.get(0x2000 + map_addr as usize).copied().unwrap_or(0)
+                } else {
+                    0
+                };
                 self.fetcher.state = FetcherState::ReadTileDataLow;
             }
             FetcherState::ReadTileDataLow => {
                 let addr = self.tile_data_addr();
-                self.fetcher.tile_data_low = vram[addr as usize];
+                self.fetcher.tile_data_low = vram.get(addr).copied().unwrap_or(0);
                 self.fetcher.state = FetcherState::ReadTileDataHigh;
             }
             FetcherState::ReadTileDataHigh => {
                 let addr = self.tile_data_addr() + 1;
-                self.fetcher.tile_data_high = vram[addr as usize];
+                self.fetcher.tile_data_high = vram.get(addr).copied().unwrap_or(0);
                 self.fetcher.state = FetcherState::Push;
             }
             FetcherState::Push => {
@@ -345,16 +512,19 @@ impl Ppu {
                     self.fetcher.tick = 0;
                     return;
                 }
+                let attr = self.fetcher.tile_attr;
+                let palette = attr & 0x07;
+                let x_flip = attr & 0x20 != 0;
                 let mut row = [FifoPixel::blank(); 8];
                 for bit in 0..8u8 {
-                    let shift = 7 - bit;
+                    let shift = if x_flip { bit } else { 7 - bit };
                     let lo = (self.fetcher.tile_data_low >> shift) & 1;
                     let hi = (self.fetcher.tile_data_high >> shift) & 1;
                     let color = (hi << 1) | lo;
                     row[bit as usize] = FifoPixel {
                         color,
-                        palette: 0, // BG uses bgp, resolved at output
-                        bg_priority: false,
+                        palette, // DMG ignores this and applies bgp at output
+                        bg_priority: attr & 0x80 != 0,
                         is_sprite: false,
                     };
                 }
@@ -365,21 +535,29 @@ impl Ppu {
         }
     }
 
-    fn tile_data_addr(&self) -> u16 {
+    /// Index into `vram` of the current tile row's low byte. CGB attributes
+    /// select VRAM bank 1 (bit 3) and flip the tile vertically (bit 6).
+    fn tile_data_addr(&self) -> usize {
         let signed_addressing = self.lcdc & 0x10 == 0;
         let y = if self.fetcher.fetching_window {
             self.window_line_counter
         } else {
             self.ly.wrapping_add(self.scy)
         };
-        let pixel_row = (y % 8) as u16;
+        let attr = self.fetcher.tile_attr;
+        let mut pixel_row = (y % 8) as usize;
+        if attr & 0x40 != 0 {
+            pixel_row = 7 - pixel_row;
+        }
+        let bank = if attr & 0x08 != 0 { 0x2000 } else { 0 };
 
-        if signed_addressing {
-            let signed_index = self.fetcher.tile_index as i8 as i16;
-            (0x0800i16 + (signed_index + 128) * 16 + pixel_row as i16 * 2) as u16
+        let tile_addr = if signed_addressing {
+            let signed_index = self.fetcher.tile_index as i8 as isize;
+            (0x0800 + (signed_index + 128) * 16) as usize
         } else {
-            self.fetcher.tile_index as u16 * 16 + pixel_row * 2
-        }
+            self.fetcher.tile_index as usize * 16
+        };
+        bank + tile_addr + pixel_row * 2
     }
 
     // --- Pixel output ---
@@ -411,39 +589,47 @@ impl Ppu {
 
         // Resolve final color
         let fb_idx = self.ly as usize * 160 + self.pixel_x as usize;
-        let bg_enabled = self.lcdc & 0x01 != 0;
+        // DMG: LCDC.0 blanks BG/window. CGB: BG always drawn, but LCDC.0=0
+        // strips BG priority so sprites always win.
+        let bg_enabled = self.bg_enabled_override && (self.cgb_mode || self.lcdc & 0x01 != 0);
+        let bg_master_priority = !self.cgb_mode || self.lcdc & 0x01 != 0;
 
         let bg_color_num = if bg_enabled { bg_pixel.color } else { 0 };
         let bg_color = (self.bgp >> (bg_color_num * 2)) & 0x03;
 
-        let final_color = if let Some(op) = obj_pixel {
-            if op.color == 0 || !op.is_sprite {
-                // Sprite transparent
-                bg_color
-            } else if op.bg_priority && bg_color_num != 0 {
-                // BG-over-OBJ and BG is not color 0
-                bg_color
-            } else {
-                (op.palette >> (op.color * 2)) & 0x03
-            }
+        let sprite = match obj_pixel {
+            // Sprites are still fetched (mode 3 timing is unchanged), just not shown
+            _ if !self.sprites_enabled_override => None,
+            Some(op) if op.color == 0 || !op.is_sprite => None, // Sprite transparent
+            // BG-over-OBJ (OAM flag or CGB BG attribute) and BG is not color 0
+            Some(op) if (op.bg_priority || bg_pixel.bg_priority) && bg_master_priority && bg_color_num != 0 => None,
+            Some(op) => Some(op),
+            None => None,
+        };
+
+        let sprite_color = if self.cgb_mode {
+            self.cgb_framebuffer[fb_idx] = match sprite {
+                Some(op) => self.obj_palette_color(op.palette, op.color),
+                None => self.bg_palette_color(bg_pixel.palette, bg_color_num),
+            };
+            // No OBP shades on CGB: keep the raw color number
+            sprite.map(|op| op.color)
         } else {
-            bg_color
+            sprite.map(|op| (op.palette >> (op.color * 2)) & 0x03)
         };
 
-        self.framebuffer[fb_idx] = final_color;
+        self.framebuffer[fb_idx] = sprite_color.unwrap_or(bg_color);
         self.pixel_x += 1;
 
         // Check window trigger
-        if !self.window_active && self.wy_triggered && self.lcdc & 0x20 != 0 {
-            if self.wx <= 166 && self.pixel_x >= self.wx.wrapping_sub(7) {
-                self.activate_window();
-            }
-        }
-
-        // Check if scanline is done
-        if self.pixel_x >= 160 {
-            self.mode = PpuMode::HBlank;
-            self.check_stat_interrupt(0);
+        if !self.window_active
+            && self.wy_triggered
+            && self.lcdc & 0x20 != 0
+            && self.window_enabled_override
+            && self.wx <= 166
+            && self.pixel_x >= self.wx.wrapping_sub(7)
+        {
+            self.activate_window();
         }
     }
 
@@ -451,12 +637,21 @@ impl Ppu {
 
     fn activate_window(&mut self) {
         self.window_active = true;
+        self.window_triggered_this_line = true;
         self.bg_fifo.clear();
         self.fetcher.reset();
         self.fetcher.tile_x = 0;
         self.fetcher.fetching_window = true;
     }
 
+    fn deactivate_window(&mut self) {
+        self.window_active = false;
+        self.fetcher.reset();
+        let next_x = self.scx as usize + self.pixel_x as usize + self.bg_fifo.len() as usize;
+        self.fetcher.tile_x = (next_x / 8) as u8;
+        self.fetcher.fetching_window = false;
+    }
+
     // --- Sprite fetching ---
 
     fn check_sprite_trigger(&mut self) -> bool {
@@ -499,7 +694,11 @@ impl Ppu {
                 (sprite.tile, row)
             };
 
-            let addr = tile.0 as u16 * 16 + tile.1 as u16 * 2;
+            let mut addr = tile.0 as u16 * 16 + tile.1 as u16 * 2;
+            // CGB: OAM attribute bit 3 selects VRAM bank 1
+            if self.cgb_mode && sprite.flags & 0x08 != 0 {
+                addr += 0x2000;
+            }
             self.sprite_tile_data_low = vram[addr as usize];
             self.sprite_tile_data_high = vram[(addr + 1) as usize];
         } else if self.sprite_fetch_step >= 6 {
@@ -520,7 +719,13 @@ impl Ppu {
     fn mix_sprite_pixels(&mut self) {
         let sprite = self.scanline_sprites[self.sprite_fetch_idx as usize];
         let x_flip = sprite.flags & 0x20 != 0;
-        let palette = if sprite.flags & 0x10 != 0 { self.obp1 } else { self.obp0 };
+        let palette = if self.cgb_mode {
+            sprite.flags & 0x07
+        } else if sprite.flags & 0x10 != 0 {
+            self.obp1
+        } else {
+            self.obp0
+        };
         let bg_priority = sprite.flags & 0x80 != 0;
 
         // Sprites with X < 8 are partially off the left edge — clip leading pixels
@@ -557,22 +762,25 @@ impl Ppu {
 
     // --- STAT interrupt helpers ---
 
-    fn check_lyc(&mut self) {
-        if self.ly == self.lyc && self.stat & 0x40 != 0 {
-            self.stat_interrupt = true;
-        }
+    fn stat_line(&self) -> bool {
+        let lyc = self.stat & 0x40 != 0 && self.ly == self.lyc;
+        let mode = match self.mode {
+            PpuMode::HBlank => self.stat & 0x08 != 0,
+            PpuMode::VBlank => self.stat & 0x10 != 0,
+            PpuMode::OamScan => self.stat & 0x20 != 0,
+            PpuMode::Drawing => false,
+        };
+        lyc || mode
     }
 
-    fn check_stat_interrupt(&mut self, mode: u8) {
-        let bit = match mode {
-            0 => 0x08,
-            1 => 0x10,
-            2 => 0x20,
-            _ => 0,
-        };
-        if self.stat & bit != 0 {
+    // Raise the STAT interrupt only when the combined line goes low -> high, so
+    // e.g. LY=LYC and a mode change on the same cycle yield a single IRQ
+    fn update_stat_irq(&mut self) {
+        let line = self.stat_line();
+        if line && !self.stat_irq_line {
             self.stat_interrupt = true;
         }
+        self.stat_irq_line = line;
     }
 }
 
@@ -607,6 +815,7 @@ impl Ppu {
         write_u8(buf, self.scx_discard);
         write_u8(buf, self.window_line_counter);
         write_bool(buf, self.window_active);
+        write_bool(buf, self.window_triggered_this_line);
         write_bool(buf, self.wy_triggered);
         write_bool(buf, self.sprite_fetching);
         write_u8(buf, self.sprite_fetch_step);
@@ -614,6 +823,7 @@ impl Ppu {
         write_u8(buf, self.sprite_tile_data_low);
         write_u8(buf, self.sprite_tile_data_high);
         write_u32_le(buf, self.drawing_cycles);
+        write_u32_le(buf, self.mode3_length);
         write_u8(buf, self.oam_scan_index);
         write_u8(buf, self.oam_scan_tick);
         write_u8(buf, self.sprite_count);
@@ -658,6 +868,13 @@ impl Ppu {
             write_bool(buf, p.bg_priority);
             write_bool(buf, p.is_sprite);
         }
+
+        // CGB palettes, STAT line (v0x04)
+        write_bool(buf, self.stat_irq_line);
+        write_bytes(buf, &self.bg_palette_ram);
+        write_u8(buf, self.bcps);
+        write_bytes(buf, &self.obj_palette_ram);
+        write_u8(buf, self.ocps);
     }
 
     pub fn load_state(&mut self, data: &[u8], cursor: &mut usize) {
@@ -690,6 +907,7 @@ impl Ppu {
         self.scx_discard = read_u8(data, cursor);
         self.window_line_counter = read_u8(data, cursor);
         self.window_active = read_bool(data, cursor);
+        self.window_triggered_this_line = read_bool(data, cursor);
         self.wy_triggered = read_bool(data, cursor);
         self.sprite_fetching = read_bool(data, cursor);
         self.sprite_fetch_step = read_u8(data, cursor);
@@ -697,6 +915,7 @@ impl Ppu {
         self.sprite_tile_data_low = read_u8(data, cursor);
         self.sprite_tile_data_high = read_u8(data, cursor);
         self.drawing_cycles = read_u32_le(data, cursor);
+        self.mode3_length = read_u32_le(data, cursor);
         self.oam_scan_index = read_u8(data, cursor);
         self.oam_scan_tick = read_u8(data, cursor);
         self.sprite_count = read_u8(data, cursor);
@@ -744,6 +963,37 @@ impl Ppu {
                 is_sprite: read_bool(data, cursor),
             };
         }
+
+        // CGB palettes, STAT line (v0x04)
+        self.stat_irq_line = read_bool(data, cursor);
+        let bg_palettes = read_bytes(data, cursor, 64);
+        self.bg_palette_ram.copy_from_slice(bg_palettes);
+        self.bcps = read_u8(data, cursor);
+        let obj_palettes = read_bytes(data, cursor, 64);
+        self.obj_palette_ram.copy_from_slice(obj_palettes);
+        self.ocps = read_u8(data, cursor);
+    }
+}
+
+fn palette_ram_color(ram: &[u8; 64], palette: u8, color: u8) -> u16 {
+    let idx = ((palette as usize & 0x07) * 4 + (color as usize & 0x03)) * 2;
+    u16::from_le_bytes([ram[idx], ram[idx + 1]])
+}
+
+/// Expand a CGB RGB555 color (red in bits 0-4, blue in 10-14) to 0x00RRGGBB.
+pub fn rgb555_to_rgb888(color: u16) -> u32 {
+    let expand = |shift: u16| {
+        let c = ((color >> shift) & 0x1F) as u32;
+        (c << 3) | (c >> 2)
+    };
+    (expand(0) << 16) | (expand(5) << 8) | expand(10)
+}
+
+// BCPD/OCPD write: store at the spec index, then auto-increment if bit 7 is set
+fn write_palette_data(ram: &mut [u8; 64], spec: &mut u8, byte: u8) {
+    ram[(*spec & 0x3F) as usize] = byte;
+    if *spec & 0x80 != 0 {
+        *spec = 0x80 | ((*spec + 1) & 0x3F);
     }
 }
 
@@ -766,6 +1016,14 @@ impl Default for Ppu {
             obp1: 0xFF,
             vblank_interrupt: false,
             stat_interrupt: false,
+            hblank_started: false,
+            stat_irq_line: false,
+            cgb_mode: false,
+            bg_palette_ram: [0; 64],
+            bcps: 0,
+            obj_palette_ram: [0; 64],
+            ocps: 0,
+            cgb_framebuffer: vec![0; 160 * 144],
             bg_fifo: PixelFifo::new(),
             obj_fifo: PixelFifo::new(),
             fetcher: Fetcher::new(),
@@ -775,6 +1033,7 @@ impl Default for Ppu {
             scx_discard: 0,
             window_line_counter: 0,
             window_active: false,
+            window_triggered_this_line: false,
             wy_triggered: false,
             sprite_fetching: false,
             sprite_fetch_step: 0,
@@ -782,8 +1041,15 @@ impl Default for Ppu {
             sprite_tile_data_low: 0,
             sprite_tile_data_high: 0,
             drawing_cycles: 0,
+            mode3_length: 172,
             oam_scan_index: 0,
             oam_scan_tick: 0,
+            bg_enabled_override: true,
+            sprites_enabled_override: true,
+            window_enabled_override: true,
         }
     }
 }
+
+#[cfg(test)]
+mod tests;
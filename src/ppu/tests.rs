@@ -0,0 +1,534 @@
+use super::*;
+
+/// Run the PPU for one full scanline (456 T-cycles).
+fn tick_scanline(ppu: &mut Ppu, vram: &[u8], oam: &[u8]) {
+    for _ in 0..114 {
+        ppu.tick(4, vram, oam);
+    }
+}
+
+/// VRAM with BG tile 0 solid color 3 and sprite tile 1 solid color 1;
+/// OAM with one BG-priority sprite at the top-left corner.
+fn sprite_over_bg_setup() -> ([u8; 0x2000], [u8; 0xA0]) {
+    let mut vram = [0u8; 0x2000];
+    for row in 0..8 {
+        vram[row * 2] = 0xFF;
+        vram[row * 2 + 1] = 0xFF;
+        vram[16 + row * 2] = 0xFF;
+    }
+    let mut oam = [0u8; 0xA0];
+    oam[0..4].copy_from_slice(&[16, 8, 1, 0x80]);
+    (vram, oam)
+}
+
+// ===============================================
+// Tests for BG/OBJ priority
+// ===============================================
+#[test]
+fn test_dmg_bg_priority_hides_sprite() {
+    let (vram, oam) = sprite_over_bg_setup();
+    let mut ppu = Ppu::default();
+    ppu.bgp = 0xE4;
+    ppu.obp0 = 0xE4;
+    ppu.lcdc = 0x93;
+
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.framebuffer[0], 3, "BG color 3 should cover BG-priority sprite");
+}
+
+#[test]
+fn test_cgb_lcdc0_off_sprite_wins() {
+    let (vram, oam) = sprite_over_bg_setup();
+    let mut ppu = Ppu::default();
+    ppu.cgb_mode = true;
+    ppu.bgp = 0xE4;
+    ppu.obp0 = 0xE4;
+    ppu.lcdc = 0x92; // master priority off
+
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.framebuffer[0], 1, "sprite should win when CGB master priority is off");
+    assert_eq!(ppu.framebuffer[8], 3, "BG is still drawn on CGB with LCDC.0=0");
+}
+
+#[test]
+fn test_layer_overrides_hide_output_only() {
+    let (vram, mut oam) = sprite_over_bg_setup();
+    oam[3] = 0; // sprite above BG
+    let mut ppu = Ppu::default();
+    ppu.bgp = 0xE4;
+    ppu.obp0 = 0xE4;
+    ppu.lcdc = 0x93;
+    ppu.sprites_enabled_override = false;
+
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.framebuffer[0], 3, "hidden sprite shows the BG under it");
+    let with_sprite_fetch = ppu.mode3_cycles();
+    assert_eq!(ppu.lcdc, 0x93, "LCDC as seen by the game is untouched");
+
+    let mut ppu = Ppu::default();
+    ppu.bgp = 0xE4;
+    ppu.obp0 = 0xE4;
+    ppu.lcdc = 0x93;
+    ppu.bg_enabled_override = false;
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.framebuffer[0], 1, "sprite drawn over blank BG");
+    assert_eq!(ppu.framebuffer[8], 0, "BG blanked to color 0");
+    assert_eq!(ppu.mode3_cycles(), with_sprite_fetch, "mode 3 timing is unchanged");
+}
+
+#[test]
+fn test_window_override_keeps_window_off() {
+    let vram = [0u8; 0x2000];
+    let oam = [0u8; 0xA0];
+    let mut ppu = Ppu::default();
+    ppu.wy = 0;
+    ppu.wx = 7;
+    ppu.lcdc = 0xB1;
+    ppu.window_enabled_override = false;
+
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.window_line_counter, 0);
+}
+
+#[test]
+fn test_hblank_started_set_on_mode0_entry() {
+    let vram = [0u8; 0x2000];
+    let oam = [0u8; 0xA0];
+    let mut ppu = Ppu::default();
+    let mut entries = 0;
+    for _ in 0..456 {
+        ppu.tick(1, &vram, &oam);
+        if ppu.hblank_started {
+            entries += 1;
+            assert_eq!(ppu.stat_mode(), 0);
+        }
+    }
+    assert_eq!(entries, 1);
+}
+
+// ===============================================
+// Tests for mode accessors
+// ===============================================
+#[test]
+fn test_in_vblank_at_ly_144() {
+    let vram = [0u8; 0x2000];
+    let oam = [0u8; 0xA0];
+    let mut ppu = Ppu::default();
+    assert_eq!(ppu.stat_mode(), 2);
+
+    for _ in 0..144 {
+        tick_scanline(&mut ppu, &vram, &oam);
+    }
+    assert_eq!(ppu.scanline(), 144);
+    assert!(ppu.in_vblank());
+    assert_eq!(ppu.stat_mode(), 1);
+
+    for _ in 144..154 {
+        tick_scanline(&mut ppu, &vram, &oam);
+    }
+    assert_eq!(ppu.scanline(), 0);
+    assert!(!ppu.in_vblank());
+    assert_eq!(ppu.stat_mode(), 2);
+}
+
+// ===============================================
+// Tests for BG palettes
+// ===============================================
+/// 16 KiB VRAM (banks 0 and 1): BG tile 0 solid color 3, tile map
+/// attributes selecting CGB palette 2.
+fn bg_attr_setup() -> Vec<u8> {
+    let mut vram = vec![0u8; 0x4000];
+    for row in 0..8 {
+        vram[row * 2] = 0xFF;
+        vram[row * 2 + 1] = 0xFF;
+    }
+    for attr in &mut vram[0x2000 + 0x1800..0x2000 + 0x1C00] {
+        *attr = 0x02;
+    }
+    vram
+}
+
+#[test]
+fn test_dmg_bg_ignores_attribute_palette() {
+    let vram = bg_attr_setup();
+    let oam = [0u8; 0xA0];
+    let mut ppu = Ppu::default();
+    ppu.lcdc = 0x91;
+    ppu.bgp = 0xE4;
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.framebuffer[0], 3);
+
+    // Same tile with an inverted BGP: output follows BGP, not the attribute
+    let mut ppu = Ppu::default();
+    ppu.lcdc = 0x91;
+    ppu.bgp = 0x1B;
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.framebuffer[0], 0);
+    assert_eq!(ppu.cgb_framebuffer[0], 0);
+}
+
+#[test]
+fn test_cgb_bg_uses_attribute_palette() {
+    let vram = bg_attr_setup();
+    let oam = [0u8; 0xA0];
+    let mut ppu = Ppu::default();
+    ppu.cgb_mode = true;
+    ppu.lcdc = 0x91;
+    // Palette 2, color 3 = pure blue
+    let idx = (2 * 4 + 3) * 2;
+    ppu.bg_palette_ram[idx..idx + 2].copy_from_slice(&0x7C00u16.to_le_bytes());
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.bg_palette_color(2, 3), 0x7C00);
+    assert_eq!(ppu.cgb_framebuffer[0], 0x7C00);
+    assert_eq!(ppu.cgb_framebuffer[159], 0x7C00);
+}
+
+/// CGB PPU with BG palette 0 set to distinct colors 1-3.
+fn cgb_attr_ppu() -> Ppu {
+    let mut ppu = Ppu { cgb_mode: true, lcdc: 0x91, ..Ppu::default() };
+    for (color, rgb) in [(1, 0x001Fu16), (2, 0x03E0), (3, 0x7C00)] {
+        ppu.bg_palette_ram[color * 2..color * 2 + 2].copy_from_slice(&rgb.to_le_bytes());
+    }
+    ppu
+}
+
+#[test]
+fn test_cgb_bg_attribute_selects_vram_bank() {
+    let mut vram = vec![0u8; 0x4000];
+    // Tile 0 is blank in bank 0 and solid color 3 in bank 1
+    for row in 0..8 {
+        vram[0x2000 + row * 2] = 0xFF;
+        vram[0x2000 + row * 2 + 1] = 0xFF;
+    }
+    vram[0x2000 + 0x1800..0x2000 + 0x1C00].fill(0x08);
+    let oam = [0u8; 0xA0];
+    let mut ppu = cgb_attr_ppu();
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.cgb_framebuffer[0], 0x7C00);
+}
+
+#[test]
+fn test_cgb_bg_attribute_flips() {
+    let mut vram = vec![0u8; 0x4000];
+    // Row 0: only the leftmost pixel is color 1. Row 7: all color 2.
+    vram[0] = 0x80;
+    vram[7 * 2 + 1] = 0xFF;
+    let oam = [0u8; 0xA0];
+
+    vram[0x2000 + 0x1800..0x2000 + 0x1C00].fill(0x20);
+    let mut ppu = cgb_attr_ppu();
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.cgb_framebuffer[0], ppu.bg_palette_color(0, 0));
+    assert_eq!(ppu.cgb_framebuffer[7], 0x001F, "X flip moves the pixel to the right edge");
+
+    vram[0x2000 + 0x1800..0x2000 + 0x1C00].fill(0x40);
+    let mut ppu = cgb_attr_ppu();
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.cgb_framebuffer[0], 0x03E0, "Y flip reads row 7 on line 0");
+}
+
+#[test]
+fn test_cgb_bg_attribute_priority_hides_sprite() {
+    let mut vram = vec![0u8; 0x4000];
+    for row in 0..8 {
+        vram[row * 2] = 0xFF;
+        vram[row * 2 + 1] = 0xFF;
+        vram[16 + row * 2] = 0xFF;
+    }
+    let mut oam = [0u8; 0xA0];
+    // Sprite without the OAM priority flag
+    oam[0..4].copy_from_slice(&[16, 8, 1, 0x00]);
+    let mut ppu = cgb_attr_ppu();
+    ppu.lcdc = 0x93;
+    ppu.obj_palette_ram[2..4].copy_from_slice(&0x7FFFu16.to_le_bytes());
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.cgb_framebuffer[0], 0x7FFF, "sprite wins without attribute bit 7");
+
+    vram[0x2000 + 0x1800..0x2000 + 0x1C00].fill(0x80);
+    let mut ppu = cgb_attr_ppu();
+    ppu.lcdc = 0x93;
+    ppu.obj_palette_ram[2..4].copy_from_slice(&0x7FFFu16.to_le_bytes());
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.cgb_framebuffer[0], 0x7C00, "attribute bit 7 puts BG color 3 on top");
+}
+
+#[test]
+fn test_bcpd_auto_increment() {
+    let mut ppu = Ppu::default();
+    ppu.bcps = 0x80 | 0x3E;
+    ppu.write_bcpd(0x11);
+    ppu.write_bcpd(0x22);
+    ppu.write_bcpd(0x33); // index wrapped to 0
+    assert_eq!(ppu.bg_palette_ram[0x3E], 0x11);
+    assert_eq!(ppu.bg_palette_ram[0x3F], 0x22);
+    assert_eq!(ppu.bg_palette_ram[0x00], 0x33);
+    assert_eq!(ppu.read_bcps(), 0xC1);
+
+    // Without bit 7 the index stays put
+    ppu.bcps = 0x05;
+    ppu.write_bcpd(0x44);
+    ppu.write_bcpd(0x55);
+    assert_eq!(ppu.read_bcpd(), 0x55);
+    assert_eq!(ppu.bcps, 0x05);
+}
+
+#[test]
+fn test_cgb_sprite_uses_obj_palette() {
+    let (vram, mut oam) = sprite_over_bg_setup();
+    oam[3] = 0x03; // sprite 0, CGB palette 3
+    let mut ppu = Ppu::default();
+    ppu.cgb_mode = true;
+    ppu.lcdc = 0x92;
+    let idx = (3 * 4 + 1) * 2;
+    ppu.obj_palette_ram[idx..idx + 2].copy_from_slice(&0x001Fu16.to_le_bytes());
+
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.obj_palette_color(3, 1), 0x001F);
+    assert_eq!(ppu.cgb_framebuffer[0], 0x001F);
+}
+
+#[test]
+fn test_cgb_sprite_attribute_selects_vram_bank() {
+    let mut vram = vec![0u8; 0x4000];
+    // Sprite tile 1 is blank in bank 0 and solid color 1 in bank 1
+    for row in 0..8 {
+        vram[0x2000 + 16 + row * 2] = 0xFF;
+    }
+    let mut oam = [0u8; 0xA0];
+    oam[0..4].copy_from_slice(&[16, 8, 1, 0x08]);
+    let mut ppu = cgb_attr_ppu();
+    ppu.lcdc = 0x93;
+    ppu.obj_palette_ram[2..4].copy_from_slice(&0x7FFFu16.to_le_bytes());
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.cgb_framebuffer[0], 0x7FFF, "sprite tile read from bank 1");
+
+    oam[3] = 0x00;
+    let mut ppu = cgb_attr_ppu();
+    ppu.lcdc = 0x93;
+    ppu.obj_palette_ram[2..4].copy_from_slice(&0x7FFFu16.to_le_bytes());
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.cgb_framebuffer[0], ppu.bg_palette_color(0, 0), "bank 0 tile is blank");
+}
+
+// ===============================================
+// Tests for window line counter
+// ===============================================
+#[test]
+fn test_window_line_counter_freezes_while_disabled() {
+    let vram = [0u8; 0x2000];
+    let oam = [0u8; 0xA0];
+    let mut ppu = Ppu::default();
+    ppu.wy = 0;
+    ppu.wx = 7;
+    ppu.lcdc = 0xB1; // window on
+
+    for _ in 0..3 {
+        tick_scanline(&mut ppu, &vram, &oam);
+    }
+    assert_eq!(ppu.window_line_counter, 3);
+
+    ppu.lcdc = 0x91; // window off for two lines
+    for _ in 0..2 {
+        tick_scanline(&mut ppu, &vram, &oam);
+    }
+    assert_eq!(ppu.window_line_counter, 3);
+
+    ppu.lcdc = 0xB1; // resumes where it left off
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.window_line_counter, 4);
+}
+
+#[test]
+fn test_window_disabled_mid_line_still_advances_counter() {
+    // BG map uses tile 0 (color 3), window map tile 1 (color 1)
+    let mut vram = [0u8; 0x2000];
+    for row in 0..8 {
+        vram[row * 2] = 0xFF;
+        vram[row * 2 + 1] = 0xFF;
+        vram[16 + row * 2] = 0xFF;
+    }
+    vram[0x1C00..0x2000].fill(1);
+    let oam = [0u8; 0xA0];
+    // Window on, window map at 0x9C00
+    let mut ppu = Ppu { bgp: 0xE4, wy: 0, wx: 7, lcdc: 0xF1, ..Ppu::default() };
+
+    // OAM scan plus part of mode 3, then clear LCDC.5 for the rest of the line
+    for _ in 0..40 {
+        ppu.tick(4, &vram, &oam);
+    }
+    ppu.lcdc = 0xD1;
+    for _ in 40..114 {
+        ppu.tick(4, &vram, &oam);
+    }
+    assert_eq!(ppu.framebuffer[16], 1, "window drawn early in the line");
+    assert_eq!(ppu.framebuffer[159], 3, "BG resumes once the window is off");
+    assert_eq!(ppu.window_line_counter, 1);
+}
+
+// ===============================================
+// Tests for STAT interrupt line
+// ===============================================
+/// Count STAT interrupts over `t_cycles`, ticking one T-cycle at a time.
+fn count_stat_irqs(ppu: &mut Ppu, t_cycles: u32) -> u32 {
+    let vram = [0u8; 0x2000];
+    let oam = [0u8; 0xA0];
+    let mut irqs = 0;
+    for _ in 0..t_cycles {
+        ppu.tick(1, &vram, &oam);
+        if ppu.stat_interrupt {
+            irqs += 1;
+        }
+    }
+    irqs
+}
+
+#[test]
+fn test_lyc_and_mode2_same_cycle_fire_once() {
+    let mut ppu = Ppu::default();
+    ppu.lyc = 1;
+    ppu.write_stat(0x60); // LYC and mode 2 sources
+    // Line 0 mode 2 fires once, then line 1 starts with both LY=LYC and mode 2
+    assert_eq!(count_stat_irqs(&mut ppu, 455), 1);
+    assert_eq!(count_stat_irqs(&mut ppu, 1), 1);
+    assert_eq!(ppu.ly, 1);
+    assert!(ppu.stat_irq_line);
+}
+
+#[test]
+fn test_lyc_high_blocks_hblank_irq() {
+    let mut ppu = Ppu::default();
+    ppu.lyc = 0;
+    ppu.write_stat(0x48); // LYC and mode 0 sources
+    // LY=LYC holds the line high for all of line 0, so HBlank does not fire again
+    assert_eq!(count_stat_irqs(&mut ppu, 455), 1);
+    // Line 1: LYC drops, then HBlank raises the line again
+    assert_eq!(count_stat_irqs(&mut ppu, 456), 1);
+}
+
+#[test]
+fn test_hblank_blocks_following_mode2_irq() {
+    let mut ppu = Ppu::default();
+    ppu.lyc = 0xFF;
+    ppu.write_stat(0x28); // mode 0 and mode 2 sources
+    // The line stays high from HBlank into the next OAM scan, so after the
+    // first mode 2 only the HBlank of each line raises an interrupt
+    assert_eq!(count_stat_irqs(&mut ppu, 456 * 3), 1 + 3);
+    assert_eq!(ppu.ly, 3);
+}
+
+// ===============================================
+// Tests for mode 3 length estimate
+// ===============================================
+#[test]
+fn test_count_mode3_penalty() {
+    let mut oam = [0u8; 0xA0];
+    for i in 0..5 {
+        oam[i * 4] = 16; // visible on LY 0
+        oam[i * 4 + 1] = 8 + i as u8 * 8;
+    }
+    let mut ppu = Ppu { lcdc: 0x93, ..Ppu::default() };
+    ppu.scx = 3;
+    // Each sprite sits 3 pixels into its own tile: 2 cycles waiting plus 6
+    assert_eq!(ppu.count_mode3_penalty(&oam), 172 + 3 + 5 * 8);
+    // Sprites off (LCDC.1): only the fine scroll counts
+    ppu.lcdc = 0x91;
+    assert_eq!(ppu.count_mode3_penalty(&oam), 172 + 3);
+
+    let ppu = Ppu::default();
+    assert_eq!(ppu.count_mode3_penalty(&[0u8; 0xA0]), 172);
+}
+
+#[test]
+fn test_count_mode3_penalty_partly_off_screen() {
+    // 8x16 sprite at OAM Y 10 covers LY 0-9
+    let mut oam = [0u8; 0xA0];
+    oam[0] = 10;
+    let mut ppu = Ppu { lcdc: 0x97, ..Ppu::default() };
+    assert_eq!(ppu.count_mode3_penalty(&oam), 172 + 11);
+
+    // The OAM scan selects it too, so the PPU runs the same length
+    let mut drawn = Ppu { lcdc: 0x97, ..Ppu::default() };
+    tick_scanline(&mut drawn, &[0u8; 0x2000], &oam);
+    assert_eq!(drawn.mode3_cycles(), 172 + 11);
+
+    ppu.ly = 10;
+    assert_eq!(ppu.count_mode3_penalty(&oam), 172);
+}
+
+#[test]
+fn test_sprite_partly_above_screen_is_drawn() {
+    // 8x16 sprite at OAM Y 10: LY 0 shows row 6 of its top tile (tile 2)
+    let mut vram = [0u8; 0x2000];
+    vram[2 * 16 + 6 * 2] = 0xFF;
+    let mut oam = [0u8; 0xA0];
+    oam[0..4].copy_from_slice(&[10, 8, 2, 0x00]);
+    let mut ppu = Ppu { lcdc: 0x97, obp0: 0xE4, bgp: 0xE4, ..Ppu::default() };
+    tick_scanline(&mut ppu, &vram, &oam);
+    assert_eq!(ppu.framebuffer[0], 1);
+}
+
+/// Mode 3 length on LY 0 with one sprite at each OAM X in `sprite_x`.
+fn measure_mode3(scx: u8, sprite_x: &[u8]) -> u32 {
+    let vram = [0u8; 0x2000];
+    let mut oam = [0u8; 0xA0];
+    for (i, &x) in sprite_x.iter().enumerate() {
+        oam[i * 4] = 16;
+        oam[i * 4 + 1] = x;
+    }
+    let mut ppu = Ppu::default();
+    ppu.lcdc = 0x93;
+    ppu.scx = scx;
+    tick_scanline(&mut ppu, &vram, &oam);
+    ppu.mode3_cycles()
+}
+
+#[test]
+fn test_mode3_length_tracks_scx_and_sprites() {
+    assert_eq!(measure_mode3(0, &[]), 172);
+    assert_eq!(measure_mode3(3, &[]), 175);
+    assert_eq!(measure_mode3(7, &[]), 179);
+
+    // Tile-aligned sprite waits the full 5 cycles; 5 pixels in, none
+    assert_eq!(measure_mode3(0, &[8]), 172 + 11);
+    assert_eq!(measure_mode3(0, &[13]), 172 + 6);
+    // SCX shifts the sprite against the tile grid
+    assert_eq!(measure_mode3(3, &[13]), 175 + 11);
+    // A second sprite on the same tile doesn't wait again
+    assert_eq!(measure_mode3(0, &[8, 9]), 172 + 11 + 6);
+    // Off-screen right: not fetched
+    assert_eq!(measure_mode3(0, &[168]), 172);
+
+    let ten: Vec<u8> = (0..10).map(|i| 8 + 16 * i).collect();
+    assert_eq!(measure_mode3(0, &ten), 172 + 10 * 11);
+}
+
+#[test]
+fn test_count_mode3_penalty_matches_scanline() {
+    for (scx, sprite_x) in [(0u8, vec![8u8, 9]), (3, vec![13, 40, 90]), (5, vec![0, 168, 100])] {
+        let mut oam = [0u8; 0xA0];
+        for (i, &x) in sprite_x.iter().enumerate() {
+            oam[i * 4] = 16;
+            oam[i * 4 + 1] = x;
+        }
+        let ppu = Ppu { lcdc: 0x93, scx, ..Ppu::default() };
+        assert_eq!(ppu.count_mode3_penalty(&oam), measure_mode3(scx, &sprite_x));
+    }
+}
+
+// ===============================================
+// Tests for framebuffer diffing
+// ===============================================
+#[test]
+fn test_framebuffer_diff_reports_changed_pixels() {
+    let previous = [0u8; 160 * 144];
+    let mut ppu = Ppu::default();
+    let indices: Vec<usize> = (0..10).map(|i| i * 1000 + 7).collect();
+    for (n, &i) in indices.iter().enumerate() {
+        ppu.framebuffer[i] = n as u8 + 1;
+    }
+
+    let diff = ppu.framebuffer_diff(&previous);
+    let expected: Vec<(usize, u8)> = indices.iter().enumerate().map(|(n, &i)| (i, n as u8 + 1)).collect();
+    assert_eq!(diff, expected);
+    assert!(ppu.framebuffer_diff(&ppu.framebuffer.clone()).is_empty());
+}
@@ -0,0 +1,306 @@
+//! Game Boy Printer on the serial port.
+//!
+//! Packets are `88 33 <cmd> <compression> <len lo> <len hi> <data..>
+//! <checksum lo> <checksum hi> 00 00`. The printer answers 0x81 to the
+//! first trailing 00 and its status byte to the second; every other byte
+//! is answered with 0x00. The checksum is the 16-bit sum of everything
+//! from the command byte to the end of the data.
+
+const CMD_INIT: u8 = 0x01;
+const CMD_PRINT: u8 = 0x02;
+const CMD_DATA: u8 = 0x04;
+const CMD_STATUS: u8 = 0x0F;
+
+const STATUS_CHECKSUM_ERROR: u8 = 0x01;
+const STATUS_PRINTING: u8 = 0x02;
+const STATUS_UNPROCESSED: u8 = 0x08;
+
+const DEVICE_ID: u8 = 0x81;
+/// One band of DATA: 20 tiles wide, 2 tiles tall
+const BAND_BYTES: usize = 640;
+/// The printer's image buffer holds 9 bands (160x144)
+const BUFFER_BYTES: usize = BAND_BYTES * 9;
+/// Status packets that still report "printing" after a PRINT
+const PRINT_BUSY_POLLS: u8 = 4;
+
+/// One printed sheet: `width` x `height` shades, 0 = white .. 3 = black.
+pub struct PrintedImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl PrintedImage {
+    pub fn to_png(&self) -> Vec<u8> {
+        const SHADES: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+        let rgb: Vec<u8> = self.pixels.iter()
+            .flat_map(|&shade| [SHADES[(shade & 0x03) as usize]; 3])
+            .collect();
+        crate::png::encode_rgb(self.width as u32, self.height as u32, &rgb)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum State {
+    Magic1,
+    Magic2,
+    Command,
+    Compression,
+    LengthLow,
+    LengthHigh,
+    Data,
+    ChecksumLow,
+    ChecksumHigh,
+    DeviceId,
+    Status,
+}
+
+pub struct Printer {
+    state: State,
+    command: u8,
+    compressed: bool,
+    length: u16,
+    packet_data: Vec<u8>,
+    checksum: u16,
+    received_checksum: u16,
+    /// Decompressed tile data waiting to be printed
+    image_data: Vec<u8>,
+    status: u8,
+    busy_polls: u8,
+    /// Sheets printed since the last `take_printed`
+    pub printed: Vec<PrintedImage>,
+}
+
+impl Default for Printer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Printer {
+    pub fn new() -> Self {
+        Printer {
+            state: State::Magic1,
+            command: 0,
+            compressed: false,
+            length: 0,
+            packet_data: Vec::new(),
+            checksum: 0,
+            received_checksum: 0,
+            image_data: Vec::new(),
+            status: 0,
+            busy_polls: 0,
+            printed: Vec::new(),
+        }
+    }
+
+    /// Shift one byte in from the Game Boy and return the byte shifted out.
+    pub fn exchange(&mut self, byte: u8) -> u8 {
+        let mut response = 0x00;
+        self.state = match self.state {
+            State::Magic1 if byte == 0x88 => State::Magic2,
+            State::Magic1 => State::Magic1,
+            State::Magic2 if byte == 0x33 => State::Command,
+            State::Magic2 => State::Magic1,
+            State::Command => {
+                self.command = byte;
+                self.checksum = byte as u16;
+                State::Compression
+            }
+            State::Compression => {
+                self.compressed = byte & 0x01 != 0;
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+                State::LengthLow
+            }
+            State::LengthLow => {
+                self.length = byte as u16;
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+                State::LengthHigh
+            }
+            State::LengthHigh => {
+                self.length |= (byte as u16) << 8;
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+                self.packet_data.clear();
+                if self.length == 0 { State::ChecksumLow } else { State::Data }
+            }
+            State::Data => {
+                self.packet_data.push(byte);
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+                if self.packet_data.len() == self.length as usize { State::ChecksumLow } else { State::Data }
+            }
+            State::ChecksumLow => {
+                self.received_checksum = byte as u16;
+                State::ChecksumHigh
+            }
+            State::ChecksumHigh => {
+                self.received_checksum |= (byte as u16) << 8;
+                State::DeviceId
+            }
+            State::DeviceId => {
+                response = DEVICE_ID;
+                State::Status
+            }
+            State::Status => {
+                self.finish_packet();
+                response = self.status;
+                State::Magic1
+            }
+        };
+        response
+    }
+
+    /// Drain the sheets printed since the last call.
+    pub fn take_printed(&mut self) -> Vec<PrintedImage> {
+        std::mem::take(&mut self.printed)
+    }
+
+    fn finish_packet(&mut self) {
+        if self.received_checksum != self.checksum {
+            self.status |= STATUS_CHECKSUM_ERROR;
+            return;
+        }
+        self.status &= !STATUS_CHECKSUM_ERROR;
+
+        // A finished print stays "printing" for a few polls, as games wait for it
+        if self.busy_polls > 0 {
+            self.busy_polls -= 1;
+            if self.busy_polls == 0 {
+                self.status &= !STATUS_PRINTING;
+            }
+        }
+
+        match self.command {
+            CMD_INIT => {
+                self.image_data.clear();
+                self.status = 0;
+                self.busy_polls = 0;
+            }
+            CMD_DATA => {
+                let data = std::mem::take(&mut self.packet_data);
+                let data = if self.compressed { decompress(&data) } else { data };
+                let room = BUFFER_BYTES - self.image_data.len();
+                self.image_data.extend_from_slice(&data[..data.len().min(room)]);
+                if !self.image_data.is_empty() {
+                    self.status |= STATUS_UNPROCESSED;
+                }
+            }
+            CMD_PRINT => {
+                let palette = self.packet_data.get(2).copied().unwrap_or(0xE4);
+                if !self.image_data.is_empty() {
+                    self.printed.push(render(&self.image_data, palette));
+                    self.image_data.clear();
+                }
+                self.status = (self.status & !STATUS_UNPROCESSED) | STATUS_PRINTING;
+                self.busy_polls = PRINT_BUSY_POLLS;
+            }
+            CMD_STATUS => {}
+            _ => {}
+        }
+    }
+}
+
+/// Printer RLE: a control byte with bit 7 set repeats the next byte
+/// (control & 0x7F) + 2 times; otherwise (control + 1) literal bytes follow.
+fn decompress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let control = data[i];
+        i += 1;
+        if control & 0x80 != 0 {
+            if let Some(&value) = data.get(i) {
+                out.extend(std::iter::repeat_n(value, (control & 0x7F) as usize + 2));
+            }
+            i += 1;
+        } else {
+            let end = (i + control as usize + 1).min(data.len());
+            out.extend_from_slice(&data[i..end]);
+            i = end;
+        }
+    }
+    out
+}
+
+/// Lay 2bpp tiles out 20 per row and map colors through `palette`
+/// (BGP format; 0 means the default 0xE4).
+fn render(data: &[u8], palette: u8) -> PrintedImage {
+    let palette = if palette == 0 { 0xE4 } else { palette };
+    let tile_rows = data.len().div_ceil(20 * 16);
+    let (width, height) = (160, tile_rows * 8);
+    let mut pixels = vec![0u8; width * height];
+    for (tile_idx, tile) in data.chunks(16).enumerate() {
+        let (tx, ty) = (tile_idx % 20, tile_idx / 20);
+        for (row, pair) in tile.chunks_exact(2).enumerate() {
+            for col in 0..8 {
+                let bit = 7 - col;
+                let color = ((pair[1] >> bit) & 1) << 1 | ((pair[0] >> bit) & 1);
+                let shade = (palette >> (color * 2)) & 0x03;
+                pixels[(ty * 8 + row) * width + tx * 8 + col] = shade;
+            }
+        }
+    }
+    PrintedImage { width, height, pixels }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Send a whole packet, returning the (device id, status) replies.
+    fn send_packet(printer: &mut Printer, command: u8, compressed: bool, data: &[u8]) -> (u8, u8) {
+        let len = data.len() as u16;
+        let mut bytes = vec![0x88, 0x33, command, compressed as u8, len as u8, (len >> 8) as u8];
+        bytes.extend_from_slice(data);
+        let sum = bytes[2..].iter().fold(0u16, |acc, &b| acc.wrapping_add(b as u16));
+        bytes.extend_from_slice(&[sum as u8, (sum >> 8) as u8, 0x00, 0x00]);
+
+        let replies: Vec<u8> = bytes.iter().map(|&b| printer.exchange(b)).collect();
+        assert!(replies[..replies.len() - 2].iter().all(|&r| r == 0));
+        (replies[replies.len() - 2], replies[replies.len() - 1])
+    }
+
+    #[test]
+    fn test_decompress_runs_and_literals() {
+        assert_eq!(decompress(&[0x81, 0xAA, 0x01, 0x12, 0x34]), vec![0xAA, 0xAA, 0xAA, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn test_print_assembles_band() {
+        let mut printer = Printer::new();
+        assert_eq!(send_packet(&mut printer, CMD_INIT, false, &[]), (DEVICE_ID, 0x00));
+
+        // One band: first tile solid black (color 3), the rest white
+        let mut band = vec![0u8; BAND_BYTES];
+        band[..16].fill(0xFF);
+        assert_eq!(send_packet(&mut printer, CMD_DATA, false, &band).1, STATUS_UNPROCESSED);
+        assert_eq!(send_packet(&mut printer, CMD_DATA, false, &[]).1, STATUS_UNPROCESSED);
+
+        let (_, status) = send_packet(&mut printer, CMD_PRINT, false, &[0x01, 0x13, 0xE4, 0x40]);
+        assert_eq!(status, STATUS_PRINTING);
+
+        let sheets = printer.take_printed();
+        assert_eq!(sheets.len(), 1);
+        let sheet = &sheets[0];
+        assert_eq!((sheet.width, sheet.height), (160, 16));
+        assert_eq!(sheet.pixels[0], 3);
+        assert_eq!(sheet.pixels[7 * 160 + 7], 3);
+        assert_eq!(sheet.pixels[8], 0);
+        assert_eq!(sheet.pixels[8 * 160], 0, "second tile row starts with tile 20");
+        assert!(sheet.to_png().starts_with(b"\x89PNG"));
+
+        // Busy for a few status polls, then idle
+        for _ in 0..PRINT_BUSY_POLLS - 1 {
+            assert_eq!(send_packet(&mut printer, CMD_STATUS, false, &[]).1, STATUS_PRINTING);
+        }
+        assert_eq!(send_packet(&mut printer, CMD_STATUS, false, &[]).1, 0x00);
+    }
+
+    #[test]
+    fn test_bad_checksum_sets_error() {
+        let mut printer = Printer::new();
+        for b in [0x88, 0x33, CMD_INIT, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x00] {
+            printer.exchange(b);
+        }
+        assert_eq!(printer.exchange(0x00), STATUS_CHECKSUM_ERROR);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+
+/// Snapshots kept at most: 10 seconds at 60 fps.
+pub const REWIND_MAX_ENTRIES: usize = 600;
+
+/// Ring buffer of save-state snapshots, capped by entry count and total bytes.
+/// The oldest snapshots are dropped first.
+pub struct Rewind {
+    snapshots: VecDeque<Vec<u8>>,
+    total_bytes: usize,
+    max_bytes: usize,
+}
+
+impl Rewind {
+    pub fn new(max_bytes: usize) -> Self {
+        Rewind {
+            snapshots: VecDeque::new(),
+            total_bytes: 0,
+            max_bytes,
+        }
+    }
+
+    pub fn push(&mut self, snapshot: Vec<u8>) {
+        self.total_bytes += snapshot.len();
+        self.snapshots.push_back(snapshot);
+        while self.snapshots.len() > REWIND_MAX_ENTRIES
+            || (self.total_bytes > self.max_bytes && self.snapshots.len() > 1)
+        {
+            if let Some(old) = self.snapshots.pop_front() {
+                self.total_bytes -= old.len();
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.total_bytes = 0;
+    }
+
+    /// Remove and return the most recent snapshot.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let snapshot = self.snapshots.pop_back()?;
+        self.total_bytes -= snapshot.len();
+        Some(snapshot)
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop_is_lifo() {
+        let mut rewind = Rewind::new(1024);
+        rewind.push(vec![1]);
+        rewind.push(vec![2, 2]);
+        assert_eq!(rewind.total_bytes(), 3);
+        assert_eq!(rewind.pop(), Some(vec![2, 2]));
+        assert_eq!(rewind.pop(), Some(vec![1]));
+        assert_eq!(rewind.pop(), None);
+        assert_eq!(rewind.total_bytes(), 0);
+    }
+
+    #[test]
+    fn test_caps_drop_oldest() {
+        let mut rewind = Rewind::new(250);
+        for i in 0..5u8 {
+            rewind.push(vec![i; 100]);
+        }
+        assert_eq!(rewind.len(), 2);
+        assert_eq!(rewind.total_bytes(), 200);
+        assert_eq!(rewind.pop().unwrap()[0], 4);
+        assert_eq!(rewind.pop().unwrap()[0], 3);
+
+        let mut rewind = Rewind::new(usize::MAX);
+        for _ in 0..REWIND_MAX_ENTRIES + 10 {
+            rewind.push(vec![0]);
+        }
+        assert_eq!(rewind.len(), REWIND_MAX_ENTRIES);
+    }
+}
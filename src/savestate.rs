@@ -1,10 +1,22 @@
+#[cfg(feature = "native")]
 use std::fs;
+#[cfg(feature = "native")]
 use std::path::{Path, PathBuf};
 
 use crate::gameboy::GameBoy;
 
-const MAGIC: [u8; 4] = *b"GBSS";
-const VERSION: u8 = 0x03;
+const MAGIC: [u8; 4] = *b"GBRS";
+const VERSION: u16 = 0x0001;
+/// Magic of states written before the GBRS header. Their layout predates
+/// the current payload, so they are rejected rather than misread.
+const OLD_MAGIC: [u8; 4] = *b"GBSS";
+/// Number of save-state slots (0-9).
+#[cfg(feature = "native")]
+pub const SAVE_SLOTS: u8 = 10;
+/// Magic + version + payload CRC32.
+const HEADER_LEN: usize = 10;
+/// MBC tag + RAM length at the start of the payload.
+const PAYLOAD_HEADER_LEN: usize = 5;
 
 // --- Write helpers ---
 
@@ -65,8 +77,24 @@ pub fn read_bytes<'a>(data: &'a [u8], cursor: &mut usize, len: usize) -> &'a [u8
     slice
 }
 
+// --- Checksum ---
+
+/// CRC-32 (IEEE, reflected polynomial 0xEDB88320).
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
 // --- Path helper ---
 
+#[cfg(feature = "native")]
 pub fn save_state_path(rom_path: &str, slot: u8) -> PathBuf {
     let path = Path::new(rom_path);
     let parent = path.parent().unwrap_or(Path::new("."));
@@ -80,22 +108,24 @@ pub fn save_state_path(rom_path: &str, slot: u8) -> PathBuf {
 // --- Top-level save/load ---
 
 pub fn save(gb: &GameBoy) -> Vec<u8> {
-    let mut buf = Vec::new();
+    let mut payload = Vec::new();
+    write_u8(&mut payload, gb.cpu.bus.cartridge.mbc_type_tag());
+    write_u32_le(&mut payload, gb.cpu.bus.cartridge.ram_len() as u32);
+    gb.cpu.save_state(&mut payload);
 
-    // Header
+    let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
     write_bytes(&mut buf, &MAGIC);
-    write_u8(&mut buf, VERSION);
-    write_u8(&mut buf, gb.cpu.bus.cartridge.mbc_type_tag());
-    write_u32_le(&mut buf, gb.cpu.bus.cartridge.ram_len() as u32);
-
-    // Body
-    gb.cpu.save_state(&mut buf);
-
+    write_u16_le(&mut buf, VERSION);
+    write_u32_le(&mut buf, crc32(&payload));
+    write_bytes(&mut buf, &payload);
     buf
 }
 
 pub fn load(gb: &mut GameBoy, data: &[u8]) -> Result<(), String> {
-    if data.len() < 10 {
+    if data.len() >= 4 && data[0..4] == OLD_MAGIC {
+        return Err("Save state is from an older, incompatible build".to_string());
+    }
+    if data.len() < HEADER_LEN + PAYLOAD_HEADER_LEN {
         return Err("Save state too small".to_string());
     }
 
@@ -107,29 +137,38 @@ pub fn load(gb: &mut GameBoy, data: &[u8]) -> Result<(), String> {
         return Err("Invalid save state magic".to_string());
     }
 
-    let version = read_u8(data, &mut cursor);
+    let version = read_u16_le(data, &mut cursor);
     if version != VERSION {
         return Err(format!("Unsupported save state version: {}", version));
     }
 
-    let mbc_tag = read_u8(data, &mut cursor);
+    let checksum = read_u32_le(data, &mut cursor);
+    if crc32(&data[cursor..]) != checksum {
+        return Err("Save state checksum mismatch (file is corrupt)".to_string());
+    }
+
+    load_payload(gb, data, &mut cursor)
+}
+
+fn load_payload(gb: &mut GameBoy, data: &[u8], cursor: &mut usize) -> Result<(), String> {
+    let mbc_tag = read_u8(data, cursor);
     if mbc_tag != gb.cpu.bus.cartridge.mbc_type_tag() {
         return Err("MBC type mismatch".to_string());
     }
 
-    let ram_len = read_u32_le(data, &mut cursor) as usize;
+    let ram_len = read_u32_le(data, cursor) as usize;
     if ram_len != gb.cpu.bus.cartridge.ram_len() {
         return Err("Cartridge RAM size mismatch".to_string());
     }
 
-    // Body
-    gb.cpu.load_state(data, &mut cursor);
+    gb.cpu.load_state(data, cursor);
 
     Ok(())
 }
 
 // --- File I/O wrappers ---
 
+#[cfg(feature = "native")]
 pub fn save_to_file(gb: &GameBoy, path: &Path) -> Result<(), String> {
     let data = save(gb);
     if let Some(parent) = path.parent() {
@@ -140,7 +179,72 @@ pub fn save_to_file(gb: &GameBoy, path: &Path) -> Result<(), String> {
     Ok(())
 }
 
+#[cfg(feature = "native")]
 pub fn load_from_file(gb: &mut GameBoy, path: &Path) -> Result<(), String> {
     let data = fs::read(path).map_err(|e| format!("Failed to read save state: {}", e))?;
     load(gb, &data)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::Cartridge;
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_header_layout() {
+        let gb = GameBoy::new(Cartridge::default());
+        let data = save(&gb);
+        assert_eq!(&data[0..4], b"GBRS");
+        assert_eq!(u16::from_le_bytes([data[4], data[5]]), 0x0001);
+        let crc = u32::from_le_bytes([data[6], data[7], data[8], data[9]]);
+        assert_eq!(crc, crc32(&data[HEADER_LEN..]));
+        assert_eq!(data[10], gb.cpu.bus.cartridge.mbc_type_tag());
+        let ram_len = u32::from_le_bytes([data[11], data[12], data[13], data[14]]);
+        assert_eq!(ram_len as usize, gb.cpu.bus.cartridge.ram_len());
+    }
+
+    #[test]
+    fn test_load_validates_header() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        gb.cpu.registers.a = 0x42;
+        let data = save(&gb);
+
+        gb.cpu.registers.a = 0;
+        assert!(load(&mut gb, &data).is_ok());
+        assert_eq!(gb.cpu.registers.a, 0x42);
+
+        let mut corrupt = data.clone();
+        *corrupt.last_mut().unwrap() ^= 0xFF;
+        assert!(load(&mut gb, &corrupt).unwrap_err().contains("checksum"));
+
+        let mut newer = data.clone();
+        newer[4] = 0x02;
+        assert!(load(&mut gb, &newer).unwrap_err().contains("version"));
+
+        let mut bad_magic = data;
+        bad_magic[0] = b'X';
+        assert!(load(&mut gb, &bad_magic).unwrap_err().contains("magic"));
+        assert!(load(&mut gb, &[0; 4]).is_err());
+    }
+
+    #[test]
+    fn test_load_rejects_gbss_state() {
+        let mut gb = GameBoy::new(Cartridge::default());
+        let mut old = b"GBSS".to_vec();
+        old.extend_from_slice(&[0x03, 0x00, 0, 0, 0, 0]);
+        assert!(load(&mut gb, &old).unwrap_err().contains("older"));
+    }
+
+    #[test]
+    fn test_layout_change_bumps_version() {
+        // A state's size changes with its layout: when this fails, bump
+        // VERSION and update both values together
+        let gb = GameBoy::new(Cartridge::default());
+        assert_eq!((VERSION, save(&gb).len()), (0x0001, 73127));
+    }
+}
@@ -6,6 +6,16 @@ pub struct Timer {
     pub tac: u8,
     pub internal_counter: u16,
     pub interrupt: bool,
+    /// TIMA overflowed and reads 0 until TMA is copied in one M-cycle later
+    pub tima_reload_pending: bool,
+    /// T-cycles left before the pending reload
+    pub tima_reload_cycle: u8,
+    /// TMA was just copied into TIMA. Until the next tick, TIMA writes are
+    /// ignored and TMA writes also land in TIMA.
+    pub tima_reloading: bool,
+    /// CGB double speed. DIV keeps counting CPU T-cycles, so the APU only
+    /// sees every other one and the frame sequencer follows bit 13.
+    pub double_speed: bool,
 }
 
 impl Timer {
@@ -21,39 +31,69 @@ impl Timer {
 
     pub fn write(&mut self, address: u16, byte: u8, apu: &mut Apu) {
         match address {
-            0xFF04 => {
-                // DIV reset: detect falling edge of bit 12 before clearing
-                let old_bit12 = (self.internal_counter >> 12) & 1;
-                self.internal_counter = 0;
-                // If bit 12 was high, resetting causes a falling edge
-                if old_bit12 == 1 {
-                    apu.clock_frame_sequencer();
+            0xFF04 => self.reset_div(apu),
+            0xFF05 => {
+                // Written on the reload cycle: the TMA copy wins
+                if self.tima_reloading {
+                    return;
+                }
+                // Writing during the reload delay cancels the reload and its interrupt
+                self.tima_reload_pending = false;
+                self.tima = byte;
+            }
+            0xFF06 => {
+                self.tma = byte;
+                if self.tima_reloading {
+                    self.tima = byte;
                 }
             }
-            0xFF05 => self.tima = byte,
-            0xFF06 => self.tma = byte,
             0xFF07 => self.tac = byte,
             _ => {}
         }
     }
 
+    /// Clear DIV, as done by writes to $FF04 and by STOP.
+    pub fn reset_div(&mut self, apu: &mut Apu) {
+        // Detect falling edge of the frame sequencer bit before clearing
+        let old_bit = (self.internal_counter >> self.frame_sequencer_bit()) & 1;
+        self.internal_counter = 0;
+        // If the bit was high, resetting causes a falling edge
+        if old_bit == 1 {
+            apu.clock_frame_sequencer();
+        }
+    }
+
     pub fn tick(&mut self, t_cycles: u8, apu: &mut Apu) {
         self.interrupt = false;
+        self.tima_reloading = false;
         let cycles = t_cycles as u16;
+        // APU cycles are batched between frame sequencer clocks
+        let mut apu_cycles = 0;
 
         for _ in 0..cycles {
+            if self.tima_reload_pending {
+                self.tima_reload_cycle -= 1;
+                if self.tima_reload_cycle == 0 {
+                    self.tima_reload_pending = false;
+                    self.tima = self.tma;
+                    self.tima_reloading = true;
+                    self.interrupt = true;
+                }
+            }
+
             let old_counter = self.internal_counter;
             self.internal_counter = self.internal_counter.wrapping_add(1);
 
-            // Detect falling edge of bit 12 for APU frame sequencer (512 Hz)
-            let old_bit12 = (old_counter >> 12) & 1;
-            let new_bit12 = (self.internal_counter >> 12) & 1;
-            if old_bit12 == 1 && new_bit12 == 0 {
+            // Detect falling edge of the APU frame sequencer bit (512 Hz)
+            let fs_bit = self.frame_sequencer_bit();
+            if (old_counter >> fs_bit) & 1 == 1 && (self.internal_counter >> fs_bit) & 1 == 0 {
+                apu.tick_n_cycles(apu_cycles);
+                apu_cycles = 0;
                 apu.clock_frame_sequencer();
             }
-
-            // Tick APU one T-cycle (advance channel frequency timers + samples)
-            apu.tick_one_t_cycle();
+            if !self.double_speed || self.internal_counter & 1 == 0 {
+                apu_cycles += 1;
+            }
 
             // Timer (TIMA) falling edge detection
             if self.tac & 0x04 != 0 {
@@ -70,19 +110,23 @@ impl Timer {
                 let new_bit = (self.internal_counter >> bit) & 1;
                 if old_bit == 1 && new_bit == 0 {
                     let (new_tima, overflow) = self.tima.overflowing_add(1);
+                    self.tima = new_tima;
                     if overflow {
-                        self.tima = self.tma;
-                        self.interrupt = true;
-                    } else {
-                        self.tima = new_tima;
+                        self.tima_reload_pending = true;
+                        self.tima_reload_cycle = 4;
                     }
                 }
             }
         }
+        apu.tick_n_cycles(apu_cycles);
     }
 }
 
 impl Timer {
+    fn frame_sequencer_bit(&self) -> u16 {
+        if self.double_speed { 13 } else { 12 }
+    }
+
     pub fn save_state(&self, buf: &mut Vec<u8>) {
         use crate::savestate::*;
         write_u8(buf, self.tima);
@@ -90,6 +134,9 @@ impl Timer {
         write_u8(buf, self.tac);
         write_u16_le(buf, self.internal_counter);
         write_bool(buf, self.interrupt);
+        write_bool(buf, self.tima_reload_pending);
+        write_u8(buf, self.tima_reload_cycle);
+        write_bool(buf, self.tima_reloading);
     }
 
     pub fn load_state(&mut self, data: &[u8], cursor: &mut usize) {
@@ -99,6 +146,9 @@ impl Timer {
         self.tac = read_u8(data, cursor);
         self.internal_counter = read_u16_le(data, cursor);
         self.interrupt = read_bool(data, cursor);
+        self.tima_reload_pending = read_bool(data, cursor);
+        self.tima_reload_cycle = read_u8(data, cursor);
+        self.tima_reloading = read_bool(data, cursor);
     }
 }
 
@@ -110,6 +160,137 @@ impl Default for Timer {
             tac: 0,
             internal_counter: 0,
             interrupt: false,
+            tima_reload_pending: false,
+            tima_reload_cycle: 0,
+            tima_reloading: false,
+            double_speed: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_div_is_upper_byte_of_counter() {
+        let mut timer = Timer::default();
+        let mut apu = Apu::default();
+        timer.tick(255, &mut apu);
+        assert_eq!(timer.read(0xFF04), 0x00);
+        timer.tick(1, &mut apu);
+        assert_eq!(timer.read(0xFF04), 0x01);
+    }
+
+    fn overflowing_timer() -> Timer {
+        // TAC = enabled, 262144 Hz (bit 3): one TIMA tick on the next falling edge
+        Timer { tima: 0xFF, tma: 0x42, tac: 0x05, internal_counter: 0x000F, ..Timer::default() }
+    }
+
+    #[test]
+    fn test_tima_reload_delayed_one_m_cycle() {
+        let mut timer = overflowing_timer();
+        let mut apu = Apu::default();
+        timer.tick(1, &mut apu);
+        assert_eq!(timer.tima, 0x00, "TIMA reads 0 during the reload delay");
+        assert!(timer.tima_reload_pending);
+        assert!(!timer.interrupt);
+
+        timer.tick(3, &mut apu);
+        assert_eq!(timer.tima, 0x00);
+        timer.tick(1, &mut apu);
+        assert_eq!(timer.tima, 0x42);
+        assert!(timer.interrupt);
+    }
+
+    #[test]
+    fn test_tima_write_during_reload_cancels_it() {
+        let mut timer = overflowing_timer();
+        let mut apu = Apu::default();
+        timer.tick(1, &mut apu);
+        timer.write(0xFF05, 0x10, &mut apu);
+        timer.tick(4, &mut apu);
+        assert_eq!(timer.tima, 0x10);
+        assert!(!timer.interrupt);
+        assert!(!timer.tima_reload_pending);
+    }
+
+    #[test]
+    fn test_tma_write_on_reload_cycle_reaches_tima() {
+        let mut timer = overflowing_timer();
+        timer.tma = 0xFE;
+        let mut apu = Apu::default();
+        timer.tick(4, &mut apu); // overflow; reload one M-cycle later
+        timer.tick(4, &mut apu);
+        assert_eq!(timer.tima, 0xFE);
+
+        // Same M-cycle as the reload: the new TMA is what TIMA ends up with,
+        // and a TIMA write is overridden
+        timer.write(0xFF06, 0x50, &mut apu);
+        timer.write(0xFF05, 0x99, &mut apu);
+        assert_eq!(timer.tima, 0x50);
+
+        // One M-cycle later both registers behave normally again
+        timer.tick(4, &mut apu);
+        timer.write(0xFF06, 0x60, &mut apu);
+        assert_eq!(timer.tima, 0x50);
+        timer.write(0xFF05, 0x10, &mut apu);
+        assert_eq!(timer.tima, 0x10);
+    }
+
+    #[test]
+    fn test_frame_sequencer_clocks_on_bit12_falling_edge() {
+        let mut timer = Timer::default();
+        let mut apu = Apu::default();
+        apu.write_register(0xFF26, 0x80);
+        timer.internal_counter = 0x1FFF;
+        timer.tick(1, &mut apu);
+        assert_eq!(apu.frame_step, 1);
+
+        // Rising edge does nothing
+        timer.internal_counter = 0x0FFF;
+        timer.tick(1, &mut apu);
+        assert_eq!(apu.frame_step, 1);
+    }
+
+    #[test]
+    fn test_double_speed_frame_sequencer_and_apu_rate() {
+        let mut timer = Timer { double_speed: true, ..Timer::default() };
+        let mut apu = Apu::default();
+        apu.write_register(0xFF26, 0x80);
+        timer.internal_counter = 0x1FFF;
+        timer.tick(1, &mut apu);
+        assert_eq!(apu.frame_step, 0, "bit 12 no longer clocks the sequencer");
+        timer.internal_counter = 0x3FFF;
+        timer.tick(1, &mut apu);
+        assert_eq!(apu.frame_step, 1);
+
+        // The APU advances half as fast, so it produces half the samples
+        let (mut normal_timer, mut normal) = (Timer::default(), Apu::default());
+        let mut double_timer = Timer { double_speed: true, ..Timer::default() };
+        let mut double = Apu::default();
+        for _ in 0..200 {
+            normal_timer.tick(200, &mut normal);
+            double_timer.tick(200, &mut double);
         }
+        assert!(!normal.sample_buffer.is_empty());
+        assert_eq!(normal.sample_buffer.len(), 2 * double.sample_buffer.len());
+    }
+
+    #[test]
+    fn test_div_write_resets_counter_and_clocks_on_edge() {
+        let mut timer = Timer::default();
+        let mut apu = Apu::default();
+        apu.write_register(0xFF26, 0x80);
+
+        timer.internal_counter = 0x0ABC; // bit 12 low: no edge
+        timer.write(0xFF04, 0x42, &mut apu);
+        assert_eq!(timer.internal_counter, 0);
+        assert_eq!(apu.frame_step, 0);
+
+        timer.internal_counter = 0x1234; // bit 12 high: reset is a falling edge
+        timer.write(0xFF04, 0x00, &mut apu);
+        assert_eq!(timer.internal_counter, 0);
+        assert_eq!(apu.frame_step, 1);
     }
 }
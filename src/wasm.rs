@@ -0,0 +1,92 @@
+//! Embedding wrapper for browser front-ends, built with
+//! `--no-default-features --features wasm` (no minifb, cpal or file I/O)
+//! for `wasm32-unknown-unknown`, then run through `wasm-bindgen`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::filters::PALETTE_GRAYSCALE;
+use crate::gameboy::GameBoy;
+use crate::joypad::JoypadKey;
+
+/// Key codes accepted by `key_down`/`key_up`.
+const KEYS: [JoypadKey; 8] = [
+    JoypadKey::Right, JoypadKey::Left, JoypadKey::Up, JoypadKey::Down,
+    JoypadKey::A, JoypadKey::B, JoypadKey::Select, JoypadKey::Start,
+];
+
+#[wasm_bindgen]
+pub struct GbEmulator {
+    gb: GameBoy,
+    palette: [u32; 4],
+}
+
+#[wasm_bindgen]
+impl GbEmulator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(rom: &[u8]) -> Result<GbEmulator, String> {
+        Ok(GbEmulator { gb: GameBoy::from_rom_bytes(rom)?, palette: PALETTE_GRAYSCALE })
+    }
+
+    pub fn run_frame(&mut self) {
+        self.gb.run_frame();
+    }
+
+    /// One u32 per pixel whose little-endian bytes are R, G, B, A, so a
+    /// `Uint8ClampedArray` view over the buffer can go straight into `ImageData`.
+    pub fn get_framebuffer(&self) -> Vec<u32> {
+        self.gb.frame_rgb(&self.palette).into_iter().map(|color| {
+            u32::from_le_bytes([(color >> 16) as u8, (color >> 8) as u8, color as u8, 0xFF])
+        }).collect()
+    }
+
+    /// `key`: 0 Right, 1 Left, 2 Up, 3 Down, 4 A, 5 B, 6 Select, 7 Start.
+    pub fn key_down(&mut self, key: u8) {
+        if let Some(&k) = KEYS.get(key as usize) {
+            self.gb.cpu.bus.joypad.key_down(k);
+        }
+    }
+
+    pub fn key_up(&mut self, key: u8) {
+        if let Some(&k) = KEYS.get(key as usize) {
+            self.gb.cpu.bus.joypad.key_up(k);
+        }
+    }
+
+    /// Interleaved stereo samples generated since the last call.
+    pub fn get_audio_samples(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.gb.cpu.bus.apu.sample_buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gb_emulator_frame_and_input() {
+        let mut emu = GbEmulator::new(&vec![0u8; 0x8000]).unwrap();
+        emu.run_frame();
+
+        let fb = emu.get_framebuffer();
+        assert_eq!(fb.len(), 160 * 144);
+        assert!(fb.iter().all(|px| px.to_le_bytes()[3] == 0xFF));
+        assert!(!emu.get_audio_samples().is_empty());
+        assert!(emu.get_audio_samples().is_empty(), "samples are drained");
+
+        emu.key_down(7);
+        assert_eq!(emu.gb.cpu.bus.joypad.buttons & 0x08, 0);
+        emu.key_up(7);
+        emu.key_down(200); // ignored
+        assert_eq!(emu.gb.cpu.bus.joypad.buttons, 0x0F);
+    }
+
+    #[test]
+    fn test_gb_emulator_mbc3_rtc_cart() {
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x0147] = 0x10; // MBC3+TIMER+RAM+BATTERY
+        rom[0x0149] = 0x02;
+        let mut emu = GbEmulator::new(&rom).unwrap();
+        emu.run_frame();
+        assert_eq!(emu.get_framebuffer().len(), 160 * 144);
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(feature = "native")]
+use std::fs;
+#[cfg(feature = "native")]
+use std::path::Path;
+
+/// Encode interleaved stereo `f32` samples as a 16-bit PCM WAV file.
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    const CHANNELS: u16 = 2;
+    const BITS_PER_SAMPLE: u16 = 16;
+    let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+    let data_len = (samples.len() * 2) as u32;
+
+    let mut buf = Vec::with_capacity(44 + data_len as usize);
+    buf.extend_from_slice(b"RIFF");
+    buf.extend_from_slice(&(36 + data_len).to_le_bytes());
+    buf.extend_from_slice(b"WAVE");
+
+    // fmt chunk
+    buf.extend_from_slice(b"fmt ");
+    buf.extend_from_slice(&16u32.to_le_bytes());
+    buf.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    buf.extend_from_slice(&CHANNELS.to_le_bytes());
+    buf.extend_from_slice(&sample_rate.to_le_bytes());
+    buf.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    buf.extend_from_slice(&block_align.to_le_bytes());
+    buf.extend_from_slice(&BITS_PER_SAMPLE.to_le_bytes());
+
+    // data chunk
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&data_len.to_le_bytes());
+    for &sample in samples {
+        let pcm = (sample * 32767.0).clamp(-32768.0, 32767.0) as i16;
+        buf.extend_from_slice(&pcm.to_le_bytes());
+    }
+    buf
+}
+
+#[cfg(feature = "native")]
+pub fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<(), String> {
+    fs::write(path, encode_wav(samples, sample_rate))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Linearly resample interleaved stereo samples from `from_rate` to `to_rate`.
+pub fn resample_stereo(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    let frames = samples.len() / 2;
+    if from_rate == to_rate || from_rate == 0 || frames == 0 {
+        return samples.to_vec();
+    }
+    let out_frames = (frames as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let step = from_rate as f64 / to_rate as f64;
+    let mut out = Vec::with_capacity(out_frames * 2);
+    for i in 0..out_frames {
+        let pos = i as f64 * step;
+        let idx = pos as usize;
+        let next = (idx + 1).min(frames - 1);
+        let frac = (pos - idx as f64) as f32;
+        for ch in 0..2 {
+            let (a, b) = (samples[idx * 2 + ch], samples[next * 2 + ch]);
+            out.push(a + (b - a) * frac);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_wav_header_and_samples() {
+        let wav = encode_wav(&[0.0, 1.0, -1.0, 2.0], 44100);
+        assert_eq!(wav.len(), 44 + 8);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()), 36 + 8);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u16::from_le_bytes([wav[22], wav[23]]), 2); // channels
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 44100);
+        assert_eq!(u32::from_le_bytes(wav[28..32].try_into().unwrap()), 44100 * 4);
+        assert_eq!(&wav[36..40], b"data");
+
+        let pcm: Vec<i16> = wav[44..].chunks(2).map(|c| i16::from_le_bytes([c[0], c[1]])).collect();
+        assert_eq!(pcm, vec![0, 32767, -32767, 32767]);
+    }
+
+    #[test]
+    fn test_resample_stereo_to_44100() {
+        // One second of a left-channel ramp at 48 kHz
+        let samples: Vec<f32> = (0..48000).flat_map(|i| [i as f32 / 48000.0, 0.5]).collect();
+        let out = resample_stereo(&samples, 48000, 44100);
+        assert_eq!(out.len(), 44100 * 2);
+        assert!((out[2 * 22050] - 0.5).abs() < 1e-4);
+        assert!(out.chunks(2).all(|frame| frame[1] == 0.5));
+        assert_eq!(resample_stereo(&samples, 44100, 44100).len(), samples.len());
+    }
+}
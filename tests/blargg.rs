@@ -0,0 +1,181 @@
+//! Blargg test ROMs, run headless.
+//!
+//! The ROMs are not distributed with the emulator, so every test is
+//! `#[ignore]`d. Point `BLARGG_ROM_DIR` at the root of the gb-test-roms
+//! suite (ROMs are looked up by their path inside it) and run:
+//!
+//!     BLARGG_ROM_DIR=path/to/gb-test-roms cargo test --test blargg -- --ignored
+
+use std::path::Path;
+use gb_emulator::gameboy::GameBoy;
+
+const MAX_FRAMES: u32 = 3600;
+/// $A001-$A003 once a ROM reports through cartridge RAM
+const RESULT_SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+/// $A000 while the test is still running
+const STATUS_RUNNING: u8 = 0x80;
+
+enum Outcome {
+    Passed,
+    Failed(String),
+}
+
+/// Text the ROM wrote to cartridge RAM from $A004, NUL-terminated.
+fn memory_text(gb: &GameBoy) -> String {
+    let mut text = String::new();
+    for addr in 0xA004..=0xBFFF {
+        let ch = gb.cpu.bus.cartridge.read_byte(addr);
+        if ch == 0 {
+            break;
+        }
+        text.push(ch as char);
+    }
+    text
+}
+
+fn run(rom: &[u8]) -> Result<Outcome, String> {
+    let mut gb = GameBoy::from_rom_bytes(rom)?;
+    gb.cpu.bus.apu.set_sample_rate(0);
+    let mut serial = Vec::new();
+
+    for _ in 0..MAX_FRAMES {
+        gb.run_frame();
+        gb.cpu.bus.apu.sample_buffer.clear();
+        serial.append(&mut gb.take_serial_output());
+
+        // Memory-mapped result (used by the sound and multi-ROM suites)
+        let sig = [
+            gb.cpu.bus.cartridge.read_byte(0xA001),
+            gb.cpu.bus.cartridge.read_byte(0xA002),
+            gb.cpu.bus.cartridge.read_byte(0xA003),
+        ];
+        if sig == RESULT_SIGNATURE {
+            match gb.cpu.bus.cartridge.read_byte(0xA000) {
+                STATUS_RUNNING => {}
+                0 => return Ok(Outcome::Passed),
+                code => return Ok(Outcome::Failed(format!("status ${:02X}: {}", code, memory_text(&gb)))),
+            }
+        }
+
+        // Serial result (cpu_instrs, instr_timing, mem_timing)
+        let text = String::from_utf8_lossy(&serial);
+        if text.contains("Passed") {
+            return Ok(Outcome::Passed);
+        }
+        if text.contains("Failed") {
+            return Ok(Outcome::Failed(text.trim_end().to_string()));
+        }
+    }
+    let text = String::from_utf8_lossy(&serial);
+    Ok(Outcome::Failed(format!("no result after {} frames: {}", MAX_FRAMES, text.trim_end())))
+}
+
+fn run_blargg(name: &str) {
+    let dir = std::env::var_os("BLARGG_ROM_DIR").expect("BLARGG_ROM_DIR is not set");
+    let path = Path::new(&dir).join(name);
+    let rom = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    match run(&rom) {
+        Ok(Outcome::Passed) => {}
+        Ok(Outcome::Failed(text)) => {
+            eprintln!("{}:\n{}", name, text);
+            panic!("{} failed", name);
+        }
+        Err(e) => panic!("{}: {}", name, e),
+    }
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_01_special() {
+    run_blargg("cpu_instrs/individual/01-special.gb");
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_02_interrupts() {
+    run_blargg("cpu_instrs/individual/02-interrupts.gb");
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_03_op_sp_hl() {
+    run_blargg("cpu_instrs/individual/03-op sp,hl.gb");
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_04_op_r_imm() {
+    run_blargg("cpu_instrs/individual/04-op r,imm.gb");
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_05_op_rp() {
+    run_blargg("cpu_instrs/individual/05-op rp.gb");
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_06_ld_r_r() {
+    run_blargg("cpu_instrs/individual/06-ld r,r.gb");
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_07_jr_jp_call_ret_rst() {
+    run_blargg("cpu_instrs/individual/07-jr,jp,call,ret,rst.gb");
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_08_misc_instrs() {
+    run_blargg("cpu_instrs/individual/08-misc instrs.gb");
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_09_op_r_r() {
+    run_blargg("cpu_instrs/individual/09-op r,r.gb");
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_10_bit_ops() {
+    run_blargg("cpu_instrs/individual/10-bit ops.gb");
+}
+
+#[test]
+#[ignore]
+fn cpu_instrs_11_op_a_hl() {
+    run_blargg("cpu_instrs/individual/11-op a,(hl).gb");
+}
+
+#[test]
+#[ignore]
+fn instr_timing() {
+    run_blargg("instr_timing/instr_timing.gb");
+}
+
+#[test]
+#[ignore]
+fn mem_timing_01_read_timing() {
+    run_blargg("mem_timing/individual/01-read_timing.gb");
+}
+
+#[test]
+#[ignore]
+fn mem_timing_02_write_timing() {
+    run_blargg("mem_timing/individual/02-write_timing.gb");
+}
+
+#[test]
+#[ignore]
+fn mem_timing_03_modify_timing() {
+    run_blargg("mem_timing/individual/03-modify_timing.gb");
+}
+
+#[test]
+#[ignore]
+fn dmg_sound() {
+    run_blargg("dmg_sound/dmg_sound.gb");
+}